@@ -23,13 +23,10 @@ use subsweep::prelude::ParticleId;
 use subsweep::prelude::Simulation;
 use subsweep::prelude::StartupStages;
 use subsweep::simulation_plugin::SimulationPlugin;
+use subsweep::sweep::add_sweep_plugin;
 use subsweep::sweep::initialize_sweep_test_components_system;
 use subsweep::sweep::DirectionsSpecification;
-use subsweep::sweep::SweepPlugin;
-use subsweep::units::Dimensionless;
 use subsweep::units::Length;
-use subsweep::units::PhotonRate;
-use subsweep::units::Time;
 use subsweep::units::VecLength;
 use subsweep::voronoi::Point3d;
 
@@ -39,24 +36,14 @@ fn setup_sweep_sim(num_particles: usize) -> Simulation {
     let mut sim = Simulation::default();
     let dirs = DirectionsSpecification::Num(NUM_DIRS);
     let num_timestep_levels = 3;
-    let timestep_safety_factor = Dimensionless::dimensionless(0.1);
     sim.write_output(false)
         .add_parameter_file_contents("{}".into())
         .add_plugin(DomainPlugin)
         .add_plugin(BaseCommunicationPlugin::new(1, 0))
         .add_parameters_explicitly(SimulationBoxParameters::Normal(Length::meters(1e5)))
         .add_parameters_explicitly(SweepParameters {
-            directions: dirs,
-            rotate_directions: false,
             num_timestep_levels,
-            significant_rate_threshold: PhotonRate::zero(),
-            timestep_safety_factor,
-            chemistry_timestep_safety_factor: timestep_safety_factor,
-            max_timestep: Time::seconds(1e-3),
-            check_deadlock: false,
-            periodic: false,
-            prevent_cooling: false,
-            num_tasks_to_solve_before_send_receive: 10000,
+            ..SweepParameters::test(dirs)
         })
         .add_parameters_explicitly(Cosmology::NonCosmological)
         .add_parameters_explicitly(SimulationParameters { final_time: None })
@@ -68,8 +55,8 @@ fn setup_sweep_sim(num_particles: usize) -> Simulation {
             insert_particles_system(commands, num_particles)
         })
         .add_plugin(ParallelVoronoiGridConstruction)
-        .add_plugin(SimulationPlugin)
-        .add_plugin(SweepPlugin);
+        .add_plugin(SimulationPlugin);
+    add_sweep_plugin(&mut sim);
     sim.update();
     sim
 }
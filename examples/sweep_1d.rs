@@ -17,10 +17,10 @@ use subsweep::prelude::WorldSize;
 use subsweep::source_systems::Source;
 use subsweep::source_systems::SourcePlugin;
 use subsweep::source_systems::Sources;
+use subsweep::sweep::add_sweep_plugin;
 use subsweep::sweep::grid::init_cartesian_grid_system;
 use subsweep::sweep::grid::NumCellsSpec;
 use subsweep::sweep::SweepParameters;
-use subsweep::sweep::SweepPlugin;
 use subsweep::units::Dimensionless;
 use subsweep::units::Length;
 use subsweep::units::NumberDensity;
@@ -77,8 +77,8 @@ fn setup_sweep_sim() -> Simulation {
         .add_startup_system_to_stage(
             StartupStages::InsertDerivedComponents,
             initialize_sweep_components_system,
-        )
-        .add_plugin(SweepPlugin);
+        );
+    add_sweep_plugin(&mut sim);
     sim
 }
 
@@ -234,7 +234,7 @@ impl<'a, 'w, 's> Remapper<'a, 'w, 's> {
     ) -> DataByRank<Vec<Identified<SearchRequest>>> {
         let mut outgoing: DataByRank<Vec<Identified<SearchRequest>>> =
             DataByRank::from_communicator(&self.comm1);
-        for rank in self.comm1.other_ranks() {
+        for (rank, outgoing) in outgoing.iter_mut() {
             let extent = &self.extents[rank];
             for request in chunk.iter() {
                 let local_squared_distance =
@@ -244,7 +244,7 @@ impl<'a, 'w, 's> Remapper<'a, 'w, 's> {
                     &request.data,
                     local_squared_distance,
                 ) {
-                    outgoing.get_mut(&rank).unwrap().push(request.clone());
+                    outgoing.push(request.clone());
                 }
             }
         }
@@ -87,25 +87,25 @@ fn read_sources(
         "PartType4/Coordinates",
         DatasetShape::TwoDimensional(read_vec),
     );
-    let position = reader.read_dataset(descriptor);
+    let position = reader.read_dataset_lazy(descriptor);
     let descriptor = make_descriptor::<Metallicity, _>(
         &unit_reader,
         "PartType4/GFM_Metallicity",
         DatasetShape::OneDimensional,
     );
-    let metallicity = reader.read_dataset(descriptor);
+    let metallicity = reader.read_dataset_lazy(descriptor);
     let descriptor = make_descriptor::<StellarFormationTime, _>(
         &unit_reader,
         "PartType4/GFM_StellarFormationTime",
         DatasetShape::OneDimensional,
     );
-    let formation_scale_factor = reader.read_dataset(descriptor);
+    let formation_scale_factor = reader.read_dataset_lazy(descriptor);
     let descriptor = make_descriptor::<components::Mass, _>(
         &unit_reader,
         "PartType4/Masses",
         DatasetShape::OneDimensional,
     );
-    let mass = reader.read_dataset(descriptor);
+    let mass = reader.read_dataset_lazy(descriptor);
     position
         .zip(metallicity)
         .zip(formation_scale_factor)
@@ -0,0 +1,161 @@
+use std::array;
+use std::iter::Sum;
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Div;
+use std::ops::Mul;
+use std::ops::Sub;
+
+use mpi::datatype::UserDatatype;
+use mpi::traits::Equivalence;
+use mpi::Count;
+
+use super::Photons;
+use crate::units::helpers::Float;
+use crate::units::Dimensionless;
+use crate::units::PhotonRate;
+
+/// Photon rates split into `N` frequency bins. The number of bins
+/// is fixed at compile time, so that the rates can be exchanged
+/// between ranks like a single [PhotonRate].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(transparent)]
+pub struct FrequencyBins<const N: usize>(pub [PhotonRate; N]);
+
+impl<const N: usize> FrequencyBins<N> {
+    /// Distributes the total rate over the bins according to the
+    /// fraction of photons in each bin.
+    pub fn from_spectrum(total: PhotonRate, spectrum: &[Dimensionless; N]) -> Self {
+        Self(spectrum.map(|fraction| total * fraction))
+    }
+
+    pub fn total(&self) -> PhotonRate {
+        self.0.iter().copied().sum()
+    }
+
+    fn map(self, f: impl Fn(PhotonRate) -> PhotonRate) -> Self {
+        Self(self.0.map(f))
+    }
+
+    fn zip_with(self, other: Self, f: impl Fn(PhotonRate, PhotonRate) -> PhotonRate) -> Self {
+        Self(array::from_fn(|bin| f(self.0[bin], other.0[bin])))
+    }
+}
+
+impl<const N: usize> Add for FrequencyBins<N> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        self.zip_with(rhs, |x, y| x + y)
+    }
+}
+
+impl<const N: usize> AddAssign for FrequencyBins<N> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl<const N: usize> Sub for FrequencyBins<N> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.zip_with(rhs, |x, y| x - y)
+    }
+}
+
+impl<const N: usize> Mul<Float> for FrequencyBins<N> {
+    type Output = Self;
+
+    fn mul(self, rhs: Float) -> Self {
+        self.map(|x| x * rhs)
+    }
+}
+
+impl<const N: usize> Mul<Dimensionless> for FrequencyBins<N> {
+    type Output = Self;
+
+    fn mul(self, rhs: Dimensionless) -> Self {
+        self.map(|x| x * rhs)
+    }
+}
+
+impl<const N: usize> Div<Float> for FrequencyBins<N> {
+    type Output = Self;
+
+    fn div(self, rhs: Float) -> Self {
+        self.map(|x| x / rhs)
+    }
+}
+
+impl<const N: usize> Sum for FrequencyBins<N> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, x| acc + x)
+    }
+}
+
+unsafe impl<const N: usize> Equivalence for FrequencyBins<N> {
+    type Out = UserDatatype;
+
+    fn equivalent_datatype() -> Self::Out {
+        UserDatatype::contiguous(N as Count, &PhotonRate::equivalent_datatype())
+    }
+}
+
+impl<const N: usize> Photons for FrequencyBins<N> {
+    fn zero() -> Self {
+        Self([PhotonRate::zero(); N])
+    }
+
+    fn relative_change_to(&self, other: &Self) -> Dimensionless {
+        self.total().relative_change_to(&other.total())
+    }
+
+    fn below_threshold(&self, threshold: PhotonRate) -> bool {
+        self.total().below_threshold(threshold)
+    }
+
+    fn make_positive(&mut self) {
+        for rate in self.0.iter_mut() {
+            rate.make_positive();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrequencyBins;
+    use crate::chemistry::Photons;
+    use crate::units::Dimensionless;
+    use crate::units::PhotonRate;
+
+    fn bins(rates: [f64; 3]) -> FrequencyBins<3> {
+        FrequencyBins(rates.map(PhotonRate::photons_per_second))
+    }
+
+    #[test]
+    fn arithmetic_is_per_bin() {
+        let x = bins([1.0, 2.0, 3.0]);
+        let y = bins([4.0, 5.0, 6.0]);
+        assert_eq!(x + y, bins([5.0, 7.0, 9.0]));
+        assert_eq!(y - x, bins([3.0, 3.0, 3.0]));
+        assert_eq!(x * 2.0, bins([2.0, 4.0, 6.0]));
+        assert_eq!(y / 2.0, bins([2.0, 2.5, 3.0]));
+        assert_eq!([x, y].into_iter().sum::<FrequencyBins<3>>(), x + y);
+        assert_eq!(x.total(), PhotonRate::photons_per_second(6.0));
+    }
+
+    #[test]
+    fn make_positive_clamps_each_bin() {
+        let mut x = bins([-1.0, 2.0, -3.0]);
+        x.make_positive();
+        assert_eq!(x, bins([0.0, 2.0, 0.0]));
+    }
+
+    #[test]
+    fn spectrum_distributes_total_rate() {
+        let spectrum = [0.5, 0.25, 0.25].map(Dimensionless::dimensionless);
+        let x = FrequencyBins::from_spectrum(PhotonRate::photons_per_second(4.0), &spectrum);
+        assert_eq!(x, bins([2.0, 1.0, 1.0]));
+    }
+}
@@ -1,8 +1,13 @@
+pub mod multi_frequency;
+
+use std::fmt::Debug;
 use std::ops::Div;
 
+use derive_custom::subsweep_parameters;
 use diman::Quotient;
 
 use super::Chemistry;
+use super::ChemistryNetwork;
 use super::Timescale;
 use crate::sweep::grid::Cell;
 use crate::sweep::site::Site;
@@ -37,12 +42,35 @@ const MAX_DEPTH: usize = 100;
 /// to ensure numerical stability.
 const IONIZED_HYDROGEN_FRACTION_EPSILON: f64 = 1e-10;
 
+/// Parameters of the chemistry.
+#[subsweep_parameters("chemistry")]
+pub struct ChemistryParameters {
+    /// The chemistry network to use.
+    #[serde(default)]
+    pub network: ChemistryNetwork,
+    /// A constant recombination coefficient. If not given, the
+    /// temperature-dependent case B recombination coefficient is used.
+    #[serde(default)]
+    pub recombination_coefficient: Option<VolumeRate>,
+    /// Accounts for unresolved density fluctuations within a cell,
+    /// which enhance the recombination rate (and the recombination
+    /// cooling) by this factor.
+    #[serde(default = "default_clumping_factor")]
+    pub clumping_factor: Dimensionless,
+}
+
+fn default_clumping_factor() -> Dimensionless {
+    Dimensionless::dimensionless(1.0)
+}
+
 #[derive(Debug)]
 pub struct HydrogenOnly {
     pub rate_threshold: PhotonRate,
     pub scale_factor: Dimensionless,
     pub timestep_safety_factor: Dimensionless,
     pub prevent_cooling: bool,
+    pub recombination_coefficient: Option<VolumeRate>,
+    pub clumping_factor: Dimensionless,
 }
 
 #[derive(Debug)]
@@ -95,25 +123,45 @@ impl Chemistry for HydrogenOnly {
         volume: Volume,
         length: Length,
     ) -> Timescale {
-        let floor = Some((
-            site.species.temperature,
-            site.species.ionized_hydrogen_fraction,
-        ))
-        .filter(|_| self.prevent_cooling);
+        self.solve(
+            &mut site.species,
+            site.density,
+            rate,
+            timestep,
+            volume,
+            length,
+        )
+    }
+}
+
+impl HydrogenOnly {
+    fn solve<P: Absorption>(
+        &self,
+        species: &mut HydrogenOnlySpecies,
+        density: Density,
+        rate: P,
+        timestep: Time,
+        volume: Volume,
+        length: Length,
+    ) -> Timescale {
+        let floor = Some((species.temperature, species.ionized_hydrogen_fraction))
+            .filter(|_| self.prevent_cooling);
         let mut solver = Solver {
-            ionized_hydrogen_fraction: site.species.ionized_hydrogen_fraction,
-            temperature: site.species.temperature,
-            density: site.density,
+            ionized_hydrogen_fraction: species.ionized_hydrogen_fraction,
+            temperature: species.temperature,
+            density,
             volume,
             length,
             rate,
             scale_factor: self.scale_factor,
             floor,
+            recombination_coefficient: self.recombination_coefficient,
+            clumping_factor: self.clumping_factor,
         };
         let timestep_used = solver.perform_timestep(timestep, self.timestep_safety_factor);
-        site.species.temperature = solver.temperature;
-        site.species.ionized_hydrogen_fraction = solver.ionized_hydrogen_fraction;
-        site.species.timestep = timestep_used.time;
+        species.temperature = solver.temperature;
+        species.ionized_hydrogen_fraction = solver.ionized_hydrogen_fraction;
+        species.timestep = timestep_used.time;
         // Timescale of change
         timestep_used
     }
@@ -122,20 +170,48 @@ impl Chemistry for HydrogenOnly {
 struct TimestepCriterionViolated;
 struct TimestepConvergenceFailed;
 
+/// The incoming photons of a cell, as seen by the [Solver].
+pub(crate) trait Absorption: Debug {
+    /// The number of photons absorbed by the neutral hydrogen in a
+    /// cell of the given length within the timestep.
+    fn num_absorbed_photons(
+        &self,
+        neutral_hydrogen_number_density: NumberDensity,
+        length: Length,
+        timestep: Time,
+    ) -> Dimensionless;
+}
+
+impl Absorption for PhotonRate {
+    fn num_absorbed_photons(
+        &self,
+        neutral_hydrogen_number_density: NumberDensity,
+        length: Length,
+        timestep: Time,
+    ) -> Dimensionless {
+        let sigma = NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION;
+        let absorbed_fraction = 1.0 - (-neutral_hydrogen_number_density * sigma * length).exp();
+        let num_photons: Dimensionless = timestep * *self;
+        num_photons * absorbed_fraction
+    }
+}
+
 #[derive(Debug)]
-pub(crate) struct Solver {
+pub(crate) struct Solver<P: Absorption = PhotonRate> {
     pub ionized_hydrogen_fraction: Dimensionless,
     pub temperature: Temperature,
     pub density: Density,
     pub volume: Volume,
     pub length: Length,
-    pub rate: PhotonRate,
+    pub rate: P,
     pub scale_factor: Dimensionless,
     pub floor: Option<(Temperature, Dimensionless)>,
+    pub recombination_coefficient: Option<VolumeRate>,
+    pub clumping_factor: Dimensionless,
 }
 
 // All numbers taken from Rosdahl et al (2015)
-impl Solver {
+impl<P: Absorption> Solver<P> {
     fn hydrogen_number_density(&self) -> NumberDensity {
         self.density / PROTON_MASS
     }
@@ -193,6 +269,21 @@ impl Solver {
         VolumeRate::centimeters_cubed_per_s(2.753e-14 * d) * dlambda_dt
     }
 
+    /// The recombination rate entering the ionization balance.
+    pub fn recombination_rate(&self) -> VolumeRate {
+        let coefficient = self
+            .recombination_coefficient
+            .unwrap_or_else(|| self.case_b_recombination_rate());
+        coefficient * self.clumping_factor
+    }
+
+    fn recombination_rate_derivative(&self) -> Quotient<VolumeRate, Temperature> {
+        match self.recombination_coefficient {
+            Some(_) => Quotient::<VolumeRate, Temperature>::zero(),
+            None => self.case_b_recombination_rate_derivative() * self.clumping_factor,
+        }
+    }
+
     fn case_b_recombination_cooling_rate(&self) -> HeatingTerm {
         let lambda = Temperature::kelvins(315614.0) / self.temperature;
         HeatingTerm::ergs_centimeters_cubed_per_s(
@@ -280,7 +371,8 @@ impl Solver {
             + self.collisional_ionization_cooling_rate())
             * ne
             * nh_neutral;
-        let recombination = self.case_b_recombination_cooling_rate() * ne * nh_ionized;
+        let recombination =
+            self.case_b_recombination_cooling_rate() * self.clumping_factor * ne * nh_ionized;
         let bremsstrahlung = self.bremsstrahlung_cooling_rate() * ne * nh_ionized;
         let compton: HeatingRate = self.compton_cooling_rate() * ne;
         collisional + recombination + bremsstrahlung + compton
@@ -294,7 +386,10 @@ impl Solver {
             + self.collisional_ionization_cooling_rate_derivative())
             * ne
             * nh_neutral;
-        let recombination = self.case_b_recombination_cooling_rate_derivative() * ne * nh_ionized;
+        let recombination = self.case_b_recombination_cooling_rate_derivative()
+            * self.clumping_factor
+            * ne
+            * nh_ionized;
         let bremsstrahlung = self.bremsstrahlung_cooling_rate_derivative() * ne * nh_ionized;
         let compton: Quotient<HeatingRate, Temperature> =
             self.compton_cooling_rate_derivative() * ne;
@@ -310,12 +405,11 @@ impl Solver {
     }
 
     fn num_newly_ionized_hydrogen_atoms(&self, timestep: Time) -> Dimensionless {
-        let neutral_hydrogen_number_density = self.neutral_hydrogen_number_density();
-        let sigma = NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION;
-        let absorbed_fraction =
-            1.0 - (-neutral_hydrogen_number_density * sigma * self.length).exp();
-        let num_photons: Dimensionless = timestep * self.rate;
-        num_photons * absorbed_fraction
+        self.rate.num_absorbed_photons(
+            self.neutral_hydrogen_number_density(),
+            self.length,
+            timestep,
+        )
     }
 
     pub fn photoheating_rate(&self, timestep: Time) -> HeatingRate {
@@ -335,8 +429,8 @@ impl Solver {
         // See A23 of Rosdahl et al
         let nh = self.hydrogen_number_density();
         let ne = self.electron_number_density();
-        let alpha = self.case_b_recombination_rate();
-        let dalpha = self.case_b_recombination_rate_derivative();
+        let alpha = self.recombination_rate();
+        let dalpha = self.recombination_rate_derivative();
         let beta = self.collisional_ionization_rate();
         let dbeta = self.collisional_ionization_rate_derivative();
         let photoionization_rate = self.photoionization_rate(timestep);
@@ -520,6 +614,8 @@ mod tests {
                 rate: Rate::zero(),
                 scale_factor: Dimensionless::dimensionless(1.0),
                 floor: None,
+                recombination_coefficient: None,
+                clumping_factor: Dimensionless::dimensionless(1.0),
             };
             let analytical = derivative(&solver);
             let v1 = function(&solver);
@@ -658,6 +754,8 @@ mod tests {
                 rate,
                 scale_factor: Dimensionless::dimensionless(1.0),
                 floor: None,
+                recombination_coefficient: None,
+                clumping_factor: Dimensionless::dimensionless(1.0),
             }
         }
 
@@ -960,6 +1058,8 @@ mod tests {
             rate: PhotonRate::photons_per_second(466103097665666700000000000000000000000000000.0),
             scale_factor: 8.35028211377591.into(),
             floor: None,
+            recombination_coefficient: None,
+            clumping_factor: 1.0.into(),
         };
         s.perform_timestep(Time::megayears(1.0), 0.1.into());
     }
@@ -979,7 +1079,45 @@ mod tests {
             rate: PhotonRate::photons_per_second(466103097665666700000000000000000000000000000.0),
             scale_factor: 8.35028211377591.into(),
             floor: None,
+            recombination_coefficient: None,
+            clumping_factor: 1.0.into(),
         };
         s.perform_timestep(Time::megayears(1.0), 0.1.into());
     }
+
+    fn equilibrium_solver(clumping_factor: f64) -> Solver {
+        let length = Length::parsec(1.0);
+        let volume = length.cubed();
+        let flux = PhotonFlux::photons_per_s_per_cm_squared(1e5);
+        let temperature = Temperature::kelvins(1e4);
+        let mut solver = Solver {
+            ionized_hydrogen_fraction: 0.5.into(),
+            temperature,
+            density: as_density(1.0),
+            volume,
+            length,
+            rate: flux * (volume / length),
+            scale_factor: 1.0.into(),
+            floor: None,
+            recombination_coefficient: None,
+            clumping_factor: clumping_factor.into(),
+        };
+        // Keep the temperature fixed, so that only the ionization
+        // balance is evolved.
+        for _ in 0..1000 {
+            solver.temperature = temperature;
+            solver.perform_timestep(Time::years(1e4), 0.1.into());
+        }
+        solver
+    }
+
+    #[test]
+    fn clumping_increases_equilibrium_neutral_fraction() {
+        let uniform = equilibrium_solver(1.0);
+        let clumped = equilibrium_solver(10.0);
+        assert!(clumped.recombination_rate() > uniform.recombination_rate());
+        assert!(
+            clumped.neutral_hydrogen_number_density() > uniform.neutral_hydrogen_number_density()
+        );
+    }
 }
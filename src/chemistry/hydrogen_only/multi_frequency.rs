@@ -0,0 +1,149 @@
+use std::array;
+
+use derive_custom::subsweep_parameters;
+
+use super::Absorption;
+use super::HydrogenOnly;
+use super::HydrogenOnlySpecies;
+use super::Timescale;
+use crate::chemistry::frequency_bins::FrequencyBins;
+use crate::chemistry::Chemistry;
+use crate::chemistry::Photons;
+use crate::sweep::grid::Cell;
+use crate::sweep::site::Site;
+use crate::units::CrossSection;
+use crate::units::Dimensionless;
+use crate::units::Length;
+use crate::units::NumberDensity;
+use crate::units::PhotonRate;
+use crate::units::Time;
+use crate::units::Volume;
+use crate::units::PROTON_MASS;
+
+/// Parameters of the [HydrogenOnlyMultiFrequency] network. Both
+/// lists need to have one entry per frequency bin.
+#[subsweep_parameters("multi_frequency")]
+pub struct MultiFrequencyParameters {
+    /// The cross section of neutral hydrogen in each bin.
+    pub cross_sections: Vec<CrossSection>,
+    /// The fraction of the photons of every source which is
+    /// emitted in each bin.
+    pub spectrum: Vec<Dimensionless>,
+}
+
+impl MultiFrequencyParameters {
+    fn per_bin<T: Copy, const N: usize>(values: &[T], name: &str) -> [T; N] {
+        values.try_into().unwrap_or_else(|_| {
+            panic!(
+                "Invalid multi_frequency parameter {name}: Expected {N} entries (one per frequency bin), found {}",
+                values.len()
+            )
+        })
+    }
+
+    pub fn cross_sections<const N: usize>(&self) -> [CrossSection; N] {
+        Self::per_bin(&self.cross_sections, "cross_sections")
+    }
+
+    pub fn spectrum<const N: usize>(&self) -> [Dimensionless; N] {
+        Self::per_bin(&self.spectrum, "spectrum")
+    }
+}
+
+/// The hydrogen only network with the photons split into `N`
+/// frequency bins. Every bin is absorbed with its own cross
+/// section and the photoionization is the sum over all bins.
+#[derive(Debug)]
+pub struct HydrogenOnlyMultiFrequency<const N: usize> {
+    pub hydrogen_only: HydrogenOnly,
+    pub cross_sections: [CrossSection; N],
+}
+
+impl<const N: usize> Chemistry for HydrogenOnlyMultiFrequency<N> {
+    type Photons = FrequencyBins<N>;
+    type Species = HydrogenOnlySpecies;
+
+    fn get_outgoing_rate(
+        &self,
+        cell: &Cell,
+        site: &Site<Self>,
+        incoming_rate: Self::Photons,
+    ) -> Self::Photons {
+        let neutral_hydrogen_number_density =
+            site.density / PROTON_MASS * (1.0 - site.species.ionized_hydrogen_fraction);
+        if incoming_rate.total() < self.hydrogen_only.rate_threshold {
+            FrequencyBins::zero()
+        } else {
+            FrequencyBins(array::from_fn(|bin| {
+                let sigma = self.cross_sections[bin];
+                let non_absorbed_fraction =
+                    (-neutral_hydrogen_number_density * sigma * cell.size).exp();
+                incoming_rate.0[bin] * non_absorbed_fraction
+            }))
+        }
+    }
+
+    fn update_abundances(
+        &self,
+        site: &mut Site<Self>,
+        rate: Self::Photons,
+        timestep: Time,
+        volume: Volume,
+        length: Length,
+    ) -> Timescale {
+        let rate: [(PhotonRate, CrossSection); N] =
+            array::from_fn(|bin| (rate.0[bin], self.cross_sections[bin]));
+        self.hydrogen_only.solve(
+            &mut site.species,
+            site.density,
+            rate,
+            timestep,
+            volume,
+            length,
+        )
+    }
+}
+
+impl<const N: usize> Absorption for [(PhotonRate, CrossSection); N] {
+    fn num_absorbed_photons(
+        &self,
+        neutral_hydrogen_number_density: NumberDensity,
+        length: Length,
+        timestep: Time,
+    ) -> Dimensionless {
+        self.iter()
+            .map(|(rate, sigma)| {
+                let absorbed_fraction =
+                    1.0 - (-neutral_hydrogen_number_density * *sigma * length).exp();
+                let num_photons: Dimensionless = timestep * *rate;
+                num_photons * absorbed_fraction
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chemistry::hydrogen_only::Absorption;
+    use crate::units::Length;
+    use crate::units::NumberDensity;
+    use crate::units::PhotonRate;
+    use crate::units::Time;
+    use crate::units::NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION;
+
+    #[test]
+    fn bins_are_absorbed_with_their_cross_section() {
+        let density = NumberDensity::per_centimeters_cubed(1e-3);
+        let length = Length::kiloparsec(1.0);
+        let timestep = Time::megayears(1.0);
+        let rate = PhotonRate::photons_per_second(1e50);
+        let sigma = NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION;
+        let single = rate.num_absorbed_photons(density, length, timestep);
+        let split = [(rate * 0.5, sigma), (rate * 0.5, sigma)]
+            .num_absorbed_photons(density, length, timestep);
+        assert!(((single - split) / single).abs().value() < 1e-10);
+        let harder = [(rate * 0.5, sigma), (rate * 0.5, sigma * 0.1)]
+            .num_absorbed_photons(density, length, timestep);
+        assert!(harder < single);
+    }
+}
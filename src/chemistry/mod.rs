@@ -1,3 +1,4 @@
+pub mod frequency_bins;
 pub mod hydrogen_only;
 pub mod timescale;
 
@@ -9,9 +10,14 @@ use std::ops::Div;
 use std::ops::Mul;
 use std::ops::Sub;
 
+use bevy_ecs::system::SystemParam;
+use bevy_ecs::system::SystemParamItem;
+use derive_custom::subsweep_parameters;
 use mpi::traits::Equivalence;
 
 use self::timescale::Timescale;
+use crate::prelude::ParticleId;
+use crate::simulation::Simulation;
 use crate::sweep::grid::Cell;
 use crate::sweep::site::Site;
 use crate::units::helpers::Float;
@@ -21,6 +27,19 @@ use crate::units::PhotonRate;
 use crate::units::Time;
 use crate::units::Volume;
 
+/// The chemistry networks which can be selected via the `network`
+/// entry of the chemistry parameters.
+#[derive(Default, Copy)]
+#[subsweep_parameters]
+pub enum ChemistryNetwork {
+    #[default]
+    HydrogenOnly,
+    /// The hydrogen only network with the photons split into
+    /// frequency bins. The bins are given by the `multi_frequency`
+    /// parameters.
+    HydrogenOnlyMultiFrequency,
+}
+
 pub trait Chemistry: Sized + 'static {
     type Photons: Photons;
     type Species: Debug;
@@ -42,6 +61,45 @@ pub trait Chemistry: Sized + 'static {
     ) -> Timescale;
 }
 
+/// Connects a [Chemistry] to the components of the particles, so
+/// that it can be solved by the [SweepPlugin](crate::sweep::SweepPlugin).
+pub trait SweepChemistry: Chemistry {
+    /// The system parameters from which the chemistry and the
+    /// initial sites are constructed.
+    type InitParam: SystemParam + 'static;
+
+    /// The system parameters which are updated from the sites after
+    /// every sweep.
+    type UpdateParam: SystemParam + 'static;
+
+    /// Registers the components, parameters and output which the
+    /// chemistry needs.
+    fn build(sim: &mut Simulation);
+
+    /// Whether the species of the particles are written to the
+    /// snapshots, which is required for them to be checkpoints.
+    fn species_are_written(sim: &Simulation) -> bool;
+
+    /// Constructs the chemistry once the initial conditions have
+    /// been read.
+    fn new(param: &SystemParamItem<'_, '_, Self::InitParam>) -> Self;
+
+    /// The initial species and the source of every local particle.
+    fn get_initial_sites(
+        param: &SystemParamItem<'_, '_, Self::InitParam>,
+    ) -> Vec<(ParticleId, Self::Species, Self::Photons)>;
+
+    /// Writes the state of the sites back to the components of the
+    /// local particles after every sweep. `time` is the simulation
+    /// time after the sweep.
+    fn update_components<'a>(
+        param: &mut SystemParamItem<'_, '_, Self::UpdateParam>,
+        time: Time,
+        get_site: impl Fn(ParticleId) -> &'a Site<Self>,
+    ) where
+        Self: 'a;
+}
+
 pub trait Photons:
     Sum<Self>
     + Add<Self, Output = Self>
@@ -50,7 +108,6 @@ pub trait Photons:
     + Mul<Float, Output = Self>
     + Mul<Dimensionless, Output = Self>
     + Div<Float, Output = Self>
-    + PartialOrd<Self>
     + Debug
     + Clone
     + Equivalence
@@ -58,11 +115,7 @@ pub trait Photons:
     fn zero() -> Self;
     fn relative_change_to(&self, other: &Self) -> Dimensionless;
     fn below_threshold(&self, threshold: PhotonRate) -> bool;
-    fn make_positive(&mut self) {
-        if *self < Self::zero() {
-            *self = Self::zero();
-        }
-    }
+    fn make_positive(&mut self);
 }
 
 impl Photons for PhotonRate {
@@ -79,4 +132,32 @@ impl Photons for PhotonRate {
     fn below_threshold(&self, threshold: PhotonRate) -> bool {
         self.abs() < threshold.abs()
     }
+
+    fn make_positive(&mut self) {
+        if *self < Self::zero() {
+            *self = Self::zero();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hydrogen_only::ChemistryParameters;
+    use super::ChemistryNetwork;
+
+    #[test]
+    fn network_is_selected_by_name() {
+        let parameters: ChemistryParameters =
+            serde_yaml::from_str("network: hydrogen_only").unwrap();
+        assert!(matches!(parameters.network, ChemistryNetwork::HydrogenOnly));
+        let parameters: ChemistryParameters = serde_yaml::from_str("{}").unwrap();
+        assert!(matches!(parameters.network, ChemistryNetwork::HydrogenOnly));
+        let parameters: ChemistryParameters =
+            serde_yaml::from_str("network: hydrogen_only_multi_frequency").unwrap();
+        assert!(matches!(
+            parameters.network,
+            ChemistryNetwork::HydrogenOnlyMultiFrequency
+        ));
+        assert!(serde_yaml::from_str::<ChemistryParameters>("network: carbon").is_err());
+    }
 }
@@ -3,6 +3,8 @@ use std::fmt;
 use std::path::PathBuf;
 use std::str::FromStr;
 
+use clap::CommandFactory;
+use clap::ErrorKind;
 use clap::Parser;
 
 use crate::parameter_plugin::parameter_file_contents::Override;
@@ -33,7 +35,10 @@ impl FromStr for Override {
                 "Expected key and value separated by `:`, found `{s}`",
             )));
         }
-        let mut keys: Vec<String> = split[0].split('/').map(|x| x.to_owned()).collect();
+        let mut keys: Vec<String> = vec![];
+        for key in split[0].split('/') {
+            keys.extend(split_list_indices(key)?);
+        }
         let section = keys.remove(0);
         let value = serde_yaml::from_str(split[1]).unwrap_or_else(|e| panic!("Failed to parse parameter value in command line argument. keys: {:?} value: {}\n{}", &keys, &split[1], e));
         Ok(Override {
@@ -44,13 +49,171 @@ impl FromStr for Override {
     }
 }
 
+/// Splits a key such as `paths[0]` into the key and the list
+/// indices: `["paths", "0"]`.
+fn split_list_indices(key: &str) -> Result<Vec<String>, ParseParameterOverrideError> {
+    let mut parts = key.split('[');
+    let mut keys = vec![parts.next().unwrap().to_owned()];
+    for part in parts {
+        match part.strip_suffix(']') {
+            Some(index) if index.parse::<usize>().is_ok() => keys.push(index.to_owned()),
+            _ => {
+                return Err(ParseParameterOverrideError(format!(
+                    "Invalid list index in key `{key}`",
+                )))
+            }
+        }
+    }
+    Ok(keys)
+}
+
 #[derive(Parser, Debug, Clone)]
 #[clap(author, version, about, long_about = None)]
 pub struct CommandLineOptions {
-    pub parameter_file_path: PathBuf,
+    /// The parameter file. Cannot be given together with `--bundle`.
+    pub parameter_file_path: Option<PathBuf>,
     pub parameter_overrides: Vec<Override>,
     #[clap(short, parse(from_occurrences))]
     pub verbosity: usize,
     #[clap(long)]
     pub num_worker_threads: Option<usize>,
+    /// Read the parameters and initial conditions from a bundle
+    /// instead of a parameter file.
+    #[clap(long)]
+    pub bundle: Option<PathBuf>,
+    /// Continue a previous run from the checkpoint given as initial
+    /// conditions.
+    #[clap(long)]
+    pub restart: bool,
+    /// Read the parameters and initial conditions and construct the
+    /// grid, then exit without integrating.
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Stop after this many timesteps, even if the final time has
+    /// not been reached.
+    #[clap(long)]
+    pub max_steps: Option<usize>,
+    /// Write the output to this directory. Overrides
+    /// `output/output_dir` in the parameter file.
+    #[clap(long)]
+    pub output_dir: Option<PathBuf>,
+    /// The time between two snapshots, for example "10 Myr".
+    /// Overrides `output/time_between_snapshots` in the parameter
+    /// file.
+    #[clap(long)]
+    pub output_every: Option<String>,
+}
+
+impl CommandLineOptions {
+    /// Parses the command line options and exits with an error
+    /// message if they are inconsistent.
+    pub fn parse_and_validate() -> Self {
+        let mut opts = Self::parse();
+        if let Err(message) = opts.resolve_bundle_positionals() {
+            Self::command()
+                .error(ErrorKind::ArgumentConflict, message)
+                .exit();
+        }
+        opts
+    }
+
+    /// The bundle contains the parameters, so with `--bundle`, all
+    /// positional arguments are overrides. Since clap assigns the
+    /// first positional argument to the parameter file path, it is
+    /// moved to the front of the overrides here.
+    fn resolve_bundle_positionals(&mut self) -> Result<(), String> {
+        if self.bundle.is_none() {
+            return Ok(());
+        }
+        if let Some(path) = self.parameter_file_path.take() {
+            let path = path.to_string_lossy().into_owned();
+            match Override::from_str(&path) {
+                Ok(o) => self.parameter_overrides.insert(0, o),
+                Err(_) => {
+                    return Err(format!(
+                        "Cannot use parameter file `{path}` together with --bundle"
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The explicitly given parameter overrides, followed by the
+    /// overrides implied by the dedicated output flags.
+    pub fn all_parameter_overrides(&self) -> Vec<Override> {
+        let output_override = |key: &str, value: String| Override {
+            section: "output".into(),
+            keys: vec![key.into()],
+            value: value.into(),
+        };
+        let mut overrides = self.parameter_overrides.clone();
+        if let Some(ref output_dir) = self.output_dir {
+            overrides.push(output_override(
+                "output_dir",
+                output_dir.to_string_lossy().into_owned(),
+            ));
+        }
+        if let Some(ref output_every) = self.output_every {
+            overrides.push(output_override(
+                "time_between_snapshots",
+                output_every.clone(),
+            ));
+        }
+        overrides
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use clap::Parser;
+
+    use super::CommandLineOptions;
+    use crate::parameter_plugin::parameter_file_contents::Override;
+
+    #[test]
+    fn parse_override_with_list_indices() {
+        let o = Override::from_str("input/paths[1]/nested[0][2]:5").unwrap();
+        assert_eq!(o.section, "input");
+        assert_eq!(o.keys, vec!["paths", "1", "nested", "0", "2"]);
+        assert_eq!(o.value.as_u64(), Some(5));
+        assert!(Override::from_str("input/paths[a]:5").is_err());
+        assert!(Override::from_str("input/paths[1:5").is_err());
+    }
+
+    fn parse(args: &[&str]) -> Result<CommandLineOptions, String> {
+        let mut opts = CommandLineOptions::try_parse_from(
+            std::iter::once("subsweep").chain(args.iter().copied()),
+        )
+        .unwrap();
+        opts.resolve_bundle_positionals().map(|_| opts)
+    }
+
+    #[test]
+    fn overrides_are_not_swallowed_with_bundle() {
+        let opts = parse(&["--bundle", "bundle.hdf5", "x/a:1", "y/b:2"]).unwrap();
+        assert!(opts.parameter_file_path.is_none());
+        let sections: Vec<_> = opts
+            .parameter_overrides
+            .iter()
+            .map(|o| o.section.as_str())
+            .collect();
+        assert_eq!(sections, ["x", "y"]);
+        let opts = parse(&["params.yml", "x/a:1"]).unwrap();
+        assert_eq!(
+            opts.parameter_file_path.unwrap().to_str(),
+            Some("params.yml")
+        );
+        assert_eq!(opts.parameter_overrides.len(), 1);
+    }
+
+    #[test]
+    fn parameter_file_conflicts_with_bundle() {
+        let err = parse(&["--bundle", "bundle.hdf5", "params.yml"])
+            .err()
+            .unwrap();
+        assert!(err.contains("Cannot use parameter file `params.yml` together with --bundle"));
+    }
 }
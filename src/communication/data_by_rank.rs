@@ -45,6 +45,18 @@ impl<T> DataByRank<Vec<T>> {
             .filter_map(|t| t.as_ref().map(|x| x.len()))
             .sum()
     }
+
+    /// Removes the entries of all ranks without any data.
+    /// Exchanging the remaining data via
+    /// [ExchangeCommunicator::exchange_all](super::ExchangeCommunicator::exchange_all)
+    /// still sends empty messages to the removed ranks.
+    pub fn retain_nonempty(&mut self) {
+        for entry in self.0.iter_mut() {
+            if entry.as_ref().map(|data| data.is_empty()).unwrap_or(false) {
+                *entry = None;
+            }
+        }
+    }
 }
 
 impl<T> DataByRank<T> {
@@ -136,6 +148,14 @@ impl<T> DataByRank<T> {
         }
     }
 
+    /// The ranks which have an entry. For data constructed via
+    /// [Self::from_communicator], these are all ranks except the
+    /// own one. The ranks of a communicator are given by
+    /// [SizedCommunicator::other_ranks] instead.
+    pub fn ranks(&self) -> Vec<Rank> {
+        self.iter().map(|(rank, _)| rank).collect()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (Rank, &T)> + '_ {
         self.0
             .iter()
@@ -224,4 +244,16 @@ mod tests {
         assert_eq!(iter.next(), Some((3, 30.0)));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn retain_nonempty() {
+        let mut x: DataByRank<Vec<f64>> = DataByRank::from_size_and_rank(4, 1);
+        assert_eq!(x.ranks(), vec![0, 2, 3]);
+        x[2].push(1.0);
+        x[3].push(2.0);
+        x[3].push(3.0);
+        x.retain_nonempty();
+        assert_eq!(x.ranks(), vec![2, 3]);
+        assert_eq!(x.size(), 3);
+    }
 }
@@ -81,6 +81,52 @@ where
         })
     }
 
+    /// Like [Self::exchange_all], but instead of waiting for all
+    /// data to arrive in the order of the ranks, `process` is called
+    /// on the data from each rank as soon as it has been received.
+    /// This allows overlapping the processing of the received data
+    /// with the remaining transfers.
+    pub fn exchange_all_overlapped<U: AsRef<[T]>>(
+        &mut self,
+        data: DataByRank<U>,
+        mut process: impl FnMut(Rank, Vec<T>),
+    ) {
+        scope(|scope| {
+            let mut guards = vec![];
+            for (rank, items) in data.iter() {
+                debug_assert!(!self.pending_data[rank]);
+                self.pending_data[rank] = true;
+                let guard =
+                    self.communicator
+                        .immediate_send_vec_wait_guard(scope, rank, items.as_ref());
+                guards.extend(guard.into_iter());
+            }
+            self.empty_send_to_others();
+            let mut remaining = self.communicator.other_ranks();
+            while !remaining.is_empty() {
+                let mut received_any = false;
+                remaining.retain(|rank| match self.communicator.try_receive_vec(*rank) {
+                    Some(received) => {
+                        self.pending_data[*rank] = false;
+                        process(*rank, received);
+                        received_any = true;
+                        false
+                    }
+                    None => true,
+                });
+                // Nothing has arrived yet, so block on one of the
+                // remaining ranks instead of polling again.
+                if !received_any {
+                    if let Some(rank) = remaining.pop() {
+                        let received = self.communicator.receive_vec(rank);
+                        self.pending_data[rank] = false;
+                        process(rank, received);
+                    }
+                }
+            }
+        })
+    }
+
     pub fn exchange_same_for_all(&mut self, data: &[T]) -> DataByRank<Vec<T>> {
         self.exchange_all(
             self.other_ranks()
@@ -123,3 +169,47 @@ fn global_num_chunks(num_elements: usize, chunk_size: usize) -> usize {
 fn div_ceil(x: usize, y: usize) -> usize {
     (x / y) + if x.rem_euclid(y) > 0 { 1 } else { 0 }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ExchangeCommunicator;
+    use crate::communication::DataByRank;
+    use crate::communication::Rank;
+    use crate::communication::SizedCommunicator;
+    use crate::test_utils::run_on_rank_counts;
+
+    fn data_for(from: Rank, to: Rank) -> Vec<i32> {
+        (0..(from * 10 + to)).collect()
+    }
+
+    #[test]
+    fn exchange_all_overlapped_receives_same_data_as_exchange_all() {
+        if run_on_rank_counts(
+            concat!(
+                module_path!(),
+                "::exchange_all_overlapped_receives_same_data_as_exchange_all"
+            ),
+            1..=4,
+        ) {
+            return;
+        }
+        let mut comm = ExchangeCommunicator::<i32>::new();
+        let rank = comm.rank();
+        let other_ranks = comm.other_ranks();
+        let data = || -> DataByRank<Vec<i32>> {
+            other_ranks
+                .iter()
+                .map(|other| (*other, data_for(rank, *other)))
+                .collect()
+        };
+        for _ in 0..10 {
+            let expected = comm.exchange_all(data());
+            let mut received = DataByRank::from_communicator(&comm);
+            comm.exchange_all_overlapped(data(), |other, data| received.insert(other, data));
+            for &other in other_ranks.iter() {
+                assert_eq!(received[other], expected[other]);
+                assert_eq!(received[other], data_for(other, rank));
+            }
+        }
+    }
+}
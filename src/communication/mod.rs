@@ -6,6 +6,7 @@ mod data_by_rank;
 pub mod exchange_communicator; // public because i (currently) cannot test mpi stuff from within this module, but require an externally run example for it
 mod identified;
 mod plugin;
+mod reproducible_sum;
 mod sized_communicator;
 
 use bevy_ecs::prelude::Resource;
@@ -15,6 +16,7 @@ pub use exchange_communicator::ExchangeCommunicator;
 pub use identified::EntityKey;
 pub use identified::Identified;
 pub use plugin::BaseCommunicationPlugin;
+pub use reproducible_sum::ReproducibleSum;
 pub use sized_communicator::SizedCommunicator;
 
 mod mpi_world;
@@ -25,12 +25,14 @@ use mpi::traits::CommunicatorCollectives;
 use mpi::traits::Destination;
 use mpi::traits::Equivalence;
 use mpi::traits::MatchedReceiveVec;
+use mpi::traits::Root;
 use mpi::traits::Source;
 use mpi::Count;
 use mpi::Tag;
 use mpi::Threading;
 
 use super::Identified;
+use super::ReproducibleSum;
 use super::SizedCommunicator;
 
 /// A wrapper around universe which contains the universe in an
@@ -197,6 +199,17 @@ where
             .sum()
     }
 
+    /// Like [Self::all_gather_sum], but the values of the individual
+    /// ranks are summed in an order-independent way, so that the
+    /// result does not depend on which rank supplied which value.
+    pub fn all_gather_reproducible_sum(&mut self, send: &S) -> S
+    where
+        S: ReproducibleSum,
+    {
+        self.verify_tag();
+        S::reproducible_sum(unchecked_all_gather(&mut self.world, send))
+    }
+
     // Temporary replacement for a proper AllReduce call
     pub fn all_gather_min<T>(&mut self, send: &S) -> Option<T>
     where
@@ -221,6 +234,13 @@ where
             .max_by(|x, y| x.partial_cmp(y).unwrap_or(std::cmp::Ordering::Equal))
     }
 
+    /// Returns the value that the root rank passed in on every rank.
+    pub fn broadcast_from_root(&mut self, send: &S) -> S {
+        let mut data = send.clone();
+        self.world.process_at_rank(0).broadcast_into(&mut data);
+        data
+    }
+
     pub fn all_gather(&mut self, send: &S) -> Vec<S> {
         self.verify_tag();
         unchecked_all_gather(&mut self.world, send)
@@ -233,6 +253,16 @@ where
         sum
     }
 
+    /// Sums the slices of all ranks element by element in a single
+    /// reduction. The slices need to have the same length on every
+    /// rank.
+    pub fn all_reduce_sum_elementwise(&mut self, send: &[u64]) -> Vec<u64> {
+        let mut sum = vec![0u64; send.len()];
+        self.world
+            .all_reduce_into(send, &mut sum[..], SystemOperation::sum());
+        sum
+    }
+
     fn all_gather_varcount_with_counts(&mut self, send: &[S], counts: &[Count]) -> Vec<S> {
         self.verify_tag();
         let mut result_buffer: Vec<S> =
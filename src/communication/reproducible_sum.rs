@@ -0,0 +1,98 @@
+use crate::units::Dimension;
+use crate::units::Quantity;
+
+/// Floating point types whose sum over values from multiple ranks
+/// can be computed independently of the order in which the values
+/// are supplied (and therefore independently of the rank layout of
+/// the individual contributions).
+pub trait ReproducibleSum: Sized {
+    fn reproducible_sum(values: Vec<Self>) -> Self;
+}
+
+impl ReproducibleSum for f64 {
+    /// Sorts the values before summing them with Neumaier's
+    /// compensated summation, so that both the order of the
+    /// summation and the rounding error are fixed.
+    fn reproducible_sum(mut values: Vec<Self>) -> Self {
+        values.sort_by(|x, y| x.total_cmp(y));
+        let mut sum = 0.0;
+        let mut compensation = 0.0;
+        for value in values {
+            let new_sum = sum + value;
+            if sum.abs() >= value.abs() {
+                compensation += (sum - new_sum) + value;
+            } else {
+                compensation += (value - new_sum) + sum;
+            }
+            sum = new_sum;
+        }
+        sum + compensation
+    }
+}
+
+impl<const D: Dimension> ReproducibleSum for Quantity<f64, D> {
+    fn reproducible_sum(values: Vec<Self>) -> Self {
+        Self::new_unchecked(f64::reproducible_sum(
+            values.into_iter().map(|x| x.value_unchecked()).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use super::ReproducibleSum;
+    use crate::communication::MpiWorld;
+    use crate::communication::SizedCommunicator;
+    use crate::test_utils::run_on_rank_counts;
+    use crate::units::Length;
+
+    #[test]
+    fn reproducible_sum_is_independent_of_order() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut values: Vec<f64> = (0..1000)
+            .map(|_| rng.gen::<f64>() * 10.0f64.powi(rng.gen_range(-10..10)))
+            .collect();
+        let sum = f64::reproducible_sum(values.clone());
+        for _ in 0..10 {
+            values.shuffle(&mut rng);
+            assert_eq!(
+                f64::reproducible_sum(values.clone()).to_bits(),
+                sum.to_bits()
+            );
+        }
+    }
+
+    #[test]
+    fn reproducible_sum_of_quantities() {
+        let values = vec![
+            Length::meters(1e20),
+            Length::meters(1.0),
+            Length::meters(-1e20),
+        ];
+        assert_eq!(Length::reproducible_sum(values), Length::meters(1.0));
+    }
+
+    #[test]
+    fn all_gather_reproducible_sum_is_independent_of_rank_order() {
+        if run_on_rank_counts(
+            concat!(
+                module_path!(),
+                "::all_gather_reproducible_sum_is_independent_of_rank_order"
+            ),
+            1..=3,
+        ) {
+            return;
+        }
+        let mut comm = MpiWorld::<f64>::new();
+        let value = 0.1 * 10.0f64.powi(comm.rank() * 8);
+        let mut values = comm.all_gather(&value);
+        let sum = comm.all_gather_reproducible_sum(&value);
+        values.reverse();
+        assert_eq!(f64::reproducible_sum(values).to_bits(), sum.to_bits());
+    }
+}
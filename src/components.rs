@@ -71,6 +71,14 @@ pub struct HeatingRate(pub units::HeatingRate);
 #[repr(transparent)]
 pub struct Timestep(pub Time);
 
+/// The timestep which the chemistry and the change of the incoming
+/// rate would permit. The actual timestep is limited by the
+/// available timestep levels.
+#[derive(H5Type, Component, Debug, Clone, Equivalence, Deref, DerefMut, From, Named, Default)]
+#[name = "desired_timestep"]
+#[repr(transparent)]
+pub struct DesiredTimestep(pub Time);
+
 #[derive(H5Type, Component, Debug, Clone, Equivalence, Deref, DerefMut, From, Named)]
 #[name = "ionization_time"]
 #[repr(transparent)]
@@ -116,4 +124,5 @@ impl_to_dataset!(RecombinationRate, units::Rate, false);
 impl_to_dataset!(CollisionalIonizationRate, units::Rate, false);
 impl_to_dataset!(HeatingRate, units::HeatingRate, false);
 impl_to_dataset!(Timestep, units::Time, false);
+impl_to_dataset!(DesiredTimestep, units::Time, false);
 impl_to_dataset!(IonizationTime, units::Time, false);
@@ -0,0 +1,372 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::schedule::SystemDescriptor;
+use bevy_ecs::schedule::SystemLabelId;
+use bevy_ecs::system::AsSystemLabel;
+use derive_custom::subsweep_parameters;
+use derive_custom::Named;
+use hdf5::H5Type;
+use mpi::traits::Equivalence;
+
+use crate::communication::DataByRank;
+use crate::communication::ExchangeCommunicator;
+use crate::communication::MpiWorld;
+use crate::communication::SizedCommunicator;
+use crate::components::Position;
+use crate::domain::DecompositionState;
+use crate::domain::LeafData;
+use crate::domain::QuadTree;
+use crate::extent::Extent;
+use crate::io::output::plugin::IntoOutputSystem;
+use crate::io::output::timer::Timer;
+use crate::io::output::OutputFiles;
+use crate::io::output::OutputPlugin;
+use crate::parameters::SimulationBox;
+use crate::prelude::ParticleId;
+use crate::prelude::Particles;
+use crate::prelude::Stages;
+use crate::quadtree::QuadTreeConfig;
+use crate::simulation::Simulation;
+use crate::simulation::SubsweepPlugin;
+use crate::units::Length;
+use crate::units::MVec;
+use crate::units::VecLength;
+use crate::units::Volume;
+
+/// Parameters of the two-point correlation function which is
+/// written to every snapshot if the [CorrelationPlugin] is added.
+#[subsweep_parameters("correlation")]
+pub struct CorrelationParameters {
+    /// The smallest radius of the radial bins.
+    pub min_radius: Length,
+    /// The largest radius of the radial bins. Can be at most half
+    /// the smallest side length of the simulation box.
+    pub max_radius: Length,
+    /// The number of logarithmically spaced radial bins.
+    #[serde(default = "default_num_bins")]
+    pub num_bins: usize,
+}
+
+fn default_num_bins() -> usize {
+    10
+}
+
+/// Logarithmically spaced radial bins.
+#[derive(Clone, Debug)]
+pub struct LogBins {
+    min: Length,
+    max: Length,
+    num: usize,
+}
+
+impl LogBins {
+    pub fn new(min: Length, max: Length, num: usize) -> Self {
+        assert!(
+            min.is_positive() && min < max,
+            "Invalid radial bins: The minimum radius ({:?}) needs to be positive and smaller than the maximum radius ({:?}).",
+            min,
+            max
+        );
+        Self { min, max, num }
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.num
+    }
+
+    fn log_ratio(&self) -> f64 {
+        (self.max / self.min).value().ln()
+    }
+
+    pub fn get_bin(&self, r: Length) -> Option<usize> {
+        if r < self.min || r >= self.max {
+            return None;
+        }
+        let bin = (self.num as f64 * (r / self.min).value().ln() / self.log_ratio()) as usize;
+        Some(bin.min(self.num - 1))
+    }
+
+    pub fn edge(&self, i: usize) -> Length {
+        self.min * (self.log_ratio() * i as f64 / self.num as f64).exp()
+    }
+
+    /// The geometric mean of the two edges of the bin.
+    pub fn center(&self, i: usize) -> Length {
+        (self.edge(i) * self.edge(i + 1)).sqrt()
+    }
+
+    #[cfg(feature = "2d")]
+    pub fn shell_volume(&self, i: usize) -> Volume {
+        (self.edge(i + 1).powi::<2>() - self.edge(i).powi::<2>()) * std::f64::consts::PI
+    }
+
+    #[cfg(not(feature = "2d"))]
+    pub fn shell_volume(&self, i: usize) -> Volume {
+        (self.edge(i + 1).powi::<3>() - self.edge(i).powi::<3>())
+            * (4.0 / 3.0 * std::f64::consts::PI)
+    }
+}
+
+impl From<&CorrelationParameters> for LogBins {
+    fn from(parameters: &CorrelationParameters) -> Self {
+        Self::new(
+            parameters.min_radius,
+            parameters.max_radius,
+            parameters.num_bins,
+        )
+    }
+}
+
+#[derive(H5Type, Clone, Debug)]
+#[repr(C)]
+pub struct CorrelationBin {
+    /// The center of the bin in SI units.
+    pub radius: f64,
+    pub value: f64,
+}
+
+#[derive(Resource, Named, Default)]
+#[name = "correlation_function"]
+pub struct CorrelationFunction(pub Vec<CorrelationBin>);
+
+#[derive(Named)]
+pub struct CorrelationPlugin;
+
+impl SubsweepPlugin for CorrelationPlugin {
+    fn should_build(&self, sim: &Simulation) -> bool {
+        sim.write_output
+    }
+
+    fn build_everywhere(&self, sim: &mut Simulation) {
+        let max_radius = sim
+            .add_parameter_type_and_get_result::<CorrelationParameters>()
+            .max_radius;
+        let largest_allowed_radius = largest_allowed_radius(sim.unwrap_resource::<SimulationBox>());
+        assert!(
+            max_radius <= largest_allowed_radius,
+            "Invalid correlation parameter max_radius = {:?}: The radius can be at most half the smallest side length of the simulation box ({:?}), since pairs are only counted between nearest periodic images.",
+            max_radius,
+            largest_allowed_radius
+        );
+        sim.insert_resource(CorrelationFunction::default())
+            .add_system_to_stage(
+                Stages::AfterSweep,
+                compute_correlation_function_system.with_run_criteria(Timer::run_criterion),
+            )
+            .add_plugin(OutputPlugin::<CorrelationFunction>::default());
+    }
+}
+
+impl IntoOutputSystem for CorrelationFunction {
+    fn create_system() -> (SystemDescriptor, SystemLabelId) {
+        let system = write_correlation_function_system
+            .into_descriptor()
+            .with_run_criteria(Timer::run_criterion);
+        (system, write_correlation_function_system.as_system_label())
+    }
+
+    fn is_always_desired() -> bool {
+        false
+    }
+}
+
+/// Pairs are counted between the nearest periodic images of two
+/// particles, which is only unique up to half the box size.
+fn largest_allowed_radius(box_: &SimulationBox) -> Length {
+    Length::new_unchecked(box_.side_lengths().value_unchecked().min_element()) / 2.0
+}
+
+#[derive(Clone, Equivalence)]
+struct CommunicatedLeaf {
+    id: ParticleId,
+    pos: VecLength,
+}
+
+/// Counts the ordered pairs of local particles and any particle
+/// in the tree within each radial bin. For the counts to be
+/// complete, the tree needs to contain all particles within the
+/// largest radius of any local particle.
+fn count_pairs(
+    local: &[LeafData],
+    tree: &QuadTree,
+    box_: &SimulationBox,
+    bins: &LogBins,
+) -> Vec<u64> {
+    let mut counts = vec![0; bins.num_bins()];
+    for particle in local.iter() {
+        for neighbour in tree.iter_particles_in_radius(box_, particle.pos, bins.max) {
+            if neighbour.id == particle.id {
+                continue;
+            }
+            let r = box_.periodic_distance(&particle.pos, &neighbour.pos);
+            if let Some(bin) = bins.get_bin(r) {
+                counts[bin] += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// Uses the natural estimator: The number of pairs in each bin is
+/// compared to the expected number of pairs in a uniform periodic
+/// box.
+fn correlation_from_pair_counts(
+    counts: &[u64],
+    num_particles: usize,
+    box_: &SimulationBox,
+    bins: &LogBins,
+) -> Vec<CorrelationBin> {
+    let num_particles = num_particles as f64;
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let volume_fraction = (bins.shell_volume(i) / box_.volume()).value();
+            let expected = num_particles * (num_particles - 1.0) * volume_fraction;
+            CorrelationBin {
+                radius: bins.center(i).value_unchecked(),
+                value: *count as f64 / expected - 1.0,
+            }
+        })
+        .collect()
+}
+
+/// Sends each local particle to all other ranks whose domain
+/// overlaps the sphere of radius `radius` around it, so that each
+/// rank receives exactly the remote particles it needs to count
+/// all pairs of its local particles.
+fn exchange_particles_within_radius(
+    local: &[LeafData],
+    decomposition: &DecompositionState,
+    box_: &SimulationBox,
+    radius: Length,
+) -> Vec<LeafData> {
+    let mut communicator = ExchangeCommunicator::<CommunicatedLeaf>::new();
+    let mut outgoing: DataByRank<Vec<CommunicatedLeaf>> =
+        DataByRank::from_communicator(&communicator);
+    for rank in communicator.other_ranks() {
+        for leaf in local.iter() {
+            let extent = Extent::<MVec>::cube_around_sphere(
+                leaf.pos.value_unchecked(),
+                radius.value_unchecked(),
+            );
+            if decomposition.rank_owns_part_of_search_radius(rank, &extent, box_) {
+                outgoing[rank].push(CommunicatedLeaf {
+                    id: leaf.id,
+                    pos: leaf.pos,
+                });
+            }
+        }
+    }
+    communicator
+        .exchange_all(outgoing)
+        .into_iter()
+        .flat_map(|(_, received)| received.into_iter())
+        .map(|leaf| LeafData {
+            id: leaf.id,
+            pos: leaf.pos,
+        })
+        .collect()
+}
+
+fn compute_correlation_function_system(
+    particles: Particles<(&ParticleId, &Position)>,
+    box_: Res<SimulationBox>,
+    decomposition: Res<DecompositionState>,
+    parameters: Res<CorrelationParameters>,
+    mut correlation: ResMut<CorrelationFunction>,
+) {
+    let bins = LogBins::from(&*parameters);
+    let local: Vec<_> = particles
+        .iter()
+        .map(|(id, pos)| LeafData {
+            id: *id,
+            pos: **pos,
+        })
+        .collect();
+    let num_particles: usize = MpiWorld::<usize>::new().all_gather_sum(&local.len());
+    let remote = exchange_particles_within_radius(&local, &decomposition, &box_, bins.max);
+    let tree = QuadTree::new(
+        &QuadTreeConfig::default(),
+        local.iter().cloned().chain(remote).collect(),
+        &box_,
+    );
+    let local_counts = count_pairs(&local, &tree, &box_, &bins);
+    let counts = MpiWorld::<u64>::new().all_reduce_sum_elementwise(&local_counts);
+    correlation.0 = correlation_from_pair_counts(&counts, num_particles, &box_, &bins);
+}
+
+fn write_correlation_function_system(
+    correlation: Res<CorrelationFunction>,
+    files: ResMut<OutputFiles>,
+) {
+    for file in files.iter_files() {
+        let dataset = file
+            .new_dataset::<CorrelationBin>()
+            .shape(&[correlation.0.len()])
+            .create(CorrelationFunction::name())
+            .expect("Failed to create dataset");
+        dataset
+            .write(&correlation.0)
+            .expect("Failed to write correlation function");
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "3d")]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use super::correlation_from_pair_counts;
+    use super::count_pairs;
+    use super::largest_allowed_radius;
+    use super::LogBins;
+    use crate::domain::LeafData;
+    use crate::domain::QuadTree;
+    use crate::extent::Extent;
+    use crate::parameters::SimulationBox;
+    use crate::prelude::ParticleId;
+    use crate::quadtree::QuadTreeConfig;
+    use crate::units::Length;
+    use crate::units::VecLength;
+
+    #[test]
+    fn correlation_of_uniform_random_field_vanishes() {
+        let num_particles = 2000;
+        let box_ = SimulationBox::cube_from_side_length(Length::meters(1.0));
+        let mut rng = StdRng::seed_from_u64(1338);
+        let particles: Vec<_> = (0..num_particles)
+            .map(|i| LeafData {
+                id: ParticleId::test(i),
+                pos: VecLength::meters(rng.gen(), rng.gen(), rng.gen()),
+            })
+            .collect();
+        let tree = QuadTree::new(&QuadTreeConfig::default(), particles.clone(), &box_);
+        let bins = LogBins::new(Length::meters(0.05), Length::meters(0.2), 5);
+        let counts = count_pairs(&particles, &tree, &box_, &bins);
+        let correlation = correlation_from_pair_counts(&counts, num_particles, &box_, &bins);
+        for bin in correlation.iter() {
+            assert!(bin.value.abs() < 0.1, "{:?}", bin);
+        }
+    }
+
+    #[test]
+    fn largest_allowed_radius_is_half_the_smallest_side_length() {
+        let box_ = SimulationBox::new(Extent::from_min_max(
+            VecLength::meters(0.0, 0.0, 0.0),
+            VecLength::meters(2.0, 1.0, 4.0),
+        ));
+        assert_eq!(largest_allowed_radius(&box_), Length::meters(0.5));
+    }
+
+    #[test]
+    fn log_bins() {
+        let bins = LogBins::new(Length::meters(1.0), Length::meters(100.0), 2);
+        assert_eq!(bins.get_bin(Length::meters(0.5)), None);
+        assert_eq!(bins.get_bin(Length::meters(1.0)), Some(0));
+        assert_eq!(bins.get_bin(Length::meters(9.0)), Some(0));
+        assert_eq!(bins.get_bin(Length::meters(11.0)), Some(1));
+        assert_eq!(bins.get_bin(Length::meters(100.0)), None);
+    }
+}
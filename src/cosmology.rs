@@ -7,8 +7,10 @@ use hdf5::H5Type;
 
 use crate::impl_attribute;
 use crate::io::output::ToAttribute;
+use crate::prelude::Float;
 use crate::units::Dimension;
 use crate::units::Dimensionless;
+use crate::units::Quantity;
 use crate::units::Time;
 
 #[subsweep_parameters("cosmology")]
@@ -18,6 +20,8 @@ pub enum Cosmology {
     Cosmological {
         a: f64,
         h: f64,
+        /// The density parameters. If not given, a flat LCDM
+        /// cosmology is assumed (see [CosmologyParams::default]).
         params: Option<CosmologyParams>,
     },
     NonCosmological,
@@ -30,6 +34,25 @@ pub struct CosmologyParams {
     omega_lambda: f64,
 }
 
+/// The number of intervals used for integrating the Friedmann
+/// equation.
+const NUM_INTEGRATION_INTERVALS: usize = 1000;
+
+/// The maximum deviation of `omega_0 + omega_lambda` from 1 for
+/// which the analytic solution of a flat cosmology is used instead
+/// of integrating the Friedmann equation.
+const FLATNESS_TOLERANCE: f64 = 1e-6;
+
+impl Default for CosmologyParams {
+    /// The flat LCDM cosmology of Planck 2015.
+    fn default() -> Self {
+        Self {
+            omega_0: 0.3089,
+            omega_lambda: 0.6911,
+        }
+    }
+}
+
 pub fn scalefactor_to_redshift(a: Dimensionless) -> Dimensionless {
     1.0 / a - 1.0
 }
@@ -60,6 +83,21 @@ impl Cosmology {
         }
     }
 
+    /// Converts a quantity given in comoving units into physical
+    /// units. The powers of the scale factor and of little h are
+    /// taken from the dimension of the quantity, so that, for
+    /// example, a comoving length is multiplied by `a`, while
+    /// quantities without these powers remain unchanged.
+    pub fn comoving_to_physical<const D: Dimension>(
+        &self,
+        quantity: Quantity<Float, D>,
+    ) -> Quantity<Float, { Dimension::non_cosmological(D) }>
+    where
+        Quantity<Float, { Dimension::non_cosmological(D) }>:,
+    {
+        quantity.make_non_cosmological(self)
+    }
+
     pub fn time_difference_between_scalefactors(
         &self,
         a0: Dimensionless,
@@ -67,7 +105,7 @@ impl Cosmology {
     ) -> Time {
         match self {
             Cosmology::Cosmological { h, params, .. } => params
-                .unwrap()
+                .unwrap_or_default()
                 .time_difference_between_scalefactors(a0, a1, Dimensionless::dimensionless(*h)),
             Cosmology::NonCosmological => {
                 panic!("Tried to compute time difference in non cosmological run")
@@ -89,21 +127,31 @@ impl CosmologyParams {
             omega_0,
             ..
         } = self;
-        if ((omega_lambda + omega_0) - 1.0).abs() > 1e-2 {
-            unimplemented!("Cosmology needs to be flat.");
+        let omega_k = 1.0 - omega_0 - omega_lambda;
+        if omega_k.abs() < FLATNESS_TOLERANCE && *omega_0 > 0.0 && *omega_lambda > 0.0 {
+            // Analytic solution for flat LCDM, which avoids the
+            // integration for the common case.
+            let time = |a: f64| {
+                let ratio = omega_lambda / omega_0;
+                2.0 / (3.0 * omega_lambda.sqrt())
+                    * ((ratio * a.powi(3)).sqrt() + (1.0 + ratio * a.powi(3)).sqrt()).ln()
+            };
+            return Time::seconds((time(a1.value()) - time(a0.value())) / (HUBBLE * *h));
         }
-
-        let time = |a: Dimensionless| {
-            let factor1 = 2.0 / (3.0 * omega_lambda.sqrt());
-            let term1 = (omega_lambda / omega_0).sqrt() * a.powf(1.5);
-            let term2 = (1.0 + omega_lambda / omega_0 * a.cubed()).sqrt();
-            let factor2 = (term1 + term2).ln();
-            factor1 * factor2
+        // Integrate dt = da / (a H(a)) with
+        // H(a) = H0 sqrt(omega_0 a^-3 + omega_k a^-2 + omega_lambda)
+        // in terms of x = sqrt(a), for which the integrand is
+        // smooth down to a = 0.
+        let integrand = |x: f64| {
+            2.0 * x.powi(2) / (omega_0 + omega_k * x.powi(2) + omega_lambda * x.powi(6)).sqrt()
         };
-
-        let t0 = time(a0);
-        let t1 = time(a1);
-        Time::seconds(*(t1 - t0) / (HUBBLE * *h))
+        let integral = integrate_simpson(
+            integrand,
+            a0.value().sqrt(),
+            a1.value().sqrt(),
+            NUM_INTEGRATION_INTERVALS,
+        );
+        Time::seconds(integral / (HUBBLE * *h))
     }
 
     /// Get the scale factor a which the given cosmology has when
@@ -129,6 +177,20 @@ impl CosmologyParams {
     }
 }
 
+/// Integrate f from min to max with Simpson's rule on
+/// `num_intervals` intervals, which needs to be even.
+fn integrate_simpson(f: impl Fn(f64) -> f64, min: f64, max: f64, num_intervals: usize) -> f64 {
+    debug_assert!(num_intervals % 2 == 0);
+    let step = (max - min) / num_intervals as f64;
+    let sum: f64 = (1..num_intervals)
+        .map(|i| {
+            let weight = if i % 2 == 0 { 2.0 } else { 4.0 };
+            weight * f(min + i as f64 * step)
+        })
+        .sum();
+    step / 3.0 * (f(min) + sum + f(max))
+}
+
 /// Find a root of the monotonously increasing function f by binary search on the interval [min, max].
 fn binary_search(f: impl Fn(f64) -> f64, min: f64, max: f64, threshold: f64) -> f64 {
     depth_limited_binary_search(f, min, max, threshold, 0)
@@ -197,8 +259,13 @@ impl_attribute!(Omega0, Dimensionless);
 
 #[cfg(test)]
 mod tests {
+    use super::Cosmology;
     use super::CosmologyParams;
+    use crate::test_utils::assert_is_close;
+    use crate::units::ComovingLength;
+    use crate::units::ComovingLengthTimesH;
     use crate::units::Dimensionless;
+    use crate::units::Length;
     use crate::units::Time;
 
     fn get_test_cosmology_and_h() -> (CosmologyParams, Dimensionless) {
@@ -219,6 +286,55 @@ mod tests {
         assert!((diff(0.99, 1.0) - Time::gigayears(0.14473176)).abs() < Time::years(10000.0));
     }
 
+    #[test]
+    fn time_difference_matches_analytic_solutions() {
+        let h = Dimensionless::dimensionless(0.7);
+        let hubble_time = Time::seconds(1.0 / (3.2407789e-18 * 0.7));
+        let diff = |params: CosmologyParams, a0: f64, a1: f64| {
+            params.time_difference_between_scalefactors(a0.into(), a1.into(), h)
+        };
+        let check = |time: Time, expected: Time| {
+            assert!(((time - expected) / expected).abs().value() < 1e-8);
+        };
+        // Einstein-de Sitter: t(a) = 2 / (3 H0) a^(3/2)
+        let einstein_de_sitter = CosmologyParams {
+            omega_0: 1.0,
+            omega_lambda: 0.0,
+        };
+        check(
+            diff(einstein_de_sitter, 0.0, 0.25),
+            hubble_time * 2.0 / 3.0 * 0.125,
+        );
+        // Empty, open universe: t(a) = a / H0
+        let milne = CosmologyParams {
+            omega_0: 0.0,
+            omega_lambda: 0.0,
+        };
+        check(diff(milne, 0.2, 0.7), hubble_time * 0.5);
+        check(diff(milne, 0.7, 0.2), hubble_time * -0.5);
+        // Flat LCDM
+        let params = CosmologyParams::default();
+        let time = |a: f64| {
+            let CosmologyParams {
+                omega_0,
+                omega_lambda,
+            } = params;
+            let term1 = (omega_lambda / omega_0).sqrt() * a.powf(1.5);
+            let term2 = (1.0 + omega_lambda / omega_0 * a.powi(3)).sqrt();
+            hubble_time * 2.0 / (3.0 * omega_lambda.sqrt()) * (term1 + term2).ln()
+        };
+        check(diff(params, 0.1, 0.9), time(0.9) - time(0.1));
+        // A slightly curved cosmology is integrated numerically, but
+        // hardly differs from the analytic flat solution.
+        let slightly_curved = CosmologyParams {
+            omega_lambda: params.omega_lambda + 1e-5,
+            ..params
+        };
+        let expected = time(0.9) - time(0.1);
+        let integrated = diff(slightly_curved, 0.1, 0.9);
+        assert!(((integrated - expected) / expected).abs().value() < 1e-4);
+    }
+
     #[test]
     fn get_scalefactor_from_scalefactor_and_time_difference() {
         let (cosmology, h) = get_test_cosmology_and_h();
@@ -236,4 +352,27 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn comoving_to_physical() {
+        let cosmology = Cosmology::Cosmological {
+            a: 0.5,
+            h: 0.7,
+            params: None,
+        };
+        assert_is_close(
+            cosmology.comoving_to_physical(ComovingLength::comoving_kiloparsec(2.0)),
+            Length::kiloparsec(1.0),
+        );
+        assert_is_close(
+            cosmology.comoving_to_physical(
+                ComovingLengthTimesH::weird_cosmological_notation_kiloparsec(0.7),
+            ),
+            Length::kiloparsec(0.5),
+        );
+        assert_is_close(
+            cosmology.comoving_to_physical(Length::kiloparsec(1.0)),
+            Length::kiloparsec(1.0),
+        );
+    }
 }
@@ -97,6 +97,10 @@ impl<K: Key> Decomposition<K> {
             .unwrap_or_else(|e| e) as i32
     }
 
+    pub fn num_ranks(&self) -> usize {
+        self.num_ranks
+    }
+
     pub fn get_imbalance(&self) -> f64 {
         let min_load = self.min_load();
         let max_load = self.max_load();
@@ -198,8 +202,14 @@ impl<'a, K: Key, C: LoadCounter<K>> Decomposer<'a, K, C> {
     }
 }
 
+/// Counts the load in a range of keys. Every key carries a weight
+/// which is the amount of work associated with it.
 pub struct KeyCounter<K> {
     keys: Vec<K>,
+    /// The total weight of all keys before the key at the same
+    /// index (with one additional entry for the total weight), so
+    /// that the load in any range can be computed by a difference.
+    cumulative_loads: Vec<Work>,
 }
 
 impl<K: Key> KeyCounter<K> {
@@ -223,13 +233,40 @@ impl<K: Key> KeyCounter<K> {
         points: impl Iterator<Item = P>,
         extent: &Extent<P>,
     ) -> Self {
-        let keys = points.map(|p| p.into_key(extent)).collect();
-        Self::new(keys)
+        Self::from_points_and_weights(points.map(|p| (p, 1)), extent)
     }
 
-    pub fn new(mut keys: Vec<K>) -> Self {
-        keys.sort();
-        Self { keys }
+    /// Like [from_points_and_extent](Self::from_points_and_extent),
+    /// but every point counts with the given weight instead of 1,
+    /// so that the decomposition balances the total work rather
+    /// than the number of points.
+    pub fn from_points_and_weights<P: IntoKey<Key = K> + Copy>(
+        points_and_weights: impl Iterator<Item = (P, Work)>,
+        extent: &Extent<P>,
+    ) -> Self {
+        let keys_and_weights = points_and_weights
+            .map(|(p, weight)| (p.into_key(extent), weight))
+            .collect();
+        Self::from_keys_and_weights(keys_and_weights)
+    }
+
+    pub fn new(keys: Vec<K>) -> Self {
+        Self::from_keys_and_weights(keys.into_iter().map(|key| (key, 1)).collect())
+    }
+
+    pub fn from_keys_and_weights(mut keys_and_weights: Vec<(K, Work)>) -> Self {
+        keys_and_weights.sort_by_key(|(key, _)| *key);
+        let mut cumulative_loads = Vec::with_capacity(keys_and_weights.len() + 1);
+        let mut load = 0;
+        cumulative_loads.push(load);
+        for (_, weight) in keys_and_weights.iter() {
+            load += weight;
+            cumulative_loads.push(load);
+        }
+        Self {
+            keys: keys_and_weights.into_iter().map(|(key, _)| key).collect(),
+            cumulative_loads,
+        }
     }
 }
 
@@ -241,7 +278,7 @@ impl<K: Key> LoadCounter<K> for KeyCounter<K> {
             .binary_search(&end)
             .map(|x| x + 1)
             .unwrap_or_else(|e| e);
-        end as u64 - start as u64
+        self.cumulative_loads[end] - self.cumulative_loads[start]
     }
 
     fn min_key(&mut self) -> K {
@@ -389,6 +426,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn weighted_domain_decomp_1d() {
+        let num_points_per_rank = 5000;
+        for num_ranks in [1, 2, 7, 10] {
+            let num_points = num_points_per_rank * num_ranks;
+            let points = get_point_set_1(num_points);
+            let extent = Extent::from_points(points.iter().copied()).unwrap();
+            let weight = |p: f64| if p < (num_points / 4) as f64 { 10 } else { 1 };
+            let mut counter = KeyCounter::from_points_and_weights(
+                points.iter().map(|p| (*p, weight(*p))),
+                &extent,
+            );
+            let decomposition = Decomposition::new(&mut counter, num_ranks);
+            let imbalance = decomposition.get_imbalance();
+            assert!(imbalance < 0.05);
+            if num_ranks > 1 {
+                // The heavy points should be spread over more ranks
+                // than their number alone would suggest.
+                let heavy_point = (num_points / 4 - 1) as f64;
+                let rank = decomposition.get_owning_rank(heavy_point.into_key(&extent));
+                assert!(rank as usize >= num_ranks / 2);
+            }
+        }
+    }
+
     fn get_point_set_3d_1(num_points: usize) -> Vec<VecLength> {
         let n = (num_points as f64).sqrt() as i32;
         get_particles(n, n).into_iter().map(|p| p.pos).collect()
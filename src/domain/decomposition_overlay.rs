@@ -0,0 +1,165 @@
+use bevy_ecs::prelude::Res;
+
+use super::DecompositionState;
+use super::DomainParameters;
+use super::IntoKey;
+use crate::communication::Rank;
+use crate::extent::Extent;
+use crate::parameters::SimulationBox;
+use crate::prelude::WorldRank;
+use crate::units::MVec;
+use crate::units::VecLength;
+use crate::voronoi::visualizer::Visualizable;
+
+/// The approximate region of space owned by each rank. The
+/// simulation box is divided into a regular grid and every grid
+/// cell is assigned to the rank which owns the Peano-Hilbert key of
+/// its center.
+pub struct DecompositionOverlay {
+    pub boxes: Vec<(Rank, Extent<VecLength>)>,
+    num_ranks: usize,
+}
+
+impl DecompositionOverlay {
+    /// Uses a grid with `2^depth` cells along each dimension.
+    pub fn new(decomposition: &DecompositionState, box_: &SimulationBox, depth: usize) -> Self {
+        let num_cells_per_dim = 1 << depth;
+        let box_min = box_.min.value_unchecked();
+        let cell_size = box_.side_lengths().value_unchecked() / num_cells_per_dim as f64;
+        let boxes = grid_indices(num_cells_per_dim)
+            .map(|index| {
+                let min = VecLength::new_unchecked(box_min + cell_size * index);
+                let max = VecLength::new_unchecked(box_min + cell_size * (index + MVec::ONE));
+                let extent = Extent::from_min_max(min, max);
+                let rank = decomposition.get_owning_rank(extent.center().into_key(box_));
+                (rank, extent)
+            })
+            .collect();
+        Self {
+            boxes,
+            num_ranks: decomposition.num_ranks(),
+        }
+    }
+
+    fn color(&self, rank: Rank) -> (f64, f64, f64) {
+        hue_to_rgb(rank as f64 / self.num_ranks as f64)
+    }
+}
+
+#[cfg(feature = "2d")]
+fn grid_indices(num_cells_per_dim: usize) -> impl Iterator<Item = MVec> {
+    (0..num_cells_per_dim)
+        .flat_map(move |x| (0..num_cells_per_dim).map(move |y| MVec::new(x as f64, y as f64)))
+}
+
+#[cfg(not(feature = "2d"))]
+fn grid_indices(num_cells_per_dim: usize) -> impl Iterator<Item = MVec> {
+    (0..num_cells_per_dim).flat_map(move |x| {
+        (0..num_cells_per_dim).flat_map(move |y| {
+            (0..num_cells_per_dim).map(move |z| MVec::new(x as f64, y as f64, z as f64))
+        })
+    })
+}
+
+/// Maps a hue in [0, 1) to a fully saturated color.
+fn hue_to_rgb(hue: f64) -> (f64, f64, f64) {
+    let h = hue * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    match h as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    }
+}
+
+impl Visualizable for DecompositionOverlay {
+    /// Draws the boxes projected onto the xy plane.
+    fn get_statements(&self) -> Vec<String> {
+        self.boxes
+            .iter()
+            .map(|(rank, extent)| {
+                let min = extent.min.value_unchecked();
+                let max = extent.max.value_unchecked();
+                let (r, g, b) = self.color(*rank);
+                format!(
+                    "Polygon {} {} {} {} {} {} {} {} color {} {} {} 0.5",
+                    min.x, min.y, max.x, min.y, max.x, max.y, min.x, max.y, r, g, b
+                )
+            })
+            .collect()
+    }
+}
+
+pub(super) fn visualize_decomposition_system(
+    decomposition: Res<DecompositionState>,
+    box_: Res<SimulationBox>,
+    parameters: Res<DomainParameters>,
+    rank: Res<WorldRank>,
+) {
+    if !rank.is_main() {
+        return;
+    }
+    let depth = parameters.decomposition_overlay_depth.unwrap();
+    crate::vis![&DecompositionOverlay::new(&decomposition, &box_, depth)];
+}
+
+#[cfg(test)]
+#[cfg(feature = "3d")]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use super::DecompositionOverlay;
+    use crate::domain::decomposition::KeyCounter;
+    use crate::domain::DecompositionState;
+    use crate::parameters::SimulationBox;
+    use crate::units::Length;
+    use crate::units::VecLength;
+    use crate::units::Volume;
+
+    #[test]
+    fn overlay_covers_box_exactly_once() {
+        let box_ = SimulationBox::cube_from_side_length(Length::meters(2.0));
+        let mut rng = StdRng::seed_from_u64(1338);
+        let mut random_point = || {
+            VecLength::meters(
+                rng.gen_range(0.0..2.0),
+                rng.gen_range(0.0..2.0),
+                rng.gen_range(0.0..2.0),
+            )
+        };
+        let points: Vec<_> = (0..1000).map(|_| random_point()).collect();
+        let mut counter = KeyCounter::from_points_and_extent(points.into_iter(), &*box_);
+        let num_ranks = 4;
+        let decomposition = DecompositionState::new(&mut counter, num_ranks);
+        let overlay = DecompositionOverlay::new(&decomposition, &box_, 3);
+        assert_eq!(overlay.boxes.len(), 8 * 8 * 8);
+        let total_volume: Volume = overlay
+            .boxes
+            .iter()
+            .map(|(_, extent)| extent.volume())
+            .sum();
+        assert!(
+            ((box_.volume() - total_volume) / box_.volume())
+                .abs()
+                .value()
+                < 1e-10
+        );
+        for rank in 0..num_ranks as i32 {
+            assert!(overlay.boxes.iter().any(|(r, _)| *r == rank));
+        }
+        for _ in 0..1000 {
+            let point = random_point();
+            let num_containing = overlay
+                .boxes
+                .iter()
+                .filter(|(_, extent)| extent.contains(&point))
+                .count();
+            assert_eq!(num_containing, 1);
+        }
+    }
+}
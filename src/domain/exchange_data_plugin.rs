@@ -138,13 +138,11 @@ impl<T: Sync + Send + 'static + Component + Clone + Equivalence> ExchangeDataPlu
     ) {
         let mut communicator = ExchangeCommunicator::<T>::new();
         let buffers = buffers.take();
-        let mut incoming = communicator.exchange_all(buffers);
-        for (rank, data) in incoming.drain_all() {
-            let spawned_entities = spawned_entities[rank].clone();
-            for (entity, component) in spawned_entities.iter().zip(data.into_iter()) {
+        communicator.exchange_all_overlapped(buffers, |rank, data| {
+            for (entity, component) in spawned_entities[rank].iter().zip(data.into_iter()) {
                 commands.entity(*entity).insert(component);
             }
-        }
+        });
     }
 
     fn reset_buffers_system(
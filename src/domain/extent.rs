@@ -71,6 +71,37 @@ macro_rules! impl_extent {
                 }
                 Some(Self::from_min_max(min?, max?))
             }
+
+            /// The distance between `pos` and the closest point of
+            /// the extent (zero if `pos` lies within the extent).
+            pub fn distance_to(&self, pos: &$unit_vec) -> $length {
+                let closest = pos.max(self.min).min(self.max);
+                closest.distance(pos)
+            }
+
+            /// Whether any point of the extent lies within `radius`
+            /// of `center`.
+            pub fn overlaps_sphere(&self, center: &$unit_vec, radius: $length) -> bool {
+                self.distance_to(center) <= radius
+            }
+
+            /// The region covered by both extents. Extents which
+            /// only touch at their boundary do not intersect, so this
+            /// returns `None` whenever the intersection has no
+            /// volume.
+            pub fn intersection(&self, other: &Self) -> Option<Self> {
+                let min = self.min.max(other.min);
+                let max = self.max.min(other.max);
+                if (max - min).value_unchecked().min_element() > 0.0 {
+                    Some(Self::from_min_max(min, max))
+                } else {
+                    None
+                }
+            }
+
+            pub fn overlaps(&self, other: &Self) -> bool {
+                self.intersection(other).is_some()
+            }
         }
 
         /// A helper struct to enable deserialization of extents.
@@ -390,6 +421,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn extent_overlaps_sphere_3d() {
+        let extent = Extent3d::from_min_max(
+            Vec3Length::meters(0.0, 0.0, 0.0),
+            Vec3Length::meters(1.0, 1.0, 1.0),
+        );
+        let overlaps = |x, y, z, radius| {
+            extent.overlaps_sphere(&Vec3Length::meters(x, y, z), Length::meters(radius))
+        };
+        assert!(overlaps(0.5, 0.5, 0.5, 0.1));
+        assert!(overlaps(2.0, 0.5, 0.5, 1.1));
+        assert!(!overlaps(2.0, 0.5, 0.5, 0.9));
+        // Closest to the corner, which is sqrt(3) away
+        assert!(!overlaps(2.0, 2.0, 2.0, 1.7));
+        assert!(overlaps(2.0, 2.0, 2.0, 1.8));
+    }
+
+    #[test]
+    fn extent_intersection_3d() {
+        let extent = |min: (f64, f64, f64), max: (f64, f64, f64)| {
+            Extent3d::from_min_max(
+                Vec3Length::meters(min.0, min.1, min.2),
+                Vec3Length::meters(max.0, max.1, max.2),
+            )
+        };
+        let e1 = extent((0.0, 0.0, 0.0), (2.0, 2.0, 2.0));
+        let e2 = extent((1.0, -1.0, 0.5), (3.0, 1.0, 1.5));
+        let intersection = e1.intersection(&e2).unwrap();
+        assert_is_close_3d(intersection.min, MVec3::new(1.0, 0.0, 0.5));
+        assert_is_close_3d(intersection.max, MVec3::new(2.0, 1.0, 1.5));
+        assert!(e1.overlaps(&e2));
+        assert!(e2.overlaps(&e1));
+        // Contained
+        let e3 = extent((0.5, 0.5, 0.5), (1.0, 1.0, 1.0));
+        let intersection = e1.intersection(&e3).unwrap();
+        assert_is_close_3d(intersection.min, MVec3::new(0.5, 0.5, 0.5));
+        assert_is_close_3d(intersection.max, MVec3::new(1.0, 1.0, 1.0));
+        // Touching at a face, an edge and a corner
+        for other in [
+            extent((2.0, 0.0, 0.0), (3.0, 2.0, 2.0)),
+            extent((2.0, 2.0, 0.0), (3.0, 3.0, 2.0)),
+            extent((2.0, 2.0, 2.0), (3.0, 3.0, 3.0)),
+        ] {
+            assert!(e1.intersection(&other).is_none());
+            assert!(!e1.overlaps(&other));
+        }
+        // Disjoint
+        let e4 = extent((0.0, 3.0, 0.0), (2.0, 4.0, 2.0));
+        assert!(e1.intersection(&e4).is_none());
+        assert!(!e1.overlaps(&e4));
+    }
+
     #[test]
     fn extent_from_positions_3d() {
         let positions = &[
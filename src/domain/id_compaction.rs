@@ -0,0 +1,312 @@
+use bevy_ecs::prelude::*;
+use log::debug;
+use mpi::traits::Equivalence;
+
+use super::IdEntityMap;
+use crate::communication::communicator::Communicator;
+use crate::communication::DataByRank;
+use crate::communication::ExchangeCommunicator;
+use crate::communication::Rank;
+use crate::communication::WorldRank;
+use crate::hash_map::HashMap;
+use crate::hash_map::HashSet;
+use crate::particle::HaloParticles;
+use crate::prelude::LocalParticle;
+use crate::prelude::ParticleId;
+use crate::prelude::Particles;
+use crate::sweep::grid::Cell;
+use crate::sweep::grid::ParticleType;
+
+/// Sent after the particle ids have been compacted, so that any
+/// state which refers to particles by their id can be rebuilt.
+pub struct ParticleIdsCompacted;
+
+#[derive(SystemLabel)]
+pub struct CompactParticleIdsLabel;
+
+#[derive(Clone, Equivalence)]
+struct RequestedId(ParticleId);
+
+#[derive(Clone, Equivalence)]
+struct RenumberedId {
+    old: ParticleId,
+    new: ParticleId,
+}
+
+type NewIds = HashMap<ParticleId, ParticleId>;
+
+fn get_contiguous_ids(ids: impl Iterator<Item = ParticleId>, rank: Rank) -> NewIds {
+    let mut ids: Vec<_> = ids.collect();
+    // Sort to keep the relative order of the old ids.
+    ids.sort();
+    ids.into_iter()
+        .enumerate()
+        .map(|(index, old)| {
+            (
+                old,
+                ParticleId {
+                    index: index as u32,
+                    rank,
+                },
+            )
+        })
+        .collect()
+}
+
+fn get_remote_neighbour_ids(cell: &Cell) -> impl Iterator<Item = ParticleId> + '_ {
+    cell.neighbours
+        .iter()
+        .filter_map(|(_, neighbour)| match neighbour {
+            ParticleType::Remote(remote) => Some(remote.id),
+            ParticleType::RemotePeriodic(remote) => Some(remote.id),
+            _ => None,
+        })
+}
+
+fn renumber_neighbours(cell: &mut Cell, new_ids: &NewIds) {
+    for (_, neighbour) in cell.neighbours.iter_mut() {
+        let id = match neighbour {
+            ParticleType::Local(id) => id,
+            ParticleType::Remote(remote) => &mut remote.id,
+            ParticleType::LocalPeriodic(periodic) => &mut periodic.id,
+            ParticleType::RemotePeriodic(periodic) => &mut periodic.id,
+            ParticleType::Boundary => continue,
+        };
+        match new_ids.get(id) {
+            Some(new_id) => *id = *new_id,
+            // The neighbour does not exist anymore, so
+            // this face is now part of the boundary.
+            None => *neighbour = ParticleType::Boundary,
+        }
+    }
+}
+
+/// Asks the owning ranks for the new ids of all remote particles
+/// which are referenced on this rank, either as halo particles or
+/// as neighbours of local cells, and answers the same requests of
+/// the other ranks.
+fn exchange_new_ids(new_ids: &mut NewIds, remote_ids: impl Iterator<Item = ParticleId>) {
+    let mut request_communicator = ExchangeCommunicator::<RequestedId>::new();
+    let mut requests: DataByRank<Vec<RequestedId>> =
+        DataByRank::from_communicator(&request_communicator);
+    for id in remote_ids.collect::<HashSet<_>>() {
+        requests[id.rank].push(RequestedId(id));
+    }
+    let replies: DataByRank<Vec<RenumberedId>> = request_communicator
+        .exchange_all(requests)
+        .into_iter()
+        .map(|(rank, requested)| {
+            let renumbered = requested
+                .into_iter()
+                .filter_map(|RequestedId(old)| {
+                    Some(RenumberedId {
+                        old,
+                        new: *new_ids.get(&old)?,
+                    })
+                })
+                .collect();
+            (rank, renumbered)
+        })
+        .collect();
+    let mut reply_communicator = ExchangeCommunicator::<RenumberedId>::new();
+    for (_, renumbered) in reply_communicator.exchange_all(replies) {
+        new_ids.extend(renumbered.into_iter().map(|id| (id.old, id.new)));
+    }
+}
+
+/// Renumbers the particle ids on each rank so that they are
+/// contiguous again after particles have been removed. Runs at the
+/// start of every timestep, but only does any work if the ids are
+/// not contiguous on at least one rank.
+pub(super) fn compact_particle_ids_system(
+    mut particles: Particles<(Entity, &mut ParticleId, Option<&mut Cell>)>,
+    mut haloes: HaloParticles<(Entity, &mut ParticleId), Without<LocalParticle>>,
+    mut map: ResMut<IdEntityMap>,
+    mut compacted: EventWriter<ParticleIdsCompacted>,
+    rank: Res<WorldRank>,
+) {
+    let num_particles = particles.iter().count();
+    let is_sparse = particles
+        .iter()
+        .any(|(_, id, _)| id.index as usize >= num_particles);
+    let num_sparse_ranks: usize =
+        Communicator::<usize>::new().all_gather_sum(&(is_sparse as usize));
+    if num_sparse_ranks == 0 {
+        return;
+    }
+    let mut new_ids = get_contiguous_ids(particles.iter().map(|(_, id, _)| *id), **rank);
+    let remote_ids: Vec<_> = haloes
+        .iter()
+        .map(|(_, id)| *id)
+        .chain(
+            particles
+                .iter()
+                .flat_map(|(_, _, cell)| cell.into_iter().flat_map(get_remote_neighbour_ids)),
+        )
+        .filter(|id| id.rank != **rank)
+        .collect();
+    exchange_new_ids(&mut new_ids, remote_ids.into_iter());
+    apply_new_ids(&mut particles, &mut haloes, &mut map, &new_ids);
+    compacted.send(ParticleIdsCompacted);
+}
+
+fn apply_new_ids(
+    particles: &mut Particles<(Entity, &mut ParticleId, Option<&mut Cell>)>,
+    haloes: &mut HaloParticles<(Entity, &mut ParticleId), Without<LocalParticle>>,
+    map: &mut IdEntityMap,
+    new_ids: &NewIds,
+) {
+    for (_, mut id, cell) in particles.iter_mut() {
+        if let Some(mut cell) = cell {
+            renumber_neighbours(&mut cell, new_ids);
+        }
+        *id = new_ids[&*id];
+    }
+    let mut renumbered_haloes = vec![];
+    for (entity, mut id) in haloes.iter_mut() {
+        // Haloes of particles which have been removed on their
+        // own rank keep their old id, which might now belong to
+        // a different particle, so they are not put into the map.
+        // No cell refers to them anymore.
+        if let Some(new_id) = new_ids.get(&*id) {
+            *id = *new_id;
+            renumbered_haloes.push((*new_id, entity));
+        }
+    }
+    map.0 = particles
+        .iter()
+        .map(|(entity, id, _)| (*id, entity))
+        .chain(renumbered_haloes)
+        .collect();
+    debug!("Compacted {} particle ids", map.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::*;
+
+    use super::apply_new_ids;
+    use super::compact_particle_ids_system;
+    use super::NewIds;
+    use super::ParticleIdsCompacted;
+    use crate::domain::IdEntityMap;
+    use crate::hash_map::BiMap;
+    use crate::hash_map::HashSet;
+    use crate::particle::HaloParticle;
+    use crate::particle::HaloParticles;
+    use crate::prelude::LocalParticle;
+    use crate::prelude::ParticleId;
+    use crate::prelude::Particles;
+    use crate::prelude::WorldRank;
+    use crate::sweep::grid::Cell;
+    use crate::sweep::grid::Face;
+    use crate::sweep::grid::FaceArea;
+    use crate::sweep::grid::ParticleType;
+    use crate::test_utils::run_system_on_world;
+    use crate::units::Length;
+    use crate::units::VecDimensionless;
+    use crate::units::Volume;
+
+    fn chain_cell(index: usize, num_particles: usize) -> Cell {
+        let face = || Face {
+            area: FaceArea::zero(),
+            normal: VecDimensionless::zero(),
+        };
+        let neighbour = |index: Option<usize>| match index {
+            Some(index) if index < num_particles => ParticleType::Local(ParticleId::test(index)),
+            _ => ParticleType::Boundary,
+        };
+        Cell {
+            neighbours: vec![
+                (face(), neighbour(index.checked_sub(1))),
+                (face(), neighbour(Some(index + 1))),
+            ],
+            size: Length::meters(1.0),
+            volume: Volume::zero(),
+        }
+    }
+
+    #[test]
+    fn ids_are_contiguous_after_despawn_and_compaction() {
+        let num_particles = 6;
+        let mut world = World::new();
+        let mut map = BiMap::default();
+        for index in 0..num_particles {
+            let id = ParticleId::test(index);
+            let entity = world
+                .spawn((LocalParticle, id, chain_cell(index, num_particles)))
+                .id();
+            map.insert(id, entity);
+        }
+        for index in [1, 4] {
+            let entity = map.remove_by_left(&ParticleId::test(index)).unwrap().1;
+            world.despawn(entity);
+        }
+        world.insert_resource(IdEntityMap(map));
+        world.insert_resource(WorldRank(0));
+        world.insert_resource(Events::<ParticleIdsCompacted>::default());
+        run_system_on_world(&mut world, compact_particle_ids_system);
+        assert_eq!(
+            world
+                .resource_mut::<Events<ParticleIdsCompacted>>()
+                .drain()
+                .count(),
+            1
+        );
+        let ids: HashSet<_> = world
+            .query::<&ParticleId>()
+            .iter(&world)
+            .map(|id| id.index)
+            .collect();
+        assert_eq!(ids, (0..num_particles as u32 - 2).collect());
+        let map = world.resource::<IdEntityMap>();
+        assert_eq!(map.len(), num_particles - 2);
+        for (entity, id, cell) in world.query::<(Entity, &ParticleId, &Cell)>().iter(&world) {
+            assert_eq!(map.get_by_left(id), Some(&entity));
+            for (_, neighbour) in cell.neighbours.iter() {
+                if let ParticleType::Local(neighbour) = neighbour {
+                    assert!(map.contains_left(neighbour));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn haloes_are_renumbered_and_kept_in_map() {
+        let remote_id = |index| ParticleId { index, rank: 1 };
+        let mut world = World::new();
+        let mut new_ids = NewIds::default();
+        for index in [0, 2] {
+            world.spawn((LocalParticle, ParticleId::test(index)));
+            new_ids.insert(ParticleId::test(index), ParticleId::test(index / 2));
+        }
+        let renumbered: Vec<_> = [(3, 0), (5, 1)]
+            .into_iter()
+            .map(|(old, new)| {
+                new_ids.insert(remote_id(old), remote_id(new));
+                let entity = world.spawn((HaloParticle { rank: 1 }, remote_id(old))).id();
+                (remote_id(new), entity)
+            })
+            .collect();
+        // This particle has been removed on its own rank, so the
+        // owner did not send a new id for it.
+        let stale = world.spawn((HaloParticle { rank: 1 }, remote_id(7))).id();
+        world.insert_resource(IdEntityMap(BiMap::default()));
+        run_system_on_world(
+            &mut world,
+            move |mut particles: Particles<(Entity, &mut ParticleId, Option<&mut Cell>)>,
+                  mut haloes: HaloParticles<(Entity, &mut ParticleId), Without<LocalParticle>>,
+                  mut map: ResMut<IdEntityMap>| {
+                apply_new_ids(&mut particles, &mut haloes, &mut map, &new_ids)
+            },
+        );
+        let map = world.resource::<IdEntityMap>();
+        assert_eq!(map.len(), 4);
+        for (id, entity) in renumbered {
+            assert_eq!(map.get_by_left(&id), Some(&entity));
+            assert_eq!(world.get::<ParticleId>(entity), Some(&id));
+        }
+        assert!(map.get_by_right(&stale).is_none());
+        assert_eq!(world.get::<ParticleId>(stale), Some(&remote_id(7)));
+    }
+}
@@ -2,17 +2,28 @@ use bevy_ecs::prelude::*;
 use bimap::BiMap;
 
 pub mod decomposition;
+mod decomposition_overlay;
 mod exchange_data_plugin;
 pub mod extent;
+mod id_compaction;
 mod key;
 mod quadtree;
 
+use decomposition_overlay::visualize_decomposition_system;
+pub use decomposition_overlay::DecompositionOverlay;
+use derive_custom::subsweep_parameters;
 use derive_more::Deref;
 use derive_more::DerefMut;
+use derive_more::From;
+use hdf5::H5Type;
+use id_compaction::compact_particle_ids_system;
+pub use id_compaction::CompactParticleIdsLabel;
+pub use id_compaction::ParticleIdsCompacted;
 pub use key::IntoKey;
 use log::debug;
 use log::error;
 use log::info;
+use mpi::traits::Equivalence;
 pub use quadtree::LeafData;
 
 use self::decomposition::KeyCounter;
@@ -26,8 +37,14 @@ use crate::communication::CommunicatedOption;
 use crate::communication::MpiWorld;
 use crate::communication::WorldRank;
 use crate::components::Position;
+use crate::io::input::ComponentInput;
+use crate::io::to_dataset::ToDataset;
+use crate::io::DatasetDescriptor;
+use crate::io::DatasetShape;
+use crate::io::InputDatasetDescriptor;
 use crate::named::Named;
 use crate::parameters::SimulationBox;
+use crate::particle::HaloParticles;
 use crate::prelude::ParticleId;
 use crate::prelude::Particles;
 use crate::prelude::StartupStages;
@@ -35,6 +52,9 @@ use crate::prelude::WorldSize;
 use crate::quadtree::QuadTreeConfig;
 use crate::simulation::Simulation;
 use crate::simulation::SubsweepPlugin;
+use crate::simulation_plugin::Stages;
+use crate::units::Dimension;
+use crate::units::Dimensionless;
 use crate::units::VecLength;
 
 #[cfg(feature = "2d")]
@@ -45,14 +65,86 @@ pub type DecompositionState = decomposition::Decomposition<DomainKey>;
 
 pub type Work = u64;
 
+/// An estimate of the work associated with a particle, for example
+/// the number of sweep tasks it caused in a previous run. The domain
+/// decomposition balances the total work of the particles instead of
+/// their number. Read from the initial conditions if they contain it,
+/// otherwise every particle gets a work of 1.
+#[derive(H5Type, Component, Debug, Clone, Copy, Equivalence, Deref, DerefMut, From, Named)]
+#[name = "decomposition_weight"]
+#[repr(transparent)]
+pub struct DecompositionWeight(pub Work);
+
+impl ToDataset for DecompositionWeight {
+    fn dimension() -> Dimension {
+        Dimensionless::dimension()
+    }
+
+    fn convert_base_units(self, _factor: f64) -> Self {
+        self
+    }
+}
+
 #[derive(Resource, Deref, DerefMut)]
 pub struct IdEntityMap(BiMap<ParticleId, Entity>);
 
+/// Parameters of the domain decomposition.
+#[derive(Default)]
+#[subsweep_parameters("domain")]
+pub struct DomainParameters {
+    /// Renumber the particle ids on every rank at the start of a
+    /// timestep whenever particles have been removed, so that they
+    /// are contiguous again. This breaks any tracking of particles
+    /// by their id across the renumbering.
+    #[serde(default)]
+    pub compact_particle_ids: bool,
+    /// If given, the approximate region of space owned by each rank
+    /// is written to the visualization output after the domain
+    /// decomposition. The box is divided into `2^depth` cells along
+    /// each dimension for this.
+    #[serde(default)]
+    pub decomposition_overlay_depth: Option<usize>,
+}
+
 #[derive(Named)]
 pub struct DomainPlugin;
 
 impl SubsweepPlugin for DomainPlugin {
     fn build_everywhere(&self, sim: &mut Simulation) {
+        sim.add_parameter_type::<DomainParameters>()
+            .add_component::<DecompositionWeight>(
+                ComponentInput::OptionalWithDefault(InputDatasetDescriptor::new(
+                    DatasetDescriptor::default_for::<DecompositionWeight>(),
+                    DatasetShape::OneDimensional,
+                )),
+                DatasetDescriptor::default_for::<DecompositionWeight>(),
+            )
+            .add_startup_system_to_stage(
+                StartupStages::InsertDerivedComponents,
+                insert_default_decomposition_weights_system,
+            );
+        if sim
+            .get_parameters::<DomainParameters>()
+            .compact_particle_ids
+        {
+            sim.add_event::<ParticleIdsCompacted>().add_system_to_stage(
+                Stages::Initial,
+                compact_particle_ids_system.label(CompactParticleIdsLabel),
+            );
+        }
+        if sim
+            .get_parameters::<DomainParameters>()
+            .decomposition_overlay_depth
+            .is_some()
+        {
+            sim.add_startup_system_to_stage(
+                StartupStages::SetOutgoingEntities,
+                visualize_decomposition_system,
+            );
+        }
+        if cfg!(debug_assertions) {
+            sim.add_startup_system_to_stage(StartupStages::Final, check_particle_ids_system);
+        }
         sim.add_startup_system_to_stage(
             StartupStages::AssignParticleIds,
             determine_particle_ids_system,
@@ -131,31 +223,77 @@ fn determine_particle_ids_system(
     commands.insert_resource(IdEntityMap(map))
 }
 
+/// Particles which are spawned after the ids have been assigned
+/// would otherwise only cause a panic deep within the sweep.
+fn check_particle_ids_system(
+    local: Particles<(), Without<ParticleId>>,
+    halo: HaloParticles<(), Without<ParticleId>>,
+) {
+    let num_missing = local.iter().count() + halo.iter().count();
+    if num_missing > 0 {
+        panic!(
+            "{} particles have no ParticleId. Particles need to be spawned before StartupStages::AssignParticleIds.",
+            num_missing
+        );
+    }
+}
+
 fn update_id_entity_map_system(query: Query<(&ParticleId, Entity)>, mut map: ResMut<IdEntityMap>) {
     map.0 = query.iter().map(|(id, entity)| (*id, entity)).collect();
 }
 
+/// The center of the smallest cell in the simulation box which maps
+/// to `key`. Together with the cuts of the [DecompositionState] this
+/// gives the region of space owned by each rank.
+pub fn key_to_cell_center(key: DomainKey, box_: &SimulationBox) -> VecLength {
+    VecLength::new_unchecked(
+        key.cell_center(box_.min.value_unchecked(), box_.max.value_unchecked()),
+    )
+}
+
 pub fn get_decomposition_from_points_and_box(
     points: impl Iterator<Item = VecLength>,
     box_: &SimulationBox,
     world_size: usize,
+) -> DecompositionState {
+    get_decomposition_from_weighted_points_and_box(points.map(|p| (p, 1)), box_, world_size)
+}
+
+/// Like [get_decomposition_from_points_and_box], but balances the
+/// total work of the points instead of their number.
+pub fn get_decomposition_from_weighted_points_and_box(
+    points_and_weights: impl Iterator<Item = (VecLength, Work)>,
+    box_: &SimulationBox,
+    world_size: usize,
 ) -> DecompositionState {
     debug!("Computing keys");
-    let local_counter = KeyCounter::from_points_and_extent(points, &**box_);
+    let local_counter = KeyCounter::from_points_and_weights(points_and_weights, &**box_);
     debug!("Determining cutoffs");
     let mut counter = ParallelCounter::new(local_counter);
     DecompositionState::new(&mut counter, world_size)
 }
 
+fn insert_default_decomposition_weights_system(
+    mut commands: Commands,
+    particles: Particles<Entity, Without<DecompositionWeight>>,
+) {
+    for entity in particles.iter() {
+        commands.entity(entity).insert(DecompositionWeight(1));
+    }
+}
+
 fn domain_decomposition_system(
     mut commands: Commands,
     box_: Res<SimulationBox>,
-    particles: Particles<&Position>,
+    particles: Particles<(&Position, &DecompositionWeight)>,
     world_size: Res<WorldSize>,
 ) {
     info!("Starting domain decomposition");
-    let decomp =
-        get_decomposition_from_points_and_box(particles.iter().map(|x| **x), &box_, **world_size);
+    let decomp = get_decomposition_from_weighted_points_and_box(
+        particles.iter().map(|(pos, weight)| (**pos, **weight)),
+        &box_,
+        **world_size,
+    );
     decomp.log_imbalance();
     commands.insert_resource(decomp);
 }
@@ -184,3 +322,29 @@ fn set_domain_extents_system(
     let all_extents = communicate_extents(&particles);
     decomposition.set_extents(all_extents);
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::*;
+
+    use super::check_particle_ids_system;
+    use super::determine_particle_ids_system;
+    use crate::prelude::HaloParticle;
+    use crate::prelude::LocalParticle;
+    use crate::prelude::WorldRank;
+    use crate::test_utils::run_system_on_world;
+
+    #[test]
+    #[should_panic(expected = "1 particles have no ParticleId")]
+    fn particle_spawned_after_id_assignment_is_reported() {
+        let mut world = World::new();
+        world.insert_resource(WorldRank(0));
+        for _ in 0..5 {
+            world.spawn(LocalParticle);
+        }
+        run_system_on_world(&mut world, determine_particle_ids_system);
+        run_system_on_world(&mut world, check_particle_ids_system);
+        world.spawn(HaloParticle { rank: 1 });
+        run_system_on_world(&mut world, check_particle_ids_system);
+    }
+}
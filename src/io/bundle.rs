@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::Path;
+
+use hdf5::types::VarLenUnicode;
+use serde_yaml::Mapping;
+use serde_yaml::Value;
+
+use super::input::InputParameters;
+
+const PARAMETERS_IDENTIFIER: &str = "parameters";
+
+/// Writes a bundle: A single hdf5 file which contains the initial
+/// conditions referenced by the parameter file along with the
+/// contents of the parameter file itself, so that a simulation can
+/// be reproduced from just this file.
+pub fn write_bundle(parameter_file_path: &Path, bundle_path: &Path) {
+    let contents = fs::read_to_string(parameter_file_path).unwrap_or_else(|_| {
+        panic!(
+            "Failed to read parameter file at {:?}",
+            &parameter_file_path
+        )
+    });
+    let input = read_section(&contents, "input")
+        .map(|input| {
+            serde_yaml::from_value::<InputParameters>(input)
+                .expect("Failed to read input section of parameter file")
+        })
+        .unwrap_or_default();
+    let input_files: Vec<_> = input.all_input_files().collect();
+    match &input_files[..] {
+        [] => {
+            hdf5::File::create(bundle_path).expect("Failed to create bundle");
+        }
+        [input_file] => {
+            fs::copy(input_file, bundle_path).unwrap_or_else(|e| {
+                panic!("Failed to copy initial conditions {input_file:?} to bundle: {e}")
+            });
+        }
+        _ => panic!(
+            "Bundles can only be written for initial conditions in a single file, found {} files.",
+            input_files.len()
+        ),
+    }
+    let file = hdf5::File::append(bundle_path).expect("Failed to open bundle");
+    let contents: VarLenUnicode = contents.parse().unwrap();
+    file.new_attr::<VarLenUnicode>()
+        .shape(())
+        .create(PARAMETERS_IDENTIFIER)
+        .and_then(|attr| attr.write_scalar(&contents))
+        .expect("Failed to write parameters to bundle");
+}
+
+/// Returns the contents of the parameter file stored in the bundle.
+/// The paths in the input section are replaced such that the
+/// initial conditions are read from the bundle itself. All other
+/// input parameters are kept.
+pub fn read_bundle_parameters(bundle_path: &Path) -> String {
+    let file = hdf5::File::open(bundle_path)
+        .unwrap_or_else(|_| panic!("Failed to open bundle at {:?}", bundle_path));
+    let contents = file
+        .attr(PARAMETERS_IDENTIFIER)
+        .and_then(|attr| attr.read_scalar::<VarLenUnicode>())
+        .expect("Failed to read parameters from bundle");
+    let mut parameters: Value =
+        serde_yaml::from_str(contents.as_str()).expect("Failed to parse parameters in bundle");
+    let input = parameters
+        .as_mapping_mut()
+        .expect("Could not parse parameters in bundle as mapping")
+        .entry("input".into())
+        .or_insert(Value::Mapping(Mapping::default()));
+    input
+        .as_mapping_mut()
+        .expect("Could not parse input section in bundle as mapping")
+        .insert(
+            "paths".into(),
+            Value::Sequence(vec![bundle_path.to_str().unwrap().into()]),
+        );
+    serde_yaml::to_string(&parameters).unwrap()
+}
+
+fn read_section(contents: &str, section: &str) -> Option<Value> {
+    let parameters: Value = serde_yaml::from_str(contents).expect("Failed to parse parameter file");
+    parameters.get(section).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::read_bundle_parameters;
+    use super::read_section;
+    use super::write_bundle;
+    use crate::components::Mass;
+    use crate::io::input::InputParameters;
+    use crate::io::input::Reader;
+    use crate::io::InputDatasetDescriptor;
+    use crate::test_utils::temp_test_dir;
+    use crate::test_utils::tests_path;
+
+    fn read_masses(contents: &str) -> Vec<Mass> {
+        let input: InputParameters =
+            serde_yaml::from_value(read_section(contents, "input").unwrap()).unwrap();
+        Reader::full(input.all_input_files())
+            .read_dataset(InputDatasetDescriptor::<Mass>::default())
+            .collect()
+    }
+
+    #[test]
+    fn simulation_from_bundle_matches_separate_files() {
+        let dir = temp_test_dir("bundle");
+        let ics = tests_path().join("input/respect_scale_factor.hdf5");
+        let parameter_file = dir.join("parameters.yml");
+        let contents = format!(
+            "input:\n  paths:\n    - {}\n  shrink_factor: 1\nsimulation:\n  final_time: 1 s\n",
+            ics.to_str().unwrap()
+        );
+        fs::write(&parameter_file, &contents).unwrap();
+        let bundle = dir.join("bundle.hdf5");
+        write_bundle(&parameter_file, &bundle);
+        let bundle_contents = read_bundle_parameters(&bundle);
+        assert_eq!(
+            read_section(&bundle_contents, "simulation"),
+            read_section(&contents, "simulation")
+        );
+        let input = read_section(&bundle_contents, "input").unwrap();
+        assert_eq!(input.get("shrink_factor"), Some(&1.into()));
+        assert_eq!(
+            input.get("paths"),
+            Some(&vec![bundle.to_str().unwrap()].into())
+        );
+        let masses_separate = read_masses(&contents);
+        let masses_bundle = read_masses(&bundle_contents);
+        assert_eq!(masses_separate.len(), 1);
+        assert_eq!(masses_bundle.len(), masses_separate.len());
+        for (separate, bundle) in masses_separate.iter().zip(masses_bundle.iter()) {
+            assert_eq!(**separate, **bundle);
+        }
+    }
+}
@@ -1,8 +1,16 @@
+use std::marker::PhantomData;
 use std::path::Path;
 
+use bevy_ecs::prelude::Commands;
+use bevy_ecs::prelude::Res;
 use hdf5::File;
+use log::info;
 
+use super::InputParameters;
 use crate::io::output::ToAttribute;
+use crate::named::Named;
+use crate::simulation::Simulation;
+use crate::simulation::SubsweepPlugin;
 
 pub trait FromAttribute: ToAttribute {
     fn from_value(val: <Self as ToAttribute>::Output) -> Self;
@@ -12,3 +20,158 @@ pub fn read_attribute<T: FromAttribute>(file: &Path) -> T {
     let f = File::open(file).unwrap();
     T::from_value(f.attr(T::name()).unwrap().read_scalar().unwrap())
 }
+
+/// Like [read_attribute], but returns `None` if the file does not
+/// contain the attribute.
+pub fn try_read_attribute<T: FromAttribute>(file: &Path) -> Option<T> {
+    let f = File::open(file)
+        .unwrap_or_else(|_| panic!("Failed to open file: {}", file.to_str().unwrap()));
+    let attr = f.attr(T::name()).ok()?;
+    Some(T::from_value(attr.read_scalar().unwrap_or_else(|e| {
+        panic!("Failed to read attribute: {}, {e:?}", T::name())
+    })))
+}
+
+/// Reads the root attribute of `T` from the first input file and
+/// inserts it as a resource at startup.
+#[derive(Named)]
+pub struct AttributeInputPlugin<T> {
+    required: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<T> AttributeInputPlugin<T> {
+    /// Panics at startup if the first input file does not contain
+    /// the attribute.
+    pub fn required() -> Self {
+        Self {
+            required: true,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Does not insert the resource if the first input file does
+    /// not contain the attribute.
+    pub fn optional() -> Self {
+        Self {
+            required: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: FromAttribute + Sync + Send + 'static> SubsweepPlugin for AttributeInputPlugin<T> {
+    fn allow_adding_twice(&self) -> bool {
+        true
+    }
+
+    fn should_build(&self, sim: &Simulation) -> bool {
+        sim.read_initial_conditions
+    }
+
+    fn build_once_everywhere(&self, sim: &mut Simulation) {
+        sim.try_add_parameter_type::<InputParameters>();
+    }
+
+    fn build_everywhere(&self, sim: &mut Simulation) {
+        let required = self.required;
+        sim.add_startup_system(
+            move |commands: Commands, parameters: Res<InputParameters>| {
+                read_attribute_system::<T>(commands, parameters, required)
+            },
+        );
+    }
+}
+
+fn read_attribute_system<T: FromAttribute>(
+    mut commands: Commands,
+    parameters: Res<InputParameters>,
+    required: bool,
+) {
+    let file = match parameters.all_input_files().next() {
+        Some(file) => file,
+        None => panic!("No input files given to read attribute {} from", T::name()),
+    };
+    match try_read_attribute::<T>(&file) {
+        Some(attribute) => {
+            info!("Reading attribute '{}'", T::name());
+            commands.insert_resource(attribute);
+        }
+        None => {
+            if required {
+                panic!(
+                    "Missing required attribute `{}` in input file `{}`",
+                    T::name(),
+                    file.to_str().unwrap()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use bevy_ecs::prelude::Commands;
+    use bevy_ecs::prelude::Res;
+    use bevy_ecs::prelude::World;
+
+    use super::read_attribute_system;
+    use crate::cosmology::LittleH;
+    use crate::cosmology::Redshift;
+    use crate::io::input::InputParameters;
+    use crate::io::output::ToAttribute;
+    use crate::test_utils::run_system_on_world;
+    use crate::test_utils::temp_test_dir;
+    use crate::units::Dimensionless;
+
+    fn file_with_redshift() -> PathBuf {
+        let path = temp_test_dir("attribute").join("redshift.hdf5");
+        let file = hdf5::File::create(&path).unwrap();
+        let redshift = Redshift(Dimensionless::dimensionless(3.0));
+        file.new_attr::<Dimensionless>()
+            .shape(())
+            .create("redshift")
+            .unwrap()
+            .write_scalar(&redshift.to_value())
+            .unwrap();
+        path
+    }
+
+    fn read_attribute_from_file(path: PathBuf, required: bool) -> World {
+        let mut world = World::new();
+        world.insert_resource(InputParameters {
+            paths: vec![path],
+            ..Default::default()
+        });
+        run_system_on_world(
+            &mut world,
+            move |commands: Commands, parameters: Res<InputParameters>| {
+                read_attribute_system::<Redshift>(commands, parameters, required)
+            },
+        );
+        run_system_on_world(
+            &mut world,
+            move |commands: Commands, parameters: Res<InputParameters>| {
+                read_attribute_system::<LittleH>(commands, parameters, false)
+            },
+        );
+        world
+    }
+
+    #[test]
+    fn attribute_is_inserted_as_resource() {
+        let world = read_attribute_from_file(file_with_redshift(), true);
+        assert_eq!(world.resource::<Redshift>().0.value(), 3.0);
+        assert!(world.get_resource::<LittleH>().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing required attribute `redshift`")]
+    fn panic_on_missing_required_attribute() {
+        let path = temp_test_dir("no_attribute").join("empty.hdf5");
+        hdf5::File::create(&path).unwrap();
+        read_attribute_from_file(path, true);
+    }
+}
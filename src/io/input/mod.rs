@@ -2,6 +2,8 @@ pub mod attribute;
 #[cfg(test)]
 mod tests;
 
+use std::error::Error;
+use std::fmt;
 use std::fs;
 use std::ops::Range;
 use std::path::Path;
@@ -19,8 +21,10 @@ use bevy_ecs::prelude::SystemLabel;
 use derive_custom::subsweep_parameters;
 use derive_more::Deref;
 use derive_more::DerefMut;
+use hdf5::types::TypeDescriptor;
 use hdf5::Dataset;
 use hdf5::File;
+use hdf5::H5Type;
 use hdf5::Result;
 use hdf5::Selection;
 use log::info;
@@ -29,10 +33,17 @@ use ndarray::s;
 use ndarray::ArrayBase;
 use ndarray::Dim;
 use ndarray::OwnedRepr;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
 
 use super::file_distribution::get_rank_input_assignment_for_rank;
 use super::file_distribution::RankAssignment;
 use super::file_distribution::Region;
+use super::output::manifest::Manifest;
 use super::to_dataset::ToDataset;
 use super::InputDatasetDescriptor;
 use crate::communication::communicator::Communicator;
@@ -54,6 +65,11 @@ pub enum ComponentInput<T> {
     /// The component does not need to be present and will be inserted
     /// by a startup system.
     Derived,
+    /// The component is read from the given dataset in every input
+    /// file which contains it. For particles from files which do
+    /// not, it needs to be inserted by a startup system, which can
+    /// find these particles by querying for `Without<T>`.
+    OptionalWithDefault(InputDatasetDescriptor<T>),
 }
 
 /// Parameters describing how the initial conditions
@@ -64,11 +80,60 @@ pub enum ComponentInput<T> {
 pub struct InputParameters {
     /// The files containing the initial conditions
     paths: Vec<PathBuf>,
+    /// Only read about one in every `shrink_factor` particles of
+    /// the initial conditions. Useful for quickly testing a setup
+    /// at a lower resolution. Needs to be at least 1.
+    #[serde(default, deserialize_with = "deserialize_shrink_factor")]
+    shrink_factor: Option<usize>,
+    /// How the particles are selected if `shrink_factor` is given.
+    #[serde(default)]
+    shrink_mode: ShrinkMode,
+}
+
+fn deserialize_shrink_factor<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<usize>, D::Error> {
+    let shrink_factor = Option::<usize>::deserialize(deserializer)?;
+    if shrink_factor == Some(0) {
+        return Err(D::Error::custom(
+            "Invalid input parameter shrink_factor = 0: The shrink factor needs to be at least 1.",
+        ));
+    }
+    Ok(shrink_factor)
+}
+
+/// How the particles which are kept are selected if the initial
+/// conditions are shrunk.
+#[derive(Default)]
+#[subsweep_parameters]
+pub enum ShrinkMode {
+    /// Keep every n-th particle by its index in the files.
+    #[default]
+    Modulo,
+    /// Keep each particle with a probability of 1 / n. Unlike
+    /// [ShrinkMode::Modulo], this does not bias the selection if
+    /// the files are sorted, for example by position. The
+    /// selection is reproducible for a given seed and number of
+    /// ranks.
+    Random { seed: u64 },
 }
 
 #[derive(Resource)]
 pub struct NumParticlesTotal(pub usize);
 
+/// The number of entries which [Reader::read_dataset_lazy] reads
+/// from the file at once.
+const LAZY_READ_CHUNK_SIZE: usize = 1 << 16;
+
+/// Reads the dataset of `T` from the file at `path` and returns the
+/// values in the internal units, without spawning any entities.
+/// Intended for analyzing output outside of a [Simulation].
+pub fn load_components<T: ToDataset + Named>(path: &Path) -> Vec<T> {
+    Reader::full(std::iter::once(path))
+        .read_dataset(InputDatasetDescriptor::<T>::default())
+        .collect()
+}
+
 pub fn get_file_or_all_hdf5_files_in_path_if_dir(path: &Path) -> Vec<PathBuf> {
     if path.is_file() {
         vec![path.to_owned()]
@@ -97,19 +162,57 @@ impl InputParameters {
             .iter()
             .flat_map(|path| get_file_or_all_hdf5_files_in_path_if_dir(path).into_iter())
     }
+
+    /// Decides for each of the `num_entries` entries which `rank`
+    /// reads from the input files whether it is kept.
+    fn get_kept_entries(&self, num_entries: usize, rank: Rank) -> Vec<bool> {
+        let shrink_factor = match self.shrink_factor {
+            Some(shrink_factor) => shrink_factor,
+            None => return vec![true; num_entries],
+        };
+        match self.shrink_mode {
+            ShrinkMode::Modulo => (0..num_entries)
+                .map(|index| index % shrink_factor == 0)
+                .collect(),
+            ShrinkMode::Random { seed } => {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(rank as u64));
+                (0..num_entries)
+                    .map(|_| rng.gen_ratio(1, shrink_factor as u32))
+                    .collect()
+            }
+        }
+    }
 }
 
+/// The entity spawned for each entry which this rank reads from the
+/// input files, or `None` if the entry is dropped because the
+/// initial conditions are shrunk. Shared between all datasets, so
+/// that the same entries are dropped for each of them.
 #[derive(Default, Deref, DerefMut, Resource)]
-struct SpawnedEntities(Vec<Entity>);
+struct SpawnedEntities(Vec<Option<Entity>>);
 
 #[derive(Named)]
 pub struct DatasetInputPlugin<T> {
     descriptor: InputDatasetDescriptor<T>,
+    optional: bool,
 }
 
 impl<T> DatasetInputPlugin<T> {
     pub fn from_descriptor(descriptor: InputDatasetDescriptor<T>) -> Self {
-        Self { descriptor }
+        Self {
+            descriptor,
+            optional: false,
+        }
+    }
+
+    /// Reads the dataset only from the input files which contain
+    /// it. The entities from all other files are spawned without
+    /// the component.
+    pub fn optional_from_descriptor(descriptor: InputDatasetDescriptor<T>) -> Self {
+        Self {
+            descriptor,
+            optional: true,
+        }
     }
 }
 
@@ -119,9 +222,22 @@ struct ReadDatasetLabel;
 #[derive(Default, Deref, DerefMut, Resource)]
 pub struct RegisteredDatasets(HashMap<String, RegisteredDataset>);
 
+impl RegisteredDatasets {
+    fn required(&self) -> impl Iterator<Item = &RegisteredDataset> + Clone {
+        self.values().filter(|dataset| !dataset.optional)
+    }
+
+    /// The name of a required dataset. Its layout in the input
+    /// files determines the entities which are spawned.
+    fn reference_dataset_name(&self) -> Option<&str> {
+        self.required().next().map(|dataset| dataset.name.as_str())
+    }
+}
+
 #[derive(Default, Resource)]
 pub struct RegisteredDataset {
     name: String,
+    optional: bool,
 }
 
 impl<T: Named + ToDataset + Component + Sync + Send + 'static> SubsweepPlugin
@@ -147,6 +263,7 @@ impl<T: Named + ToDataset + Component + Sync + Send + 'static> SubsweepPlugin
             T::name().into(),
             RegisteredDataset {
                 name: self.descriptor.dataset_name().into(),
+                optional: self.optional,
             },
         );
         let input_plugin_for_type_been_added_previously = sim
@@ -156,12 +273,21 @@ impl<T: Named + ToDataset + Component + Sync + Send + 'static> SubsweepPlugin
         sim.insert_non_send_resource(self.descriptor.clone());
         // Only add read_dataset_system if it has not been added by another DatasetInputPlugin earlier.
         if !input_plugin_for_type_been_added_previously {
-            sim.add_startup_system(
-                read_dataset_system::<T>
-                    .after(spawn_entities_system)
-                    .label(ReadDatasetLabel)
-                    .ambiguous_with(ReadDatasetLabel),
-            );
+            if self.optional {
+                sim.add_startup_system(
+                    read_optional_dataset_system::<T>
+                        .after(spawn_entities_system)
+                        .label(ReadDatasetLabel)
+                        .ambiguous_with(ReadDatasetLabel),
+                );
+            } else {
+                sim.add_startup_system(
+                    read_dataset_system::<T>
+                        .after(spawn_entities_system)
+                        .label(ReadDatasetLabel)
+                        .ambiguous_with(ReadDatasetLabel),
+                );
+            }
         }
     }
 }
@@ -171,6 +297,23 @@ fn open_file(path: impl AsRef<Path>) -> File {
         .unwrap_or_else(|_| panic!("Failed to open file: {}", path.as_ref().to_str().unwrap()))
 }
 
+/// The required datasets which are missing in the input files,
+/// along with the names of the files they are missing in.
+#[derive(Debug)]
+pub struct MissingDatasetsError(Vec<(String, String)>);
+
+impl fmt::Display for MissingDatasetsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Missing required datasets in input files:")?;
+        for (file, name) in self.0.iter() {
+            writeln!(f, "    `{name}` in `{file}`")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for MissingDatasetsError {}
+
 pub struct Reader {
     rank: Rank,
     num_ranks: usize,
@@ -201,6 +344,16 @@ impl Reader {
         }
     }
 
+    /// Construct a reader for the contents of a snapshot which was
+    /// written with one file per rank. The files are read in the
+    /// order given in the manifest of the snapshot, after checking
+    /// that they contain all particles.
+    pub fn from_snapshot_manifest(snapshot_dir: &Path) -> Self {
+        let manifest = Manifest::read(snapshot_dir);
+        manifest.check_complete();
+        Self::full(manifest.files.iter().map(|file| snapshot_dir.join(file)))
+    }
+
     pub fn get_num_entities(&self, dataset_name: &str) -> usize {
         self.get_assignment(dataset_name)
             .regions
@@ -209,6 +362,33 @@ impl Reader {
             .sum()
     }
 
+    /// Checks that every input file which contains any of the
+    /// datasets contains all of them. Files containing none of them
+    /// are treated as empty.
+    fn check_datasets_present<'a>(
+        &self,
+        dataset_names: impl Iterator<Item = &'a str> + Clone,
+    ) -> std::result::Result<(), MissingDatasetsError> {
+        let mut missing = vec![];
+        for file in self.files.iter() {
+            let (present, absent): (Vec<_>, Vec<_>) = dataset_names
+                .clone()
+                .partition(|name| file.dataset(name).is_ok());
+            if !present.is_empty() {
+                missing.extend(
+                    absent
+                        .into_iter()
+                        .map(|name| (file.filename(), name.to_owned())),
+                );
+            }
+        }
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(MissingDatasetsError(missing))
+        }
+    }
+
     fn get_assignment(&self, dataset_name: &str) -> RankAssignment {
         let num_entries = self
             .files
@@ -242,6 +422,54 @@ impl Reader {
             .flat_map(move |region| self.read_region(descriptor.clone(), &region))
     }
 
+    /// Reads the dataset from every file which contains it and
+    /// yields `None` for each entry of the files which do not. The
+    /// entries are assigned to the ranks according to the reference
+    /// dataset, which needs to be present in all files, so that
+    /// they line up with the entries of [Reader::read_dataset] for
+    /// the reference dataset.
+    pub fn read_optional_dataset<T: ToDataset + Named>(
+        &'_ self,
+        descriptor: InputDatasetDescriptor<T>,
+        reference_dataset_name: &str,
+    ) -> impl Iterator<Item = Option<T>> + '_ {
+        let name = descriptor.dataset_name();
+        for file in self.files.iter() {
+            if file.dataset(name).is_ok() {
+                let num_entries = self.get_num_entries(name, file);
+                let num_entries_reference = self.get_num_entries(reference_dataset_name, file);
+                if num_entries != num_entries_reference {
+                    panic!(
+                        "Different lengths of datasets in file `{}`: {reference_dataset_name} ({num_entries_reference}) and {name} ({num_entries})",
+                        file.filename()
+                    );
+                }
+            }
+        }
+        let assignment = self.get_assignment(reference_dataset_name);
+        assignment.regions.into_iter().flat_map(move |region| {
+            let file = &self.files[region.file_index];
+            let values: Vec<Option<T>> = if file.dataset(descriptor.dataset_name()).is_ok() {
+                self.read_region(descriptor.clone(), &region)
+                    .map(Some)
+                    .collect()
+            } else {
+                (0..region.size()).map(|_| None).collect()
+            };
+            values
+        })
+    }
+
+    /// Reads the dataset in blocks of bounded size, so that, unlike
+    /// [Reader::read_dataset], the region of a file assigned to this
+    /// rank is never held in memory at once.
+    pub fn read_dataset_lazy<T: ToDataset + Named>(
+        &'_ self,
+        descriptor: InputDatasetDescriptor<T>,
+    ) -> impl Iterator<Item = T> + '_ {
+        self.read_dataset_chunked(descriptor, LAZY_READ_CHUNK_SIZE)
+    }
+
     pub fn read_dataset_chunked<T>(
         &'_ self,
         descriptor: InputDatasetDescriptor<T>,
@@ -318,24 +546,33 @@ fn spawn_entities_system(
     if datasets.len() == 0 {
         return;
     }
-    let (_, example_dataset) = &datasets.iter().next().unwrap();
-    let num_entities = reader.get_num_entities(&example_dataset.name);
-    for (_, dataset) in datasets.iter() {
+    if let Err(e) = reader.check_datasets_present(datasets.required().map(|d| d.name.as_str())) {
+        panic!("{e}");
+    }
+    let example_dataset = match datasets.reference_dataset_name() {
+        Some(name) => name,
+        None => panic!("No required dataset registered to determine the number of particles"),
+    };
+    let num_entities = reader.get_num_entities(example_dataset);
+    for dataset in datasets.required() {
         let num_entities_this_dataset = reader.get_num_entities(&dataset.name);
         if num_entities_this_dataset != num_entities {
             panic!(
-                "Different lengths of datasets: {} ({num_entities}) and {} ({num_entities_this_dataset})", &example_dataset.name, &dataset.name
+                "Different lengths of datasets: {} ({num_entities}) and {} ({num_entities_this_dataset})", example_dataset, &dataset.name
             );
         }
     }
+    let kept_entries = parameters.get_kept_entries(num_entities, reader.rank);
+    let num_kept = kept_entries.iter().filter(|keep| **keep).count();
     let mut comm: Communicator<usize> = Communicator::new();
-    let num_entities_total: usize = comm.all_gather_sum(&num_entities);
+    let num_entities_total: usize = comm.all_gather_sum(&num_kept);
     info!("Spawned {} particles", num_entities_total);
     commands.insert_resource(NumParticlesTotal(num_entities_total));
     performance_data.record_number("num_particles", num_entities_total);
     assert_eq!(spawned_entities.len(), 0);
-    spawned_entities.0 = (0..num_entities)
-        .map(|_| commands.spawn((LocalParticle,)).id())
+    spawned_entities.0 = kept_entries
+        .into_iter()
+        .map(|keep| keep.then(|| commands.spawn((LocalParticle,)).id()))
         .collect();
 }
 
@@ -349,11 +586,31 @@ fn read_dataset_system<T: ToDataset + Component + Named>(
     info!("Reading dataset '{}'", descriptor.dataset_name());
     for (item, entity) in reader
         .read_dataset::<T>(descriptor.clone())
-        .enumerate()
-        .map(|(_, t)| t)
         .zip(spawned_entities.iter())
     {
-        commands.entity(*entity).insert(item);
+        if let Some(entity) = entity {
+            commands.entity(*entity).insert(item);
+        }
+    }
+}
+
+fn read_optional_dataset_system<T: ToDataset + Component + Named>(
+    descriptor: NonSend<InputDatasetDescriptor<T>>,
+    mut commands: Commands,
+    spawned_entities: Res<SpawnedEntities>,
+    datasets: Res<RegisteredDatasets>,
+    parameters: Res<InputParameters>,
+) {
+    let reader = Reader::split_between_ranks(parameters.all_input_files());
+    let reference_dataset_name = datasets.reference_dataset_name().unwrap();
+    info!("Reading optional dataset '{}'", descriptor.dataset_name());
+    for (item, entity) in reader
+        .read_optional_dataset::<T>(descriptor.clone(), reference_dataset_name)
+        .zip(spawned_entities.iter())
+    {
+        if let (Some(item), Some(entity)) = (item, entity) {
+            commands.entity(*entity).insert(item);
+        }
     }
 }
 
@@ -434,9 +691,39 @@ fn read_chunk_fallible<T: ToDataset>(
             .outer_iter()
             .map(|row| constructor(row.as_slice().unwrap()))
             .collect(),
+        DatasetShape::FixedArray(len) => {
+            check_is_fixed_array_of_floats::<T>(len);
+            let data = set.read_slice_2d::<Float, _>(Selection::try_new(s![slice, ..]).unwrap())?;
+            assert_eq!(
+                data.shape()[1],
+                len,
+                "Expected {len} columns in dataset {}.",
+                descriptor.dataset_name()
+            );
+            data.outer_iter()
+                .map(|row| {
+                    // Safety: The type descriptor of T guarantees
+                    // that it has the memory layout of [Float; len].
+                    unsafe {
+                        std::ptr::read_unaligned(row.as_slice().unwrap().as_ptr() as *const T)
+                    }
+                })
+                .collect()
+        }
     })
 }
 
+fn check_is_fixed_array_of_floats<T: ToDataset>(len: usize) {
+    let is_fixed_array = match T::type_descriptor() {
+        TypeDescriptor::FixedArray(ty, n) => n == len && *ty == Float::type_descriptor(),
+        _ => false,
+    };
+    assert!(
+        is_fixed_array,
+        "Cannot read a dataset with {len} columns into a type which is not an array of {len} floats."
+    );
+}
+
 #[cfg(test)]
 mod unit_tests {
     use crate::io::file_distribution::Region;
@@ -3,21 +3,32 @@ use std::path::Path;
 use bevy_ecs::prelude::Component;
 use bevy_ecs::prelude::Query;
 use bevy_ecs::prelude::World;
+use hdf5::H5Type;
+use ndarray::Array2;
 
 use super::read_dataset_system;
+use super::ChunkIter;
 use super::InputParameters;
+use super::Reader;
 use super::SpawnedEntities;
 use crate::components::Mass;
+use crate::io::file_distribution::Region;
+use crate::io::load_components;
+use crate::io::output::add_dimension_attrs;
 use crate::io::to_dataset::ToDataset;
 use crate::io::DatasetDescriptor;
 use crate::io::DatasetShape;
 use crate::io::InputDatasetDescriptor;
+use crate::prelude::Float;
 use crate::prelude::Named;
 use crate::prelude::WorldRank;
 use crate::prelude::WorldSize;
 use crate::test_utils::assert_is_close;
 use crate::test_utils::run_system_on_world;
+use crate::test_utils::temp_test_dir;
 use crate::test_utils::tests_path;
+use crate::units::Dimension;
+use crate::units::NONE;
 use crate::units::{self};
 
 #[test]
@@ -39,6 +50,13 @@ fn check_value_system(query: Query<&Mass>) {
     assert_is_close(mass, units::Mass::solar(5.0));
 }
 
+#[test]
+fn load_components_without_world() {
+    let masses: Vec<Mass> = load_components(&tests_path().join("input/respect_scale_factor.hdf5"));
+    assert_eq!(masses.len(), 1);
+    assert_is_close(*masses[0], units::Mass::solar(5.0));
+}
+
 #[test]
 #[should_panic(expected = "Mismatch in dimension while reading dataset mass.")]
 fn panic_on_dimension_mismatch() {
@@ -52,7 +70,7 @@ fn panic_on_dimension_mismatch() {
 
 fn read_dataset_from_file<T: ToDataset + Component + Named>(world: &mut World, file: &Path) {
     let entity = world.spawn_empty().id();
-    world.insert_resource(SpawnedEntities(vec![entity]));
+    world.insert_resource(SpawnedEntities(vec![Some(entity)]));
     world.insert_resource(WorldRank(0));
     world.insert_resource(WorldSize(1));
     world.insert_resource(InputParameters {
@@ -65,3 +83,213 @@ fn read_dataset_from_file<T: ToDataset + Component + Named>(world: &mut World, f
     ));
     run_system_on_world(world, read_dataset_system::<T>);
 }
+
+#[test]
+fn lazy_read_matches_eager_read() {
+    let path = temp_test_dir("lazy_read").join("lazy_read.hdf5");
+    let num_entries = 10;
+    let data: Vec<Mass> = (0..num_entries)
+        .map(|i| Mass::from(units::Mass::kilograms(i as f64)))
+        .collect();
+    let file = hdf5::File::create(&path).unwrap();
+    let dataset = file
+        .new_dataset::<Mass>()
+        .shape(num_entries)
+        .create(Mass::name())
+        .unwrap();
+    add_dimension_attrs::<Mass>(&dataset);
+    dataset.write(&data).unwrap();
+    drop(file);
+    let reader = Reader::full([&path].into_iter());
+    let descriptor = InputDatasetDescriptor::<Mass>::default();
+    let eager: Vec<_> = reader.read_dataset(descriptor.clone()).collect();
+    assert_eq!(eager.len(), num_entries);
+    for chunk_size in [1, 3, num_entries, 2 * num_entries] {
+        let lazy: Vec<_> = reader
+            .read_dataset_chunked(descriptor.clone(), chunk_size)
+            .collect();
+        assert_eq!(lazy.len(), eager.len());
+        for (lazy, eager) in lazy.iter().zip(eager.iter()) {
+            assert_is_close(**lazy, **eager);
+        }
+        // Only a single chunk is read from the file at a time.
+        let region = Region {
+            file_index: 0,
+            start: 0,
+            end: num_entries,
+        };
+        let set = hdf5::File::open(&path)
+            .unwrap()
+            .dataset(Mass::name())
+            .unwrap();
+        for chunk in ChunkIter::new(set, &descriptor, chunk_size, &region) {
+            assert!(chunk.len() <= chunk_size);
+        }
+    }
+    let lazy: Vec<_> = reader.read_dataset_lazy(descriptor).collect();
+    assert_eq!(lazy.len(), eager.len());
+}
+
+fn shrink_parameters(contents: &str) -> InputParameters {
+    serde_yaml::from_str(&format!("paths: []\n{contents}")).unwrap()
+}
+
+#[test]
+fn modulo_shrink_keeps_every_nth_entry_by_default() {
+    let parameters = shrink_parameters("shrink_factor: 3");
+    let kept = parameters.get_kept_entries(7, 0);
+    assert_eq!(kept, [true, false, false, true, false, false, true]);
+}
+
+#[test]
+fn zero_shrink_factor_is_rejected() {
+    let err = serde_yaml::from_str::<InputParameters>("paths: []\nshrink_factor: 0")
+        .err()
+        .unwrap();
+    assert!(err
+        .to_string()
+        .contains("The shrink factor needs to be at least 1"));
+}
+
+#[test]
+fn random_shrink_is_reproducible_and_uniform() {
+    let parameters = shrink_parameters("shrink_factor: 4\nshrink_mode: !random\n  seed: 3");
+    let num_entries = 10000;
+    let kept = parameters.get_kept_entries(num_entries, 0);
+    assert_eq!(kept, parameters.get_kept_entries(num_entries, 0));
+    // Unlike the modulo mode, the kept entries should be spread
+    // evenly over both halves of the file.
+    let (first, second) = kept.split_at(num_entries / 2);
+    for half in [first, second] {
+        let num_kept = half.iter().filter(|keep| **keep).count();
+        assert!((1000..1500).contains(&num_kept));
+    }
+}
+
+#[test]
+fn all_missing_datasets_are_reported() {
+    let dir = temp_test_dir("missing_datasets");
+    let create_file = |name: &str, datasets: &[&str]| {
+        let path = dir.join(name);
+        let file = hdf5::File::create(&path).unwrap();
+        for dataset in datasets {
+            file.new_dataset::<f64>().shape(1).create(*dataset).unwrap();
+        }
+        path
+    };
+    let files = [
+        create_file("0.hdf5", &["mass", "position"]),
+        create_file("1.hdf5", &["mass"]),
+        create_file("2.hdf5", &[]),
+        create_file("3.hdf5", &["density"]),
+    ];
+    let reader = Reader::full(files.iter());
+    let err = reader
+        .check_datasets_present(["mass", "position", "density"].into_iter())
+        .unwrap_err();
+    let report = err.to_string();
+    for (file, name) in [(0, "density"), (1, "position"), (1, "density"), (3, "mass")] {
+        let line = format!("`{name}` in `{}`", files[file].to_str().unwrap());
+        assert!(report.contains(&line), "{report}");
+    }
+    assert_eq!(report.lines().count(), 6);
+}
+
+#[test]
+fn optional_dataset_is_read_only_from_files_containing_it() {
+    let dir = temp_test_dir("optional_dataset");
+    let create_file = |name: &str, masses: Option<&[f64]>| {
+        let path = dir.join(name);
+        let file = hdf5::File::create(&path).unwrap();
+        file.new_dataset::<f64>()
+            .shape(2)
+            .create("reference")
+            .unwrap();
+        if let Some(masses) = masses {
+            let data: Vec<Mass> = masses
+                .iter()
+                .map(|mass| Mass::from(units::Mass::kilograms(*mass)))
+                .collect();
+            let dataset = file
+                .new_dataset::<Mass>()
+                .shape(data.len())
+                .create(Mass::name())
+                .unwrap();
+            add_dimension_attrs::<Mass>(&dataset);
+            dataset.write(&data).unwrap();
+        }
+        path
+    };
+    let files = [
+        create_file("0.hdf5", Some(&[1.0, 2.0])),
+        create_file("1.hdf5", None),
+    ];
+    let reader = Reader::full(files.iter());
+    let masses: Vec<_> = reader
+        .read_optional_dataset(InputDatasetDescriptor::<Mass>::default(), "reference")
+        .collect();
+    assert_eq!(masses.len(), 4);
+    assert_is_close(**masses[0].as_ref().unwrap(), units::Mass::kilograms(1.0));
+    assert_is_close(**masses[1].as_ref().unwrap(), units::Mass::kilograms(2.0));
+    assert!(masses[2].is_none());
+    assert!(masses[3].is_none());
+}
+
+#[derive(H5Type, Clone, Debug, Named)]
+#[name = "abundances"]
+#[repr(transparent)]
+struct Abundances([Float; 3]);
+
+impl ToDataset for Abundances {
+    fn dimension() -> Dimension {
+        NONE
+    }
+
+    fn convert_base_units(self, _: f64) -> Self {
+        self
+    }
+}
+
+fn write_abundances(path: &Path, num_columns: usize) {
+    let num_entries = 4;
+    let data = Array2::from_shape_fn((num_entries, num_columns), |(i, j)| {
+        (i * num_columns + j) as Float
+    });
+    let file = hdf5::File::create(path).unwrap();
+    let dataset = file
+        .new_dataset::<Float>()
+        .shape((num_entries, num_columns))
+        .create(Abundances::name())
+        .unwrap();
+    add_dimension_attrs::<Abundances>(&dataset);
+    dataset.write(&data).unwrap();
+}
+
+fn read_abundances(path: &Path) -> Vec<Abundances> {
+    Reader::full([path].into_iter())
+        .read_dataset(InputDatasetDescriptor::<Abundances>::new(
+            DatasetDescriptor::default_for::<Abundances>(),
+            DatasetShape::FixedArray(3),
+        ))
+        .collect()
+}
+
+#[test]
+fn fixed_array_dataset_is_read_into_array_component() {
+    let path = temp_test_dir("fixed_array").join("fixed_array.hdf5");
+    write_abundances(&path, 3);
+    let abundances = read_abundances(&path);
+    assert_eq!(abundances.len(), 4);
+    for (i, abundance) in abundances.iter().enumerate() {
+        let expected = [0, 1, 2].map(|j| (i * 3 + j) as Float);
+        assert_eq!(abundance.0, expected);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Expected 3 columns in dataset abundances.")]
+fn fixed_array_dataset_with_wrong_number_of_columns_fails() {
+    let path = temp_test_dir("fixed_array_wrong_columns").join("fixed_array.hdf5");
+    write_abundances(&path, 2);
+    read_abundances(&path);
+}
@@ -1,14 +1,17 @@
+pub mod bundle;
 mod file_distribution;
 pub mod input;
 pub mod output;
 pub mod time_series;
 pub mod to_dataset;
+pub mod tracer;
 pub mod unit_reader;
 
 use std::marker::PhantomData;
 
 use bevy_ecs::prelude::Resource;
 use hdf5::Dataset;
+pub use input::load_components;
 pub use unit_reader::DefaultUnitReader;
 pub use unit_reader::UnitReader;
 
@@ -47,6 +50,11 @@ impl DatasetDescriptor {
 pub enum DatasetShape<T> {
     OneDimensional,
     TwoDimensional(fn(&[Float]) -> T),
+    /// A two-dimensional dataset of floats with the given number of
+    /// columns, each row of which is read into a component which
+    /// is stored as a fixed-size array of that length, such as
+    /// `[Dimensionless; N]`.
+    FixedArray(usize),
 }
 
 #[derive(Clone)]
@@ -78,16 +86,28 @@ impl<T> std::ops::Deref for InputDatasetDescriptor<T> {
     }
 }
 
+/// The precision of the floating point numbers in a dataset on
+/// disk. Independently of this, all quantities are kept in double
+/// precision in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputPrecision {
+    Single,
+    #[default]
+    Double,
+}
+
 #[derive(Clone)]
 pub struct OutputDatasetDescriptor<T> {
     _marker: PhantomData<T>,
     descriptor: DatasetDescriptor,
+    precision: OutputPrecision,
 }
 
 impl<T> OutputDatasetDescriptor<T> {
-    fn new(descriptor: DatasetDescriptor) -> Self {
+    fn new(descriptor: DatasetDescriptor, precision: OutputPrecision) -> Self {
         Self {
             descriptor,
+            precision,
             _marker: PhantomData,
         }
     }
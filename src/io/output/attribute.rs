@@ -38,10 +38,6 @@ impl<T: ToAttribute> IntoOutputSystem for Attribute<T> {
         (system, write_attribute::<T>.as_system_label())
     }
 
-    fn write_system() -> SystemDescriptor {
-        (|| {}).into_descriptor()
-    }
-
     fn is_always_desired() -> bool {
         true
     }
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use bevy_ecs::prelude::Res;
+use mpi::traits::Equivalence;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::get_output_file_name;
+use super::get_snapshot_dir;
+use super::parameters::OutputParameters;
+use super::timer::Timer;
+use super::OutputFileLayout;
+use crate::communication::MpiWorld;
+use crate::communication::Rank;
+use crate::prelude::ParticleId;
+use crate::prelude::Particles;
+use crate::prelude::WorldRank;
+
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// A contiguous range `start..end` of indices of [ParticleId]s of a
+/// single rank which is contained in one of the files of a snapshot.
+#[derive(Clone, Debug, PartialEq, Equivalence, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file_index: usize,
+    /// The `rank` of the [ParticleId]s. Since particles move
+    /// between ranks, this is not necessarily the rank which wrote
+    /// the file.
+    pub rank: Rank,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Lists the files of a snapshot which was written with one file
+/// per rank, along with the particle ids contained in each file.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub files: Vec<PathBuf>,
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn read(snapshot_dir: &Path) -> Self {
+        let path = snapshot_dir.join(MANIFEST_FILENAME);
+        let contents = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read manifest at {path:?}: {e}"));
+        serde_json::from_str(&contents)
+            .unwrap_or_else(|e| panic!("Failed to parse manifest at {path:?}: {e}"))
+    }
+
+    pub fn write(&self, snapshot_dir: &Path) {
+        let path = snapshot_dir.join(MANIFEST_FILENAME);
+        fs::write(&path, serde_json::to_string_pretty(self).unwrap())
+            .unwrap_or_else(|e| panic!("Failed to write manifest to {path:?}: {e}"));
+    }
+
+    /// Panics unless the files contain every particle with the
+    /// indices `0..n` exactly once, for every rank that assigned ids.
+    pub fn check_complete(&self) {
+        let mut ranges_by_rank: BTreeMap<Rank, Vec<(u32, u32)>> = BTreeMap::new();
+        for entry in self.entries.iter() {
+            assert!(
+                entry.file_index < self.files.len(),
+                "Manifest entry refers to file {} but only {} files are listed.",
+                entry.file_index,
+                self.files.len()
+            );
+            ranges_by_rank
+                .entry(entry.rank)
+                .or_default()
+                .push((entry.start, entry.end));
+        }
+        for (rank, mut ranges) in ranges_by_rank {
+            ranges.sort();
+            let mut num_covered = 0;
+            for (start, end) in ranges {
+                assert!(
+                    start <= num_covered,
+                    "Incomplete snapshot: Particles with ids of rank {rank} and indices {num_covered}..{start} are missing."
+                );
+                assert!(
+                    start == num_covered,
+                    "Invalid snapshot: Particles with ids of rank {rank} and indices {start}..{} are contained more than once.",
+                    end.min(num_covered)
+                );
+                num_covered = end;
+            }
+        }
+    }
+}
+
+/// Splits the indices of the ids into contiguous ranges for every
+/// rank. An id which appears more than once starts a new range, so
+/// that duplicates show up as overlapping ranges.
+fn get_manifest_entries<'a>(
+    file_index: usize,
+    ids: impl Iterator<Item = &'a ParticleId>,
+) -> Vec<ManifestEntry> {
+    let mut indices_by_rank: BTreeMap<Rank, Vec<u32>> = BTreeMap::new();
+    for id in ids {
+        indices_by_rank.entry(id.rank).or_default().push(id.index);
+    }
+    let mut entries = vec![];
+    for (rank, mut indices) in indices_by_rank {
+        indices.sort();
+        let mut current: Option<ManifestEntry> = None;
+        for index in indices {
+            if let Some(entry) = current.as_mut().filter(|entry| entry.end == index) {
+                entry.end += 1;
+                continue;
+            }
+            entries.extend(current.replace(ManifestEntry {
+                file_index,
+                rank,
+                start: index,
+                end: index + 1,
+            }));
+        }
+        entries.extend(current);
+    }
+    entries
+}
+
+pub(super) fn write_manifest_system(
+    particles: Particles<&ParticleId>,
+    rank: Res<WorldRank>,
+    parameters: Res<OutputParameters>,
+    output_timer: Res<Timer>,
+    layout: Res<OutputFileLayout>,
+) {
+    let entries = get_manifest_entries(**rank as usize, particles.iter());
+    let entries = MpiWorld::<ManifestEntry>::new().all_gather_varcount(&entries);
+    if !rank.is_main() {
+        return;
+    }
+    let snapshot_num = output_timer.snapshot_num();
+    let manifest = Manifest {
+        files: (0..layout.num_files)
+            .map(|file_index| {
+                get_output_file_name(&parameters, snapshot_num, layout.num_files, file_index).into()
+            })
+            .collect(),
+        entries,
+    };
+    manifest.write(&get_snapshot_dir(&parameters, snapshot_num));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_manifest_entries;
+    use super::Manifest;
+    use super::MANIFEST_FILENAME;
+    use crate::prelude::ParticleId;
+    use crate::test_utils::temp_test_dir;
+
+    fn manifest_of_two_files(ids: &[ParticleId]) -> Manifest {
+        let (first, second) = ids.split_at(ids.len() / 2);
+        let mut entries = get_manifest_entries(0, first.iter());
+        entries.extend(get_manifest_entries(1, second.iter()));
+        Manifest {
+            files: vec!["0.hdf5".into(), "1.hdf5".into()],
+            entries,
+        }
+    }
+
+    fn ids_of_two_ranks() -> Vec<ParticleId> {
+        (0..10)
+            .map(|index| ParticleId { index, rank: 0 })
+            .chain((0..5).map(|index| ParticleId { index, rank: 1 }))
+            .rev()
+            .collect()
+    }
+
+    #[test]
+    fn manifest_of_all_particles_is_complete() {
+        let manifest = manifest_of_two_files(&ids_of_two_ranks());
+        assert_eq!(manifest.entries.len(), 3);
+        manifest.check_complete();
+    }
+
+    #[test]
+    #[should_panic(expected = "Incomplete snapshot")]
+    fn missing_particle_is_detected() {
+        let mut ids = ids_of_two_ranks();
+        ids.retain(|id| *id != ParticleId { index: 3, rank: 0 });
+        manifest_of_two_files(&ids).check_complete();
+    }
+
+    #[test]
+    #[should_panic(expected = "contained more than once")]
+    fn duplicate_particle_is_detected() {
+        let mut ids = ids_of_two_ranks();
+        ids.push(ParticleId { index: 5, rank: 0 });
+        manifest_of_two_files(&ids).check_complete();
+    }
+
+    #[test]
+    #[should_panic(expected = "Incomplete snapshot")]
+    fn duplicate_and_missing_particle_is_detected() {
+        let mut ids = ids_of_two_ranks();
+        ids.retain(|id| *id != ParticleId { index: 3, rank: 0 });
+        ids.push(ParticleId { index: 5, rank: 0 });
+        manifest_of_two_files(&ids).check_complete();
+    }
+
+    #[test]
+    fn manifest_round_trip() {
+        let snapshot_dir = temp_test_dir("manifest");
+        let manifest = manifest_of_two_files(&ids_of_two_ranks());
+        manifest.write(&snapshot_dir);
+        let contents = std::fs::read_to_string(snapshot_dir.join(MANIFEST_FILENAME)).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(json["files"][1], "1.hdf5");
+        assert_eq!(json["entries"][0]["rank"], 0);
+        assert_eq!(json["entries"][0]["start"], 8);
+        assert_eq!(json["entries"][0]["end"], 10);
+        let read = Manifest::read(&snapshot_dir);
+        assert_eq!(read, manifest);
+        read.check_complete();
+    }
+}
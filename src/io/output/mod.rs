@@ -1,9 +1,13 @@
 mod attribute;
+pub mod manifest;
 pub(crate) mod parameters;
-pub(super) mod plugin;
+pub(crate) mod plugin;
+#[cfg(test)]
+mod tests;
 pub mod timer;
 
 use std::fs;
+use std::ops::Range;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -14,6 +18,13 @@ use bevy_ecs::prelude::ResMut;
 use bevy_ecs::prelude::Resource;
 use bevy_ecs::system::Commands;
 use bevy_ecs::system::NonSend;
+use chrono::TimeZone;
+use hdf5::filters::SZip;
+use hdf5::types::CompoundField;
+use hdf5::types::CompoundType;
+use hdf5::types::FloatSize;
+use hdf5::types::TypeDescriptor;
+use hdf5::types::VarLenUnicode;
 use hdf5::Dataset;
 use hdf5::File;
 use log::info;
@@ -22,7 +33,9 @@ use mpi::traits::Equivalence;
 
 pub use self::attribute::Attribute;
 pub use self::attribute::ToAttribute;
+use self::parameters::Compression;
 use self::parameters::OutputParameters;
+use self::parameters::TransferMode;
 pub use self::plugin::OutputPlugin;
 use self::timer::Timer;
 use super::file_distribution::Region;
@@ -30,6 +43,7 @@ use super::input::NumParticlesTotal;
 use super::to_dataset::ToDataset;
 use super::DatasetDescriptor;
 use super::OutputDatasetDescriptor;
+use super::OutputPrecision;
 use crate::communication::communicator::Communicator;
 use crate::communication::MPI_UNIVERSE;
 use crate::io::file_distribution::get_rank_output_assignment_for_rank;
@@ -37,6 +51,7 @@ use crate::io::file_distribution::RankAssignment;
 use crate::parameter_plugin::ParameterFileContents;
 use crate::prelude::Particles;
 use crate::prelude::WorldRank;
+use crate::prelude::WorldSize;
 use crate::units::Dimension;
 
 pub const SCALE_FACTOR_IDENTIFIER: &str = "scale_factor_si";
@@ -46,6 +61,15 @@ pub const MASS_IDENTIFIER: &str = "scaling_mass";
 pub const TEMPERATURE_IDENTIFIER: &str = "scaling_temperature";
 pub const H_SCALING_IDENTIFIER: &str = "scaling_h";
 pub const A_SCALING_IDENTIFIER: &str = "scaling_a";
+pub const CODE_VERSION_IDENTIFIER: &str = "code_version";
+pub const PARAMETERS_HASH_IDENTIFIER: &str = "parameters_hash";
+pub const CREATION_TIME_IDENTIFIER: &str = "creation_time";
+
+const CODE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), " (", env!("VERGEN_GIT_SHA"), ")");
+
+/// The number of entries per chunk of compressed datasets if no
+/// chunk size is given in the [OutputParameters].
+const DEFAULT_COMPRESSION_CHUNK_SIZE: usize = 1 << 16;
 
 // Output order:
 // Output proceeds as follows
@@ -56,10 +80,27 @@ pub const A_SCALING_IDENTIFIER: &str = "scaling_a";
 // 5. All ranks open files
 // 6. All ranks write data
 // 7. All ranks close files
+// Without parallel hdf5 (or with the sequential transfer mode),
+// steps 5-7 happen one rank at a time.
 
 #[derive(Default, Resource)]
 pub struct OutputFiles(pub Option<Vec<FileWithRegion>>);
 
+impl OutputFiles {
+    pub(crate) fn iter_files(&self) -> impl Iterator<Item = &File> + '_ {
+        self.0.as_ref().unwrap().iter().map(|f| &f.file)
+    }
+}
+
+/// The files of a snapshot and the region of each of them, as if
+/// a single rank wrote all of them. Used to create the files and
+/// datasets.
+#[derive(Resource)]
+pub struct OutputFileLayout {
+    regions: Vec<Region>,
+    num_files: usize,
+}
+
 #[derive(Debug)]
 pub struct FileWithRegion {
     file: File,
@@ -96,7 +137,8 @@ pub fn make_output_dirs(parameters: &OutputParameters) {
                 fs::remove_dir_all(&parameters.output_dir)
                     .unwrap_or_else(|e| panic!("Failed to remove output directory. {e}"));
             }
-            parameters::HandleExistingOutput::Overwrite => {}
+            parameters::HandleExistingOutput::Overwrite
+            | parameters::HandleExistingOutput::Append => {}
         }
     }
     fs::create_dir_all(&parameters.output_dir)
@@ -117,6 +159,7 @@ fn make_snapshot_dir(snapshot_dir: &Path) {
 pub fn compute_output_rank_assignment_system(
     mut commands: Commands,
     rank: Res<WorldRank>,
+    world_size: Res<WorldSize>,
     parameters: Res<OutputParameters>,
     particles: Particles<Entity>,
     num_particles_total: Res<NumParticlesTotal>,
@@ -131,40 +174,110 @@ pub fn compute_output_rank_assignment_system(
         num_particles_per_rank.iter().sum::<usize>(),
         num_particles_total.0
     );
-    let rank_assignment = get_rank_output_assignment_for_rank(
-        &num_particles_per_rank,
-        parameters.num_output_files,
-        **rank,
-    );
-    commands.insert_resource(rank_assignment);
+    if parameters.one_file_per_rank() {
+        let regions: Vec<_> = num_particles_per_rank
+            .iter()
+            .enumerate()
+            .map(|(file_index, num_particles)| Region {
+                file_index,
+                start: 0,
+                end: *num_particles,
+            })
+            .collect();
+        let own_region = Some(regions[**rank as usize].clone()).filter(|region| region.size() > 0);
+        commands.insert_resource(RankAssignment {
+            regions: own_region.into_iter().collect(),
+        });
+        commands.insert_resource(OutputFileLayout {
+            regions,
+            num_files: **world_size,
+        });
+    } else {
+        let rank_assignment = get_rank_output_assignment_for_rank(
+            &num_particles_per_rank,
+            parameters.num_output_files,
+            **rank,
+        );
+        commands.insert_resource(rank_assignment);
+        // In order to know how large the datasets are that we need to create:
+        // Compute rank assignment for one rank.
+        let assignment = get_rank_output_assignment_for_rank(
+            &[num_particles_total.0],
+            parameters.num_output_files,
+            0,
+        );
+        commands.insert_resource(OutputFileLayout {
+            regions: assignment.regions,
+            num_files: parameters.num_output_files,
+        });
+    }
 }
 
-fn get_snapshot_dir(parameters: &OutputParameters, output_timer: &Timer) -> PathBuf {
+pub(crate) fn get_snapshot_dir(parameters: &OutputParameters, snapshot_num: usize) -> PathBuf {
     let snapshot_name = format!(
         "{:0snap_padding$}",
-        output_timer.snapshot_num(),
+        snapshot_num,
         snap_padding = parameters.snapshot_padding
     );
     parameters.snapshot_dir().join(&snapshot_name)
 }
 
+/// With one file per rank, the files are named
+/// `snapshot_{snapshot_num}.rank_{rank}.hdf5`, otherwise only by
+/// their index.
+pub(crate) fn get_output_file_name(
+    parameters: &OutputParameters,
+    snapshot_num: usize,
+    num_files: usize,
+    file_index: usize,
+) -> String {
+    if parameters.one_file_per_rank() {
+        return format!(
+            "snapshot_{:0snap_padding$}.rank_{}.hdf5",
+            snapshot_num,
+            file_index,
+            snap_padding = parameters.snapshot_padding
+        );
+    }
+    let file_index_padding = ((num_files as f64).log10().floor() as usize) + 1;
+    format!(
+        "{:0file_index_padding$}.hdf5",
+        file_index,
+        file_index_padding = file_index_padding
+    )
+}
+
+/// Returns the number following the highest number among the
+/// existing snapshots in the snapshot directory.
+pub(crate) fn get_next_free_snapshot_num(snapshot_dir: &Path) -> usize {
+    let entries = match fs::read_dir(snapshot_dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<usize>().ok())
+        .max()
+        .map(|num| num + 1)
+        .unwrap_or(0)
+}
+
 fn get_output_files(
     parameters: &OutputParameters,
     output_timer: &Timer,
-    assignment: &RankAssignment,
+    regions: &[Region],
+    num_files: usize,
     get_file: impl Fn(PathBuf) -> hdf5::Result<File>,
 ) -> Vec<FileWithRegion> {
-    let file_index_padding = ((parameters.num_output_files as f64).log10().floor() as usize) + 1;
-    let snapshot_dir = get_snapshot_dir(parameters, output_timer);
+    let snapshot_dir = get_snapshot_dir(parameters, output_timer.snapshot_num());
     make_snapshot_dir(&snapshot_dir);
-    assignment
-        .regions
+    regions
         .iter()
         .map(|region| {
-            let filename = &format!(
-                "{:0file_index_padding$}.hdf5",
+            let filename = get_output_file_name(
+                parameters,
+                output_timer.snapshot_num(),
+                num_files,
                 region.file_index,
-                file_index_padding = file_index_padding
             );
             let file = get_file(snapshot_dir.join(filename)).expect("Failed to open output file");
             FileWithRegion {
@@ -178,26 +291,65 @@ fn get_output_files(
 fn create_file_system(
     mut file: ResMut<OutputFiles>,
     parameters: Res<OutputParameters>,
+    parameter_file_contents: Res<ParameterFileContents>,
     output_timer: Res<Timer>,
-    num_particles_total: Res<NumParticlesTotal>,
+    layout: Res<OutputFileLayout>,
     _rank: Res<WorldRank>,
 ) {
     info!("Writing snapshot: {}", &output_timer.snapshot_num());
     assert!(file.0.is_none());
-
-    // In order to know how large the datasets are that we need to create:
-    // Compute rank assignment for one rank.
-    let assignment = get_rank_output_assignment_for_rank(
-        &[num_particles_total.0],
-        parameters.num_output_files,
-        0,
-    );
-    file.0 = Some(get_output_files(
+    let independent = !parameters.uses_mpi_io();
+    let files = get_output_files(
         &parameters,
         &output_timer,
-        &assignment,
-        create_file_rw,
-    ));
+        &layout.regions,
+        layout.num_files,
+        |path| create_file_rw(path, independent),
+    );
+    write_provenance_attributes(&files, parameter_file_contents.hash(), &creation_time());
+    file.0 = Some(files);
+}
+
+/// The current time on the root rank. With MPI-IO, attributes are
+/// written collectively and need to be identical on all ranks.
+fn creation_time() -> String {
+    let millis =
+        Communicator::<i64>::new().broadcast_from_root(&chrono::Utc::now().timestamp_millis());
+    chrono::Utc
+        .timestamp_millis_opt(millis)
+        .unwrap()
+        .to_rfc3339()
+}
+
+/// Writes the version of the code, a hash of the merged parameters
+/// and the time of creation to the root of each file, so that a
+/// snapshot can be matched to the run which produced it.
+pub(crate) fn write_provenance_attributes(
+    files: &[FileWithRegion],
+    parameters_hash: u64,
+    creation_time: &str,
+) {
+    let code_version: VarLenUnicode = CODE_VERSION.parse().unwrap();
+    let creation_time: VarLenUnicode = creation_time.parse().unwrap();
+    for FileWithRegion { file, .. } in files.iter() {
+        for (identifier, value) in [
+            (CODE_VERSION_IDENTIFIER, &code_version),
+            (CREATION_TIME_IDENTIFIER, &creation_time),
+        ] {
+            file.new_attr::<VarLenUnicode>()
+                .shape(())
+                .create(identifier)
+                .and_then(|attr| attr.write_scalar(value))
+                .unwrap_or_else(|e| panic!("Failed to write attribute {identifier}: {e}"));
+        }
+        file.new_attr::<u64>()
+            .shape(())
+            .create(PARAMETERS_HASH_IDENTIFIER)
+            .and_then(|attr| attr.write_scalar(&parameters_hash))
+            .unwrap_or_else(|e| {
+                panic!("Failed to write attribute {PARAMETERS_HASH_IDENTIFIER}: {e}")
+            });
+    }
 }
 
 #[cfg(feature = "parallel-hdf5")]
@@ -219,27 +371,33 @@ fn make_mpi_file_builder() -> hdf5::FileBuilder {
 }
 
 #[cfg(feature = "parallel-hdf5")]
-fn create_file_rw(path: PathBuf) -> hdf5::Result<File> {
+fn create_file_rw(path: PathBuf, independent: bool) -> hdf5::Result<File> {
     use hdf5::plist;
 
+    if independent {
+        return File::create(path);
+    }
     let mut builder = make_mpi_file_builder();
     let fcpl = plist::FileCreate::build().finish().unwrap();
     builder.set_create_plist(&fcpl).unwrap().create(path)
 }
 
 #[cfg(feature = "parallel-hdf5")]
-fn open_file_rw(path: PathBuf) -> hdf5::Result<File> {
+fn open_file_rw(path: PathBuf, independent: bool) -> hdf5::Result<File> {
+    if independent {
+        return File::open_rw(path);
+    }
     let builder = make_mpi_file_builder();
     builder.open_rw(path)
 }
 
 #[cfg(not(feature = "parallel-hdf5"))]
-fn create_file_rw(path: PathBuf) -> hdf5::Result<File> {
+fn create_file_rw(path: PathBuf, _independent: bool) -> hdf5::Result<File> {
     File::create(path)
 }
 
 #[cfg(not(feature = "parallel-hdf5"))]
-fn open_file_rw(path: PathBuf) -> hdf5::Result<File> {
+fn open_file_rw(path: PathBuf, _independent: bool) -> hdf5::Result<File> {
     File::open_rw(path)
 }
 
@@ -248,13 +406,16 @@ fn open_file_system(
     parameters: Res<OutputParameters>,
     output_timer: Res<Timer>,
     assignment: Res<RankAssignment>,
+    layout: Res<OutputFileLayout>,
 ) {
     assert!(file.0.is_none());
+    let independent = !parameters.uses_mpi_io();
     file.0 = Some(get_output_files(
         &parameters,
         &output_timer,
-        &assignment,
-        open_file_rw,
+        &assignment.regions,
+        layout.num_files,
+        |path| open_file_rw(path, independent),
     ))
 }
 
@@ -265,40 +426,144 @@ fn close_file_system(mut file: ResMut<OutputFiles>) {
 pub fn create_dataset_system<T: Component + ToDataset>(
     file: ResMut<OutputFiles>,
     descriptor: NonSend<OutputDatasetDescriptor<T>>,
+    parameters: Res<OutputParameters>,
+    output_timer: Res<Timer>,
+    layout: Res<OutputFileLayout>,
 ) {
+    // When appending to the output of a previous run, the first
+    // snapshot of this run does not start at zero.
+    if output_timer.is_first_snapshot() && output_timer.snapshot_num() > 0 {
+        let previous_snapshot_num = output_timer.snapshot_num() - 1;
+        let previous_snapshot = get_snapshot_dir(&parameters, previous_snapshot_num).join(
+            get_output_file_name(&parameters, previous_snapshot_num, layout.num_files, 0),
+        );
+        check_dimension_of_existing_dataset::<T>(&previous_snapshot, &descriptor);
+    }
     let files = file.0.as_ref().unwrap();
-    create_dataset_in_files::<T>(files, &descriptor);
+    create_dataset_in_files::<T>(
+        files,
+        &descriptor,
+        parameters.chunk_size,
+        descriptor.precision,
+        parameters.compression,
+    );
 }
 
+/// Panics if the file at `path` contains the dataset with a
+/// dimension which differs from that of `T`.
+pub(crate) fn check_dimension_of_existing_dataset<T: ToDataset>(
+    path: &Path,
+    descriptor: &DatasetDescriptor,
+) {
+    let name = descriptor.dataset_name();
+    if let Ok(set) = File::open(path).and_then(|file| file.dataset(name)) {
+        assert_eq!(
+            descriptor.read_dimension(&set),
+            T::dimension(),
+            "Cannot append to existing output: dataset {name} in {path:?} has a different dimension.",
+        );
+    }
+}
+
+/// Creates the dataset with the given precision on disk. Data is
+/// always passed to the write functions in double precision; if
+/// the dataset is single precision, hdf5 converts the values while
+/// writing. The scale factor attribute refers to the values
+/// regardless of their precision, so reading the dataset back into
+/// `T` yields the same units.
 pub fn create_dataset_in_files<T: ToDataset>(
     files: &[FileWithRegion],
     descriptor: &DatasetDescriptor,
+    chunk_size: Option<usize>,
+    precision: OutputPrecision,
+    compression: Option<Compression>,
 ) {
+    let type_descriptor = match precision {
+        OutputPrecision::Single => to_single_precision(T::type_descriptor()),
+        OutputPrecision::Double => T::type_descriptor(),
+    };
     for FileWithRegion { file, region } in files.iter() {
         assert!(region.start == 0);
-        let dataset = file
-            .new_dataset::<T>()
-            .shape(&[region.end - region.start])
+        let size = region.end - region.start;
+        let mut builder = file
+            .new_dataset_builder()
+            .empty_as(&type_descriptor)
+            .shape(&[size]);
+        // Chunks cannot be larger than a fixed-size dataset and
+        // empty datasets cannot be chunked at all. Filters can only
+        // be applied to chunked datasets.
+        let chunk_size = match compression {
+            Some(_) => Some(chunk_size.unwrap_or(DEFAULT_COMPRESSION_CHUNK_SIZE)),
+            None => chunk_size,
+        };
+        if let Some(chunk_size) = chunk_size.filter(|_| size > 0) {
+            builder = builder.chunk(chunk_size.min(size));
+            builder = match compression {
+                Some(Compression::Gzip(level)) => builder.deflate(level),
+                Some(Compression::Szip) => builder.szip(SZip::NearestNeighbor, 8),
+                None => builder,
+            };
+        }
+        let dataset = builder
             .create(descriptor.dataset_name())
             .expect("Failed to create dataset");
         add_dimension_attrs::<T>(&dataset);
     }
 }
 
+/// Replaces all floating point numbers within the type by single
+/// precision floats.
+fn to_single_precision(descriptor: TypeDescriptor) -> TypeDescriptor {
+    match descriptor {
+        TypeDescriptor::Float(_) => TypeDescriptor::Float(FloatSize::U4),
+        TypeDescriptor::FixedArray(ty, len) => {
+            TypeDescriptor::FixedArray(Box::new(to_single_precision(*ty)), len)
+        }
+        TypeDescriptor::Compound(compound) => {
+            let fields = compound
+                .fields
+                .into_iter()
+                .map(|field| CompoundField {
+                    ty: to_single_precision(field.ty),
+                    ..field
+                })
+                .collect();
+            // The offsets of the fields change with their size.
+            TypeDescriptor::Compound(
+                CompoundType {
+                    fields,
+                    size: compound.size,
+                }
+                .to_packed_repr(),
+            )
+        }
+        descriptor => descriptor,
+    }
+}
+
 pub fn write_dataset_system<T: Component + ToDataset>(
     query: Particles<&T>,
     file: ResMut<OutputFiles>,
     descriptor: NonSend<OutputDatasetDescriptor<T>>,
+    parameters: Res<OutputParameters>,
 ) {
     let files = file.0.as_ref().unwrap();
     let data: Vec<T> = query.iter().cloned().collect();
-    write_dataset_to_files(data, files, &descriptor);
+    // Files which are not opened via MPI-IO cannot be written with
+    // an MPI-IO transfer mode.
+    let transfer_mode = if parameters.uses_mpi_io() {
+        parameters.transfer_mode
+    } else {
+        TransferMode::Sequential
+    };
+    write_dataset_to_files(data, files, &descriptor, transfer_mode);
 }
 
 pub fn write_dataset_to_files<T: ToDataset>(
     data: Vec<T>,
     files: &[FileWithRegion],
     descriptor: &DatasetDescriptor,
+    transfer_mode: TransferMode,
 ) {
     let mut data_start = 0;
     for FileWithRegion { file, region } in files.iter() {
@@ -306,14 +571,94 @@ pub fn write_dataset_to_files<T: ToDataset>(
             .dataset(&descriptor.dataset_name())
             .expect("Failed to open dataset");
         let data_end = data_start + region.size();
-        dataset
-            .write_slice(&data[data_start..data_end], region.start..region.end)
-            .expect("Failed to write slice to dataset");
+        write_slice(
+            &dataset,
+            &data[data_start..data_end],
+            region.start..region.end,
+            transfer_mode,
+        )
+        .expect("Failed to write slice to dataset");
         data_start += region.size();
     }
     assert_eq!(data_start, data.len());
 }
 
+#[cfg(feature = "parallel-hdf5")]
+fn write_slice<T: ToDataset>(
+    dataset: &Dataset,
+    data: &[T],
+    range: Range<usize>,
+    transfer_mode: TransferMode,
+) -> hdf5::Result<()> {
+    use hdf5::Dataspace;
+    use hdf5::Datatype;
+    use hdf5_sys::h5d::H5Dwrite;
+    use hdf5_sys::h5fd::H5FD_mpio_xfer_t;
+    use hdf5_sys::h5fd::H5Pset_dxpl_mpio;
+    use hdf5_sys::h5p::H5Pcreate;
+    use hdf5_sys::h5p::H5P_CLS_DATASET_XFER;
+
+    // The high-level hdf5 API always writes with the default
+    // transfer property list, so the transfer mode has to be set
+    // on a property list of our own.
+    let xfer_mode = match transfer_mode {
+        TransferMode::Independent => H5FD_mpio_xfer_t::H5FD_MPIO_INDEPENDENT,
+        TransferMode::Collective => H5FD_mpio_xfer_t::H5FD_MPIO_COLLECTIVE,
+        TransferMode::Sequential => return dataset.write_slice(data, range),
+    };
+    let datatype = Datatype::from_type::<T>()?;
+    let file_space = dataset.space()?.select(range)?;
+    let mem_space = Dataspace::try_new(data.len())?;
+    // All calls into the hdf5 library need to hold its lock.
+    hdf5::sync::sync(|| unsafe {
+        let dxpl = H5Pcreate(*H5P_CLS_DATASET_XFER);
+        if dxpl < 0 {
+            return Err(format!("H5Pcreate failed with id {dxpl}").into());
+        }
+        let dxpl = TransferPropertyList(dxpl);
+        let status = H5Pset_dxpl_mpio(dxpl.0, xfer_mode);
+        if status < 0 {
+            return Err(format!("H5Pset_dxpl_mpio failed with status {status}").into());
+        }
+        let status = H5Dwrite(
+            dataset.id(),
+            datatype.id(),
+            mem_space.id(),
+            file_space.id(),
+            dxpl.0,
+            data.as_ptr().cast(),
+        );
+        if status < 0 {
+            return Err(format!("H5Dwrite failed with status {status}").into());
+        }
+        Ok(())
+    })
+}
+
+/// Closes the wrapped transfer property list when dropped, so that
+/// it is closed on every path out of [write_slice].
+#[cfg(feature = "parallel-hdf5")]
+struct TransferPropertyList(hdf5_sys::h5i::hid_t);
+
+#[cfg(feature = "parallel-hdf5")]
+impl Drop for TransferPropertyList {
+    fn drop(&mut self) {
+        hdf5::sync::sync(|| unsafe {
+            hdf5_sys::h5p::H5Pclose(self.0);
+        });
+    }
+}
+
+#[cfg(not(feature = "parallel-hdf5"))]
+fn write_slice<T: ToDataset>(
+    dataset: &Dataset,
+    data: &[T],
+    range: Range<usize>,
+    _transfer_mode: TransferMode,
+) -> hdf5::Result<()> {
+    dataset.write_slice(data, range)
+}
+
 pub fn add_dimension_attrs<T: ToDataset>(dataset: &Dataset) {
     let attr = dataset
         .new_attr::<f64>()
@@ -351,47 +696,80 @@ fn write_dimension(dataset: &Dataset, identifier: &str, dimension: i32) {
     attr.write_scalar(&dimension).unwrap();
 }
 
-#[cfg(feature = "parallel-hdf5")]
-pub fn init_wait_for_other_ranks_system(mut perf: ResMut<crate::performance::Performance>) {
-    // Make sure all ranks wait for the main rank to arrive who
-    // creates the datasets
-
-    perf.start("output_dataset");
+/// Blocks until all ranks with a lower rank number have written
+/// their data. Together with [wait_for_subsequent_ranks], this
+/// passes a token from one rank to the next, so that only one rank
+/// accesses the files at a time.
+fn wait_for_previous_ranks(world_size: usize, rank: usize) {
+    let world = MPI_UNIVERSE.world();
+    for _ in 0..rank.min(world_size) {
+        world.barrier();
+    }
+}
 
+fn wait_for_subsequent_ranks(world_size: usize, rank: usize) {
     let world = MPI_UNIVERSE.world();
-    world.barrier();
+    for _ in rank.min(world_size)..world_size {
+        world.barrier();
+    }
 }
 
 #[cfg(feature = "parallel-hdf5")]
-pub fn finish_wait_for_other_ranks_system(mut perf: ResMut<crate::performance::Performance>) {
+pub fn init_wait_for_other_ranks_system(
+    mut perf: ResMut<crate::performance::Performance>,
+    parameters: Res<OutputParameters>,
+    world_size: Res<crate::prelude::WorldSize>,
+    rank: Res<WorldRank>,
+) {
+    perf.start("output_dataset");
+    if parameters.writes_sequentially() {
+        wait_for_previous_ranks(**world_size, **rank as usize);
+    } else {
+        // Make sure all ranks wait for the main rank to arrive who
+        // creates the datasets
+        let world = MPI_UNIVERSE.world();
+        world.barrier();
+    }
+}
+
+#[cfg(feature = "parallel-hdf5")]
+pub fn finish_wait_for_other_ranks_system(
+    mut perf: ResMut<crate::performance::Performance>,
+    parameters: Res<OutputParameters>,
+    world_size: Res<crate::prelude::WorldSize>,
+    rank: Res<WorldRank>,
+) {
+    if parameters.writes_sequentially() {
+        wait_for_subsequent_ranks(**world_size, **rank as usize);
+    }
     perf.stop("output_dataset");
 }
 
 #[cfg(not(feature = "parallel-hdf5"))]
 pub fn init_wait_for_other_ranks_system(
+    parameters: Res<OutputParameters>,
     world_size: Res<crate::prelude::WorldSize>,
     rank: Res<WorldRank>,
 ) {
+    if parameters.one_file_per_rank() {
+        // Make sure all ranks wait for the main rank to arrive who
+        // creates the files
+        MPI_UNIVERSE.world().barrier();
+        return;
+    }
     if **world_size > 10 {
         log::warn!("Serial hdf5 output is very slow on many ranks, try compiling with the parallel-hdf5 feature enabled")
     }
-    let world = MPI_UNIVERSE.world();
-    for i in 0..**world_size {
-        if i < **rank as usize {
-            world.barrier();
-        }
-    }
+    wait_for_previous_ranks(**world_size, **rank as usize);
 }
 
 #[cfg(not(feature = "parallel-hdf5"))]
 pub fn finish_wait_for_other_ranks_system(
+    parameters: Res<OutputParameters>,
     world_size: Res<crate::prelude::WorldSize>,
     rank: Res<WorldRank>,
 ) {
-    let world = MPI_UNIVERSE.world();
-    for i in 0..**world_size {
-        if i >= **rank as usize {
-            world.barrier();
-        }
+    if !parameters.one_file_per_rank() {
+        wait_for_subsequent_ranks(**world_size, **rank as usize);
     }
 }
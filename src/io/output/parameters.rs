@@ -21,6 +21,58 @@ pub enum HandleExistingOutput {
     /// Delete the existing output folder. This will erase all
     /// data of the previous simulation.
     Delete,
+    /// Keep the existing snapshots and number the new snapshots
+    /// starting after the last existing one. Useful when restarting
+    /// a simulation.
+    Append,
+}
+
+/// How the ranks write their data to the output files when
+/// compiled with parallel hdf5.
+#[derive(Default, Copy)]
+#[subsweep_parameters]
+pub enum TransferMode {
+    /// Every rank writes its part of a dataset on its own.
+    #[default]
+    Independent,
+    /// All ranks take part in every write, which allows the MPI-IO
+    /// layer to aggregate the requests. Whether this is faster
+    /// depends on the file system.
+    Collective,
+    /// Do not use MPI-IO at all. Instead, the ranks take turns
+    /// writing their data into the shared files at their global
+    /// offsets. This is slow, but works on file systems on which
+    /// MPI-IO is unreliable. Without parallel hdf5, output is always
+    /// written this way.
+    Sequential,
+}
+
+/// How the particles are distributed over the files of a snapshot.
+#[derive(Default, Copy)]
+#[subsweep_parameters]
+pub enum WriteMode {
+    /// The particles of all ranks are distributed evenly over
+    /// `num_output_files` files.
+    #[default]
+    Shared,
+    /// Every rank writes its particles to a file of its own without
+    /// waiting for the other ranks. The files are named
+    /// `snapshot_{num}.rank_{rank}.hdf5`. A manifest which lists the
+    /// particle ids contained in every file is written alongside.
+    OnePerRank,
+}
+
+/// The filter with which the output datasets are compressed.
+/// Compression is transparent to readers of the snapshots.
+#[derive(Copy)]
+#[subsweep_parameters]
+pub enum Compression {
+    /// Deflate with the given level between 0 (fastest) and 9
+    /// (smallest).
+    Gzip(u8),
+    /// Szip with nearest neighbour coding. Requires hdf5 to be
+    /// built with szip support.
+    Szip,
 }
 
 #[subsweep_parameters]
@@ -54,6 +106,10 @@ pub struct OutputParameters {
     /// to which the time series are written
     #[serde(default = "default_time_series_dir")]
     pub time_series_dir: PathBuf,
+    /// The name of the sub-directory of the output directory
+    /// to which the tracer trajectories are written
+    #[serde(default = "default_tracers_dir")]
+    pub tracers_dir: PathBuf,
     /// Either 'all' or the names of all the fields that should be written to snapshots.
     /// Can be names of both attributes and datasets. Example values:
     /// ["position", "velocity", "time"]
@@ -78,6 +134,31 @@ pub struct OutputParameters {
     #[serde(default = "default_num_output_files")]
     /// The number of output files per snapshot. Default: 1
     pub num_output_files: usize,
+    /// The number of entries per chunk of the output datasets. If
+    /// None, the datasets are stored contiguously.
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+    /// How the datasets are written. Only has an effect if compiled
+    /// with the parallel-hdf5 feature.
+    #[serde(default)]
+    pub transfer_mode: TransferMode,
+    /// Whether the ranks share the output files or every rank
+    /// writes its own file. If every rank writes its own file,
+    /// `num_output_files` is ignored.
+    #[serde(default)]
+    pub write_mode: WriteMode,
+    /// The filter with which the datasets are compressed. Compressed
+    /// datasets are always chunked, using `chunk_size` if given. With
+    /// parallel hdf5, writing compressed datasets requires the
+    /// collective transfer mode.
+    #[serde(default)]
+    pub compression: Option<Compression>,
+    /// The number of output steps for which the entries of the time
+    /// series are buffered before they are appended to their files.
+    /// The remaining entries are always written when the simulation
+    /// stops.
+    #[serde(default = "default_time_series_flush_every")]
+    pub time_series_flush_every: usize,
 }
 
 fn default_snapshot_padding() -> usize {
@@ -96,6 +177,10 @@ fn default_time_series_dir() -> PathBuf {
     "time_series".into()
 }
 
+fn default_tracers_dir() -> PathBuf {
+    "tracers".into()
+}
+
 fn default_fields() -> Fields {
     Fields::All
 }
@@ -112,6 +197,10 @@ fn default_num_output_files() -> usize {
     1
 }
 
+fn default_time_series_flush_every() -> usize {
+    1
+}
+
 pub fn is_desired_field<T: Named + IntoOutputSystem>(sim: &Simulation) -> bool {
     T::is_always_desired()
         || sim
@@ -127,6 +216,26 @@ impl OutputParameters {
         }
     }
 
+    /// Whether the ranks take turns writing to the output files
+    /// instead of writing in parallel.
+    pub fn writes_sequentially(&self) -> bool {
+        !self.one_file_per_rank()
+            && (!cfg!(feature = "parallel-hdf5")
+                || matches!(self.transfer_mode, TransferMode::Sequential))
+    }
+
+    pub fn one_file_per_rank(&self) -> bool {
+        matches!(self.write_mode, WriteMode::OnePerRank)
+    }
+
+    /// Whether the output files are accessed by all ranks
+    /// collectively via MPI-IO. Otherwise, the files and datasets
+    /// are created by the main rank and every rank opens the files
+    /// it writes to on its own.
+    pub fn uses_mpi_io(&self) -> bool {
+        !self.writes_sequentially() && !self.one_file_per_rank()
+    }
+
     pub fn snapshot_dir(&self) -> PathBuf {
         self.output_dir.join(&self.snapshots_dir)
     }
@@ -134,4 +243,8 @@ impl OutputParameters {
     pub fn time_series_dir(&self) -> PathBuf {
         self.output_dir.join(&self.time_series_dir)
     }
+
+    pub fn tracers_dir(&self) -> PathBuf {
+        self.output_dir.join(&self.tracers_dir)
+    }
 }
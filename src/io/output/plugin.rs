@@ -8,15 +8,19 @@ use super::compute_output_rank_assignment_system;
 use super::create_file_system;
 use super::finish_wait_for_other_ranks_system;
 use super::init_wait_for_other_ranks_system;
+use super::manifest::write_manifest_system;
 use super::open_file_system;
 use super::parameters::is_desired_field;
 use super::parameters::Fields;
 use super::parameters::OutputParameters;
+use super::timer::DatasetTimer;
+use super::timer::OutputCadence;
 use super::timer::Timer;
 use super::write_used_parameters_system;
 use super::OutputFiles;
 use crate::io::DatasetDescriptor;
 use crate::io::OutputDatasetDescriptor;
+use crate::io::OutputPrecision;
 use crate::named::Named;
 use crate::prelude::Simulation;
 use crate::prelude::Stages;
@@ -27,7 +31,12 @@ use crate::simulation::SubsweepPlugin;
 pub struct OutputDataMarker;
 
 pub(crate) trait IntoOutputSystem {
-    fn write_system() -> SystemDescriptor;
+    /// The system which writes the data of each rank to the datasets
+    /// created by [Self::create_system]. Not needed if all data is
+    /// already written when the dataset is created.
+    fn write_system() -> Option<SystemDescriptor> {
+        None
+    }
     fn create_system() -> (SystemDescriptor, SystemLabelId);
     fn is_always_desired() -> bool;
 }
@@ -41,12 +50,17 @@ struct RegisteredFields(pub Vec<String>);
 #[derive(Named)]
 pub struct OutputPlugin<T> {
     descriptor: OutputDatasetDescriptor<T>,
+    cadence: OutputCadence,
 }
 
 impl<T: Named> Default for OutputPlugin<T> {
     fn default() -> Self {
         Self {
-            descriptor: OutputDatasetDescriptor::<T>::new(DatasetDescriptor::default_for::<T>()),
+            descriptor: OutputDatasetDescriptor::<T>::new(
+                DatasetDescriptor::default_for::<T>(),
+                OutputPrecision::default(),
+            ),
+            cadence: OutputCadence::default(),
         }
     }
 }
@@ -54,9 +68,31 @@ impl<T: Named> Default for OutputPlugin<T> {
 impl<T> OutputPlugin<T> {
     pub fn from_descriptor(descriptor: DatasetDescriptor) -> Self {
         Self {
-            descriptor: OutputDatasetDescriptor::<T>::new(descriptor),
+            descriptor: OutputDatasetDescriptor::<T>::new(descriptor, OutputPrecision::default()),
+            cadence: OutputCadence::default(),
         }
     }
+
+    /// Sets how often this dataset is written. Only affects
+    /// datasets, attributes are written to every snapshot.
+    pub fn with_cadence(mut self, cadence: OutputCadence) -> Self {
+        self.cadence = cadence;
+        self
+    }
+
+    /// Sets the precision with which this dataset is written to
+    /// disk.
+    pub fn with_precision(mut self, precision: OutputPrecision) -> Self {
+        self.descriptor.precision = precision;
+        self
+    }
+}
+
+/// If the files are accessed via MPI-IO, creating the files and
+/// datasets is a collective operation. Otherwise, they are created
+/// by the main rank alone.
+fn uses_mpi_io(sim: &Simulation) -> bool {
+    sim.unwrap_resource::<OutputParameters>().uses_mpi_io()
 }
 
 fn add_file_creation_systems(sim: &mut Simulation) {
@@ -133,35 +169,54 @@ where
                     .after(close_file_system)
                     .with_run_criteria(Timer::run_criterion),
             );
-
-        #[cfg(feature = "parallel-hdf5")]
-        add_file_creation_systems(sim);
+        if sim
+            .unwrap_resource::<OutputParameters>()
+            .one_file_per_rank()
+        {
+            sim.add_system_to_stage(
+                Stages::Output,
+                write_manifest_system
+                    .after(close_file_system)
+                    .before(Timer::update_system)
+                    .with_run_criteria(Timer::run_criterion),
+            );
+        }
+        if uses_mpi_io(sim) {
+            add_file_creation_systems(sim);
+        }
     }
 
     fn build_everywhere(&self, sim: &mut Simulation) {
-        sim.insert_non_send_resource::<OutputDatasetDescriptor<T>>(
-            OutputDatasetDescriptor::<T>::new(self.descriptor.descriptor.clone()),
-        );
+        sim.insert_non_send_resource::<OutputDatasetDescriptor<T>>(self.descriptor.clone());
+        // A cadence set via `Simulation::set_output_cadence` before
+        // the plugin was added takes precedence.
+        if !sim.contains_resource::<DatasetTimer<T>>() {
+            sim.insert_resource(DatasetTimer::<T>::new(self.cadence));
+        }
         if is_desired_field::<T>(sim) {
-            sim.add_system_to_stage(
-                Stages::Output,
-                T::write_system()
-                    .after(open_file_system)
-                    .before(close_file_system)
-                    .label(OutputSystemLabel)
-                    .ambiguous_with(OutputSystemLabel),
-            );
+            if let Some(system) = T::write_system() {
+                sim.add_system_to_stage(
+                    Stages::Output,
+                    system
+                        .after(open_file_system)
+                        .before(close_file_system)
+                        .label(OutputSystemLabel)
+                        .ambiguous_with(OutputSystemLabel),
+                );
+            }
+        }
+        if uses_mpi_io(sim) {
+            add_dataset_creation_system_if_desired::<T>(sim);
         }
-        #[cfg(feature = "parallel-hdf5")]
-        add_dataset_creation_system_if_desired::<T>(sim);
     }
 
     fn build_once_on_main_rank(&self, sim: &mut Simulation) {
         sim.insert_resource(RegisteredFields::default());
         sim.add_startup_system(write_used_parameters_system)
             .add_startup_system(verify_output_fields_system);
-        #[cfg(not(feature = "parallel-hdf5"))]
-        add_file_creation_systems(sim);
+        if !uses_mpi_io(sim) {
+            add_file_creation_systems(sim);
+        }
     }
 
     fn build_on_main_rank(&self, sim: &mut Simulation) {
@@ -169,8 +224,9 @@ where
             .unwrap()
             .0
             .push(T::name().into());
-        #[cfg(not(feature = "parallel-hdf5"))]
-        add_dataset_creation_system_if_desired::<T>(sim);
+        if !uses_mpi_io(sim) {
+            add_dataset_creation_system_if_desired::<T>(sim);
+        }
     }
 }
 
@@ -0,0 +1,366 @@
+use std::fs;
+
+use bevy_ecs::prelude::Commands;
+use bevy_ecs::prelude::Res;
+use hdf5::types::VarLenUnicode;
+
+use super::check_dimension_of_existing_dataset;
+use super::create_dataset_in_files;
+use super::get_next_free_snapshot_num;
+use super::get_output_file_name;
+use super::get_snapshot_dir;
+use super::make_output_dirs;
+use super::parameters::Compression;
+use super::parameters::OutputParameters;
+use super::parameters::TransferMode;
+use super::timer::OutputCadence;
+use super::wait_for_previous_ranks;
+use super::wait_for_subsequent_ranks;
+use super::write_dataset_to_files;
+use super::write_provenance_attributes;
+use super::FileWithRegion;
+use super::CODE_VERSION_IDENTIFIER;
+use super::CREATION_TIME_IDENTIFIER;
+use super::PARAMETERS_HASH_IDENTIFIER;
+use crate::communication::communicator::Communicator;
+use crate::communication::SizedCommunicator;
+use crate::communication::MPI_UNIVERSE;
+use crate::components::Mass;
+use crate::components::Position;
+use crate::io::file_distribution::get_rank_output_assignment_for_rank;
+use crate::io::file_distribution::Region;
+use crate::io::input::NumParticlesTotal;
+use crate::io::input::Reader;
+use crate::io::DatasetDescriptor;
+use crate::io::InputDatasetDescriptor;
+use crate::io::OutputPrecision;
+use crate::named::Named;
+use crate::prelude::LocalParticle;
+use crate::prelude::StartupStages;
+use crate::prelude::WorldRank;
+use crate::simulation_plugin::SimulationTime;
+use crate::simulation_plugin::StopSimulationEvent;
+use crate::test_utils::assert_is_close;
+use crate::test_utils::run_on_rank_counts;
+use crate::test_utils::temp_test_dir;
+use crate::test_utils::TestSimulationBuilder;
+use crate::units;
+use crate::units::MVec;
+use crate::units::VecLength;
+
+#[test]
+fn write_chunked_dataset() {
+    let path = temp_test_dir("write_chunked_dataset").join("chunked.hdf5");
+    let num_entries = 10;
+    let chunk_size = 3;
+    let descriptor = DatasetDescriptor::default_for::<Mass>();
+    let files = vec![FileWithRegion {
+        file: hdf5::File::create(&path).unwrap(),
+        region: Region {
+            file_index: 0,
+            start: 0,
+            end: num_entries,
+        },
+    }];
+    create_dataset_in_files::<Mass>(
+        &files,
+        &descriptor,
+        Some(chunk_size),
+        OutputPrecision::Double,
+        None,
+    );
+    let data: Vec<Mass> = (0..num_entries)
+        .map(|i| Mass::from(units::Mass::kilograms(i as f64)))
+        .collect();
+    write_dataset_to_files(data, &files, &descriptor, TransferMode::Independent);
+    drop(files);
+    let file = hdf5::File::open(&path).unwrap();
+    let dataset = file.dataset(descriptor.dataset_name()).unwrap();
+    assert_eq!(dataset.chunk(), Some(vec![chunk_size]));
+    let read: Vec<Mass> = dataset.read_raw().unwrap();
+    assert_eq!(read.len(), num_entries);
+    for (i, mass) in read.iter().enumerate() {
+        assert_is_close(**mass, units::Mass::kilograms(i as f64));
+    }
+}
+
+#[test]
+fn sequential_writes_of_two_ranks_are_ordered() {
+    if run_on_rank_counts(
+        concat!(
+            module_path!(),
+            "::sequential_writes_of_two_ranks_are_ordered"
+        ),
+        [2],
+    ) {
+        return;
+    }
+    let mut comm = Communicator::<u32>::new();
+    let rank = comm.rank();
+    let world_size = comm.size();
+    let num_entries_per_rank = [4, 6];
+    assert_eq!(world_size, num_entries_per_rank.len());
+    let num_entries = num_entries_per_rank.iter().sum();
+    // All ranks write to the same file, so the directory cannot
+    // depend on the process id of each rank.
+    let main_process_id = comm.broadcast_from_root(&std::process::id());
+    let dir = std::env::temp_dir()
+        .join("subsweep_tests")
+        .join(format!("{main_process_id}_sequential_writes"));
+    let path = dir.join("sequential.hdf5");
+    let descriptor = DatasetDescriptor::default_for::<Mass>();
+    if rank == 0 {
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let files = vec![FileWithRegion {
+            file: hdf5::File::create(&path).unwrap(),
+            region: Region {
+                file_index: 0,
+                start: 0,
+                end: num_entries,
+            },
+        }];
+        create_dataset_in_files::<Mass>(&files, &descriptor, None, OutputPrecision::Double, None);
+    }
+    MPI_UNIVERSE.barrier();
+    wait_for_previous_ranks(world_size, rank as usize);
+    let assignment = get_rank_output_assignment_for_rank(&num_entries_per_rank, 1, rank);
+    let files: Vec<_> = assignment
+        .regions
+        .into_iter()
+        .map(|region| FileWithRegion {
+            file: hdf5::File::open_rw(&path).unwrap(),
+            region,
+        })
+        .collect();
+    let offset: usize = num_entries_per_rank[..rank as usize].iter().sum();
+    let data: Vec<Mass> = (offset..offset + num_entries_per_rank[rank as usize])
+        .map(|i| Mass::from(units::Mass::kilograms(i as f64)))
+        .collect();
+    write_dataset_to_files(data, &files, &descriptor, TransferMode::Sequential);
+    drop(files);
+    wait_for_subsequent_ranks(world_size, rank as usize);
+    let file = hdf5::File::open(&path).unwrap();
+    let read: Vec<Mass> = file
+        .dataset(descriptor.dataset_name())
+        .unwrap()
+        .read_raw()
+        .unwrap();
+    assert_eq!(read.len(), num_entries);
+    for (i, mass) in read.iter().enumerate() {
+        assert_is_close(**mass, units::Mass::kilograms(i as f64));
+    }
+}
+
+#[test]
+fn single_precision_dataset_round_trips() {
+    let path = temp_test_dir("single_precision_dataset").join("single_precision.hdf5");
+    let num_entries = 10;
+    let descriptor = DatasetDescriptor::default_for::<Mass>();
+    let files = vec![FileWithRegion {
+        file: hdf5::File::create(&path).unwrap(),
+        region: Region {
+            file_index: 0,
+            start: 0,
+            end: num_entries,
+        },
+    }];
+    create_dataset_in_files::<Mass>(&files, &descriptor, None, OutputPrecision::Single, None);
+    let data: Vec<Mass> = (0..num_entries)
+        .map(|i| Mass::from(units::Mass::kilograms(0.5 * i as f64)))
+        .collect();
+    write_dataset_to_files(data, &files, &descriptor, TransferMode::Independent);
+    drop(files);
+    let file = hdf5::File::open(&path).unwrap();
+    let dataset = file.dataset(descriptor.dataset_name()).unwrap();
+    assert_eq!(dataset.dtype().unwrap().size(), 4);
+    let read: Vec<Mass> = Reader::full([&path].into_iter())
+        .read_dataset(InputDatasetDescriptor::<Mass>::default())
+        .collect();
+    assert_eq!(read.len(), num_entries);
+    for (i, mass) in read.iter().enumerate() {
+        assert_is_close(**mass, units::Mass::kilograms(0.5 * i as f64));
+    }
+}
+
+const NUM_PARTICLES: usize = 3;
+
+fn spawn_particles_system(mut commands: Commands, rank: Res<WorldRank>) {
+    if rank.is_main() {
+        for i in 0..NUM_PARTICLES {
+            commands.spawn((
+                LocalParticle,
+                Position(VecLength::new_unchecked(MVec::ONE * 0.1 * i as f64)),
+                Mass::from(units::Mass::kilograms(1.0)),
+            ));
+        }
+    }
+    commands.insert_resource(NumParticlesTotal(NUM_PARTICLES));
+}
+
+/// Returns whether the snapshot with the given number contains
+/// the dataset of `T`.
+fn snapshot_contains<T: Named>(parameters: &OutputParameters, snapshot_num: usize) -> bool {
+    let path = get_snapshot_dir(parameters, snapshot_num).join(get_output_file_name(
+        parameters,
+        snapshot_num,
+        parameters.num_output_files,
+        0,
+    ));
+    let file = hdf5::File::open(path).unwrap();
+    file.dataset(DatasetDescriptor::default_for::<T>().dataset_name())
+        .is_ok()
+}
+
+#[test]
+fn datasets_with_different_cadences() {
+    let output_dir = temp_test_dir("output_cadence").join("output");
+    let num_steps = 5;
+    let mut sim = TestSimulationBuilder::new()
+        .parameter_file_contents(&format!(
+            "output:\n  output_dir: {}\n",
+            output_dir.to_str().unwrap()
+        ))
+        .setup(|sim| {
+            sim.write_output(true)
+                .insert_resource(SimulationTime(units::Time::zero()))
+                .add_event::<StopSimulationEvent>()
+                .add_startup_system_to_stage(StartupStages::ReadInput, spawn_particles_system)
+                // Set before the component is registered, so that
+                // the default cadence of the output plugin must not
+                // override it.
+                .set_output_cadence::<Mass>(OutputCadence::EveryNthSnapshot(2))
+                .add_derived_component::<Position>()
+                .add_derived_component::<Mass>();
+        })
+        .build();
+    let parameters = sim.get_parameters::<OutputParameters>().clone();
+    make_output_dirs(&parameters);
+    // The simulation time does not advance, so a snapshot is
+    // written at every step.
+    for _ in 0..num_steps {
+        sim.update();
+    }
+    for snapshot_num in 0..num_steps {
+        assert!(snapshot_contains::<Position>(&parameters, snapshot_num));
+        assert_eq!(
+            snapshot_contains::<Mass>(&parameters, snapshot_num),
+            snapshot_num % 2 == 0
+        );
+    }
+}
+
+#[test]
+fn appended_snapshots_continue_after_last_existing_one() {
+    let snapshot_dir = temp_test_dir("append_snapshots").join("snapshots");
+    assert_eq!(get_next_free_snapshot_num(&snapshot_dir), 0);
+    for name in ["000", "001", "004", "not_a_snapshot"] {
+        std::fs::create_dir_all(snapshot_dir.join(name)).unwrap();
+    }
+    assert_eq!(get_next_free_snapshot_num(&snapshot_dir), 5);
+}
+
+#[test]
+#[should_panic(expected = "has a different dimension")]
+fn appending_dataset_with_different_dimension_fails() {
+    let path = temp_test_dir("append_different_dimension").join("snapshot.hdf5");
+    let descriptor = DatasetDescriptor::default_for::<Mass>();
+    let files = vec![FileWithRegion {
+        file: hdf5::File::create(&path).unwrap(),
+        region: Region {
+            file_index: 0,
+            start: 0,
+            end: 1,
+        },
+    }];
+    create_dataset_in_files::<Mass>(&files, &descriptor, None, OutputPrecision::Double, None);
+    drop(files);
+    check_dimension_of_existing_dataset::<Mass>(&path, &descriptor);
+    // Pretend that a dataset of the same name now holds positions.
+    check_dimension_of_existing_dataset::<Position>(&path, &descriptor);
+}
+
+#[test]
+fn compressed_dataset_round_trips() {
+    let path = temp_test_dir("compressed_dataset").join("compressed.hdf5");
+    let num_entries = 1000;
+    let descriptor = DatasetDescriptor::default_for::<Mass>();
+    let files = vec![FileWithRegion {
+        file: hdf5::File::create(&path).unwrap(),
+        region: Region {
+            file_index: 0,
+            start: 0,
+            end: num_entries,
+        },
+    }];
+    create_dataset_in_files::<Mass>(
+        &files,
+        &descriptor,
+        None,
+        OutputPrecision::Double,
+        Some(Compression::Gzip(6)),
+    );
+    let data: Vec<Mass> = (0..num_entries)
+        .map(|_| Mass::from(units::Mass::kilograms(1.0)))
+        .collect();
+    write_dataset_to_files(data, &files, &descriptor, TransferMode::Independent);
+    drop(files);
+    let dataset = hdf5::File::open(&path)
+        .unwrap()
+        .dataset(descriptor.dataset_name())
+        .unwrap();
+    assert_eq!(dataset.chunk(), Some(vec![num_entries]));
+    assert!(dataset.storage_size() < (num_entries * std::mem::size_of::<f64>()) as u64);
+    let read: Vec<Mass> = Reader::full([&path].into_iter())
+        .read_dataset(InputDatasetDescriptor::<Mass>::default())
+        .collect();
+    assert_eq!(read.len(), num_entries);
+    for mass in read.iter() {
+        assert_is_close(**mass, units::Mass::kilograms(1.0));
+    }
+}
+
+#[test]
+fn provenance_attributes_are_written_to_root_of_file() {
+    let path = temp_test_dir("provenance_attributes").join("provenance.hdf5");
+    let files = vec![FileWithRegion {
+        file: hdf5::File::create(&path).unwrap(),
+        region: Region {
+            file_index: 0,
+            start: 0,
+            end: 0,
+        },
+    }];
+    write_provenance_attributes(&files, 1234, "2024-01-01T00:00:00+00:00");
+    drop(files);
+    let file = hdf5::File::open(&path).unwrap();
+    let read_string = |identifier| {
+        file.attr(identifier)
+            .unwrap()
+            .read_scalar::<VarLenUnicode>()
+            .unwrap()
+            .to_string()
+    };
+    assert!(read_string(CODE_VERSION_IDENTIFIER).starts_with(env!("CARGO_PKG_VERSION")));
+    assert_eq!(
+        read_string(CREATION_TIME_IDENTIFIER),
+        "2024-01-01T00:00:00+00:00"
+    );
+    let hash: u64 = file
+        .attr(PARAMETERS_HASH_IDENTIFIER)
+        .unwrap()
+        .read_scalar()
+        .unwrap();
+    assert_eq!(hash, 1234);
+}
+
+#[test]
+fn output_files_are_named_by_snapshot_and_rank_with_one_file_per_rank() {
+    let shared: OutputParameters = serde_yaml::from_str("num_output_files: 12").unwrap();
+    assert_eq!(get_output_file_name(&shared, 7, 12, 3), "03.hdf5");
+    let one_per_rank: OutputParameters = serde_yaml::from_str("write_mode: one_per_rank").unwrap();
+    assert_eq!(
+        get_output_file_name(&one_per_rank, 7, 12, 3),
+        "snapshot_007.rank_3.hdf5"
+    );
+}
@@ -1,3 +1,5 @@
+use std::marker::PhantomData;
+
 use bevy_ecs::prelude::Commands;
 use bevy_ecs::prelude::EventReader;
 use bevy_ecs::prelude::Res;
@@ -5,8 +7,11 @@ use bevy_ecs::prelude::ResMut;
 use bevy_ecs::prelude::Resource;
 use bevy_ecs::schedule::ShouldRun;
 
+use super::get_next_free_snapshot_num;
+use super::parameters::HandleExistingOutput;
 use super::parameters::OutputParameters;
 use crate::io::to_dataset::ToDataset;
+use crate::simulation_plugin::Checkpoint;
 use crate::simulation_plugin::SimulationTime;
 use crate::simulation_plugin::StopSimulationEvent;
 use crate::units;
@@ -15,15 +20,21 @@ use crate::units;
 pub struct Timer {
     next_output_time: units::Time,
     snapshot_num: usize,
+    first_snapshot_num: usize,
 }
 
 impl Timer {
     pub fn initialize_system(mut commands: Commands, parameters: Res<OutputParameters>) {
+        let first_snapshot_num = match parameters.handle_existing_output {
+            HandleExistingOutput::Append => get_next_free_snapshot_num(&parameters.snapshot_dir()),
+            _ => 0,
+        };
         commands.insert_resource(Timer {
             next_output_time: parameters
                 .time_first_snapshot
                 .unwrap_or_else(units::Time::zero),
-            snapshot_num: 0,
+            snapshot_num: first_snapshot_num,
+            first_snapshot_num,
         });
     }
 
@@ -43,12 +54,26 @@ impl Timer {
     pub fn dataset_write_run_criterion<T: ToDataset>(
         time: Res<SimulationTime>,
         timer: Res<Self>,
+        mut dataset_timer: ResMut<DatasetTimer<T>>,
         events: EventReader<StopSimulationEvent>,
+        checkpoint: Option<Res<Checkpoint>>,
     ) -> ShouldRun {
         let is_first_snapshot = timer.is_first_snapshot();
+        let snapshot_num = timer.snapshot_num();
+        let current_time = time.0;
         let should_run = Self::run_criterion(time, timer, events);
         if should_run == ShouldRun::Yes {
-            match !T::is_static() || is_first_snapshot {
+            // Every checkpoint needs to contain all datasets in order
+            // to restart from it.
+            let writes_checkpoint = checkpoint.map(|checkpoint| **checkpoint).unwrap_or(false);
+            let should_write = if writes_checkpoint {
+                true
+            } else if T::is_static() {
+                is_first_snapshot
+            } else {
+                dataset_timer.should_write(snapshot_num, current_time)
+            };
+            match should_write {
                 true => ShouldRun::Yes,
                 false => ShouldRun::No,
             }
@@ -62,11 +87,88 @@ impl Timer {
         output_timer.next_output_time += parameters.time_between_snapshots;
     }
 
+    /// Moves the next output time past `time`, so that a restarted
+    /// simulation does not write a snapshot at every step until it
+    /// has caught up with the output times of the previous run.
+    pub(crate) fn skip_to(&mut self, time: units::Time, parameters: &OutputParameters) {
+        if parameters.time_between_snapshots <= units::Time::zero() {
+            return;
+        }
+        while self.next_output_time <= time {
+            self.next_output_time += parameters.time_between_snapshots;
+        }
+    }
+
+    pub fn next_output_time(&self) -> units::Time {
+        self.next_output_time
+    }
+
     pub fn snapshot_num(&self) -> usize {
         self.snapshot_num
     }
 
     pub fn is_first_snapshot(&self) -> bool {
-        self.snapshot_num == 0
+        self.snapshot_num == self.first_snapshot_num
+    }
+}
+
+/// How often a dataset is written to the snapshots.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum OutputCadence {
+    /// Write the dataset to every snapshot.
+    #[default]
+    EverySnapshot,
+    /// Write the dataset to every n-th snapshot, beginning with
+    /// the first one.
+    EveryNthSnapshot(usize),
+    /// Write the dataset to the first snapshot and afterwards to
+    /// the first snapshot which is at least the given time after
+    /// the last snapshot that contained the dataset.
+    TimeInterval(units::Time),
+}
+
+/// Keeps track of the snapshots to which the dataset `T` is
+/// written, according to its [OutputCadence].
+#[derive(Resource)]
+pub struct DatasetTimer<T> {
+    cadence: OutputCadence,
+    last_written: Option<(usize, units::Time)>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> DatasetTimer<T> {
+    pub fn new(cadence: OutputCadence) -> Self {
+        if let OutputCadence::EveryNthSnapshot(n) = cadence {
+            assert!(n > 0, "Output cadence needs to be at least one snapshot.");
+        }
+        Self {
+            cadence,
+            last_written: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Decides whether the dataset is part of the snapshot with the
+    /// given number. Since both the dataset creation and the write
+    /// system ask this for every snapshot, the decision is
+    /// remembered and repeated for the same snapshot.
+    pub fn should_write(&mut self, snapshot_num: usize, time: units::Time) -> bool {
+        if let Some((last_snapshot_num, _)) = self.last_written {
+            if last_snapshot_num == snapshot_num {
+                return true;
+            }
+        }
+        let should_write = match self.cadence {
+            OutputCadence::EverySnapshot => true,
+            OutputCadence::EveryNthSnapshot(n) => snapshot_num % n == 0,
+            OutputCadence::TimeInterval(interval) => match self.last_written {
+                Some((_, last_time)) => time >= last_time + interval,
+                None => true,
+            },
+        };
+        if should_write {
+            self.last_written = Some((snapshot_num, time));
+        }
+        should_write
     }
 }
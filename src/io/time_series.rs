@@ -9,10 +9,13 @@ use bevy_ecs::prelude::EventReader;
 use bevy_ecs::prelude::IntoSystemDescriptor;
 use bevy_ecs::prelude::NonSend;
 use bevy_ecs::prelude::Res;
+use bevy_ecs::prelude::ResMut;
+use bevy_ecs::prelude::Resource;
 use serde::Serialize;
 
 use super::DatasetDescriptor;
 use super::OutputDatasetDescriptor;
+use super::OutputPrecision;
 use crate::named::Named;
 use crate::parameters::Cosmology;
 use crate::parameters::OutputParameters;
@@ -20,6 +23,7 @@ use crate::prelude::Stages;
 use crate::simulation::Simulation;
 use crate::simulation::SubsweepPlugin;
 use crate::simulation_plugin::SimulationTime;
+use crate::simulation_plugin::StopSimulationEvent;
 use crate::time_spec::TimeSpec;
 
 pub trait TimeSeries: 'static + Sync + Send + Clone + Serialize {}
@@ -32,6 +36,23 @@ struct Entry<T> {
     val: T,
 }
 
+/// The entries of the time series `T` which have not been
+/// written to its file yet.
+#[derive(Resource)]
+pub struct TimeSeriesBuffer<T> {
+    entries: Vec<Entry<T>>,
+    num_outputs_since_flush: usize,
+}
+
+impl<T> Default for TimeSeriesBuffer<T> {
+    fn default() -> Self {
+        Self {
+            entries: vec![],
+            num_outputs_since_flush: 0,
+        }
+    }
+}
+
 #[derive(Named)]
 pub struct TimeSeriesPlugin<T: TimeSeries> {
     descriptor: OutputDatasetDescriptor<T>,
@@ -43,6 +64,7 @@ impl<T: Named + TimeSeries> Default for TimeSeriesPlugin<T> {
             descriptor: OutputDatasetDescriptor {
                 _marker: PhantomData,
                 descriptor: DatasetDescriptor::default_for::<T>(),
+                precision: OutputPrecision::default(),
             },
         }
     }
@@ -62,15 +84,19 @@ impl<T: TimeSeries> SubsweepPlugin for TimeSeriesPlugin<T> {
     }
 
     fn build_on_main_rank(&self, sim: &mut Simulation) {
-        sim.add_startup_system(
-            initialize_output_files_system::<T>.after(setup_time_series_output_system),
-        )
-        .add_system_to_stage(Stages::Output, output_time_series_system::<T>);
+        sim.insert_resource(TimeSeriesBuffer::<T>::default())
+            .add_startup_system(
+                initialize_output_files_system::<T>.after(setup_time_series_output_system),
+            )
+            .add_system_to_stage(Stages::Output, output_time_series_system::<T>);
     }
 
     fn build_everywhere(&self, sim: &mut Simulation) {
         sim.insert_non_send_resource::<OutputDatasetDescriptor<T>>(
-            OutputDatasetDescriptor::<T>::new(self.descriptor.descriptor.clone()),
+            OutputDatasetDescriptor::<T>::new(
+                self.descriptor.descriptor.clone(),
+                self.descriptor.precision,
+            ),
         );
         // Add this here too, so we can request this even on systems running on non-main ranks without the crash.
         sim.add_event::<T>();
@@ -99,6 +125,8 @@ fn initialize_output_files_system<T: TimeSeries>(
 
 pub fn output_time_series_system<T: TimeSeries>(
     mut event_reader: EventReader<T>,
+    mut stop_sim: EventReader<StopSimulationEvent>,
+    mut buffer: ResMut<TimeSeriesBuffer<T>>,
     time: Res<SimulationTime>,
     parameters: Res<OutputParameters>,
     cosmology: Res<Cosmology>,
@@ -106,22 +134,32 @@ pub fn output_time_series_system<T: TimeSeries>(
 ) where
     T: TimeSeries,
 {
-    let path = get_time_series_filename::<T>(&parameters, &descriptor);
-    let entries: Vec<_> = event_reader
-        .iter()
-        .map(|ev| Entry {
-            time: TimeSpec::new(**time, &cosmology),
-            val: ev.clone(),
-        })
-        .collect();
-    if entries.len() > 0 {
-        let f = OpenOptions::new()
-            .append(true)
-            .open(&path)
-            .unwrap_or_else(|e| panic!("Failed to open time series file. {}", e));
-        serde_yaml::to_writer(&f, &entries)
-            .unwrap_or_else(|e| panic!("Failed to write to time series file: {}", e));
+    buffer.entries.extend(event_reader.iter().map(|ev| Entry {
+        time: TimeSpec::new(**time, &cosmology),
+        val: ev.clone(),
+    }));
+    buffer.num_outputs_since_flush += 1;
+    // The stage containing the exit system runs after the output
+    // stage, so this is the last chance to write the entries.
+    let simulation_finished = stop_sim.iter().count() > 0;
+    if simulation_finished || buffer.num_outputs_since_flush >= parameters.time_series_flush_every {
+        let path = get_time_series_filename::<T>(&parameters, &descriptor);
+        flush_time_series(&path, &mut buffer);
+    }
+}
+
+fn flush_time_series<T: TimeSeries>(path: &Path, buffer: &mut TimeSeriesBuffer<T>) {
+    buffer.num_outputs_since_flush = 0;
+    if buffer.entries.is_empty() {
+        return;
     }
+    let f = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .unwrap_or_else(|e| panic!("Failed to open time series file. {}", e));
+    serde_yaml::to_writer(&f, &buffer.entries)
+        .unwrap_or_else(|e| panic!("Failed to write to time series file: {}", e));
+    buffer.entries.clear();
 }
 
 fn get_time_series_filename<T: TimeSeries>(
@@ -131,3 +169,93 @@ fn get_time_series_filename<T: TimeSeries>(
     let time_series_dir = parameters.time_series_dir();
     time_series_dir.join(format!("{}.yml", descriptor.dataset_name()))
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::Events;
+    use bevy_ecs::prelude::SystemStage;
+    use bevy_ecs::prelude::World;
+    use serde::Serialize;
+
+    use super::get_time_series_filename;
+    use super::initialize_output_files_system;
+    use super::output_time_series_system;
+    use super::setup_time_series_output_system;
+    use super::TimeSeriesBuffer;
+    use crate::io::DatasetDescriptor;
+    use crate::io::OutputDatasetDescriptor;
+    use crate::io::OutputPrecision;
+    use crate::named::Named;
+    use crate::parameters::Cosmology;
+    use crate::parameters::OutputParameters;
+    use crate::simulation_plugin::SimulationTime;
+    use crate::simulation_plugin::StopSimulationEvent;
+    use crate::test_utils::run_system_on_world;
+    use crate::test_utils::temp_test_dir;
+    use crate::units::Time;
+
+    #[derive(Clone, Serialize, Named)]
+    #[name = "test_series"]
+    struct TestSeries(usize);
+
+    fn read_entries(world: &World) -> Vec<usize> {
+        let path = get_time_series_filename(
+            world.resource::<OutputParameters>(),
+            world.non_send_resource::<OutputDatasetDescriptor<TestSeries>>(),
+        );
+        let contents = std::fs::read_to_string(path).unwrap();
+        if contents.is_empty() {
+            return vec![];
+        }
+        let entries: Vec<serde_yaml::Value> = serde_yaml::from_str(&contents).unwrap();
+        entries
+            .iter()
+            .map(|entry| entry["val"].as_u64().unwrap() as usize)
+            .collect()
+    }
+
+    #[test]
+    fn time_series_is_flushed_periodically_and_on_stop() {
+        let output_dir = temp_test_dir("time_series");
+        let mut world = World::new();
+        world.insert_resource::<OutputParameters>(
+            serde_yaml::from_str(&format!(
+                "output_dir: {}\ntime_series_flush_every: 2",
+                output_dir.to_str().unwrap()
+            ))
+            .unwrap(),
+        );
+        world.insert_resource(SimulationTime(Time::zero()));
+        world.insert_resource(Cosmology::NonCosmological);
+        world.init_resource::<Events<TestSeries>>();
+        world.init_resource::<Events<StopSimulationEvent>>();
+        world.insert_resource(TimeSeriesBuffer::<TestSeries>::default());
+        world.insert_non_send_resource(OutputDatasetDescriptor::<TestSeries>::new(
+            DatasetDescriptor::default_for::<TestSeries>(),
+            OutputPrecision::default(),
+        ));
+        run_system_on_world(&mut world, setup_time_series_output_system);
+        run_system_on_world(&mut world, initialize_output_files_system::<TestSeries>);
+        let mut stage =
+            SystemStage::single_threaded().with_system(output_time_series_system::<TestSeries>);
+        let expected_written = [
+            vec![],
+            vec![0, 1],
+            vec![0, 1],
+            vec![0, 1, 2, 3],
+            vec![0, 1, 2, 3],
+        ];
+        for (step, expected) in expected_written.iter().enumerate() {
+            world.send_event(TestSeries(step));
+            stage.run(&mut world);
+            assert_eq!(&read_entries(&world), expected);
+        }
+        world.send_event(StopSimulationEvent);
+        stage.run(&mut world);
+        assert_eq!(read_entries(&world), vec![0, 1, 2, 3, 4]);
+        assert!(world
+            .resource::<TimeSeriesBuffer<TestSeries>>()
+            .entries
+            .is_empty());
+    }
+}
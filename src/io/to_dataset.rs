@@ -24,12 +24,14 @@ pub trait ToDataset: Clone + H5Type + Sync + Send + 'static {
 }
 
 impl<T: ToDataset + Component> IntoOutputSystem for T {
-    fn write_system() -> SystemDescriptor {
-        write_dataset_system::<T>
-            .with_run_criteria(Timer::dataset_write_run_criterion::<T>)
-            .into_descriptor()
-            .label(DatasetSystemAmbiguityLabel)
-            .ambiguous_with(DatasetSystemAmbiguityLabel)
+    fn write_system() -> Option<SystemDescriptor> {
+        Some(
+            write_dataset_system::<T>
+                .with_run_criteria(Timer::dataset_write_run_criterion::<T>)
+                .into_descriptor()
+                .label(DatasetSystemAmbiguityLabel)
+                .ambiguous_with(DatasetSystemAmbiguityLabel),
+        )
     }
 
     fn create_system() -> (SystemDescriptor, SystemLabelId) {
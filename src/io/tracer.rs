@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+use bevy_ecs::prelude::*;
+use derive_custom::subsweep_parameters;
+use derive_more::Deref;
+use derive_more::From;
+use hdf5::Group;
+use hdf5::H5Type;
+use mpi::traits::Equivalence;
+
+use super::output::add_dimension_attrs;
+use super::to_dataset::ToDataset;
+use crate::communication::MpiWorld;
+use crate::impl_to_dataset;
+use crate::named::Named;
+use crate::parameters::OutputParameters;
+use crate::prelude::ParticleId;
+use crate::prelude::Particles;
+use crate::prelude::Stages;
+use crate::prelude::StartupStages;
+use crate::prelude::WorldRank;
+use crate::simulation::Simulation;
+use crate::simulation::SubsweepPlugin;
+use crate::simulation_plugin::SimulationTime;
+use crate::units;
+use crate::units::Time;
+
+const TRACER_CHUNK_SIZE: usize = 64;
+
+/// Parameters of the tracer particles whose state is recorded at
+/// every timestep if a [TracerPlugin] is added.
+#[subsweep_parameters("tracers")]
+pub struct TracerParameters {
+    /// The ids of the particles which are marked as tracers.
+    #[serde(default)]
+    pub ids: Vec<ParticleId>,
+}
+
+/// Marks a particle whose state is recorded at every timestep.
+#[derive(Component)]
+pub struct Tracer;
+
+#[derive(H5Type, Clone, Debug, Deref, From, Named)]
+#[name = "time"]
+#[repr(transparent)]
+pub struct TracerTime(pub Time);
+
+impl_to_dataset!(TracerTime, units::Time, false);
+
+/// The recorded values of a single tracer, together with the
+/// times at which they were recorded.
+pub struct Trajectory<T> {
+    pub times: Vec<Time>,
+    pub values: Vec<T>,
+}
+
+impl<T> Default for Trajectory<T> {
+    fn default() -> Self {
+        Self {
+            times: vec![],
+            values: vec![],
+        }
+    }
+}
+
+/// The trajectories of all tracers, gathered from all ranks. Only
+/// kept on the main rank. If output is enabled, the entries are
+/// removed once they have been written, so that only the entries
+/// recorded since the last write are kept.
+#[derive(Resource)]
+pub struct TracerTrajectories<T>(pub BTreeMap<ParticleId, Trajectory<T>>);
+
+impl<T> Default for TracerTrajectories<T> {
+    fn default() -> Self {
+        Self(BTreeMap::default())
+    }
+}
+
+/// Records the component `T` of all [Tracer] particles at every
+/// timestep. If output is enabled, the trajectories are written to
+/// one file per component in the tracers directory, with one group
+/// per tracer.
+#[derive(Named)]
+pub struct TracerPlugin<T> {
+    _marker: PhantomData<T>,
+}
+
+impl<T> Default for TracerPlugin<T> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> SubsweepPlugin for TracerPlugin<T>
+where
+    T: Component + ToDataset + Equivalence + Named,
+{
+    fn allow_adding_twice(&self) -> bool {
+        true
+    }
+
+    fn build_once_everywhere(&self, sim: &mut Simulation) {
+        sim.add_parameter_type::<TracerParameters>()
+            .add_startup_system_to_stage(StartupStages::Final, mark_tracers_system);
+    }
+
+    fn build_everywhere(&self, sim: &mut Simulation) {
+        sim.insert_resource(TracerTrajectories::<T>::default())
+            .add_system_to_stage(Stages::Output, record_tracers_system::<T>);
+    }
+
+    fn build_on_main_rank(&self, sim: &mut Simulation) {
+        if sim.write_output {
+            sim.add_startup_system(initialize_tracer_file_system::<T>)
+                .add_system_to_stage(
+                    Stages::Output,
+                    write_tracers_system::<T>.after(record_tracers_system::<T>),
+                );
+        }
+    }
+}
+
+fn mark_tracers_system(
+    mut commands: Commands,
+    particles: Particles<(Entity, &ParticleId)>,
+    parameters: Res<TracerParameters>,
+) {
+    for (entity, id) in particles.iter() {
+        if parameters.ids.contains(id) {
+            commands.entity(entity).insert(Tracer);
+        }
+    }
+}
+
+fn record_tracers_system<T: Component + ToDataset + Equivalence>(
+    tracers: Particles<(&ParticleId, &T), With<Tracer>>,
+    time: Res<SimulationTime>,
+    mut trajectories: ResMut<TracerTrajectories<T>>,
+    rank: Res<WorldRank>,
+) {
+    let (ids, values): (Vec<_>, Vec<_>) = tracers
+        .iter()
+        .map(|(id, value)| (*id, value.clone()))
+        .unzip();
+    // The ids and values are gathered in the same rank order, so
+    // they still match up afterwards.
+    let ids = MpiWorld::<ParticleId>::new().all_gather_varcount(&ids);
+    let values = MpiWorld::<T>::new().all_gather_varcount(&values);
+    if !rank.is_main() {
+        return;
+    }
+    for (id, value) in ids.into_iter().zip(values) {
+        let trajectory = trajectories.0.entry(id).or_default();
+        trajectory.times.push(**time);
+        trajectory.values.push(value);
+    }
+}
+
+fn get_tracer_filename<T: Named>(parameters: &OutputParameters) -> PathBuf {
+    parameters.tracers_dir().join(format!("{}.hdf5", T::name()))
+}
+
+fn initialize_tracer_file_system<T: Named>(parameters: Res<OutputParameters>) {
+    let tracers_dir = parameters.tracers_dir();
+    fs::create_dir_all(&tracers_dir)
+        .unwrap_or_else(|_| panic!("Failed to create tracers dir: {tracers_dir:?}"));
+    hdf5::File::create(get_tracer_filename::<T>(&parameters))
+        .expect("Failed to create tracer output file");
+}
+
+fn write_tracers_system<T: ToDataset + Named>(
+    mut trajectories: ResMut<TracerTrajectories<T>>,
+    parameters: Res<OutputParameters>,
+) {
+    let file = hdf5::File::append(get_tracer_filename::<T>(&parameters))
+        .expect("Failed to open tracer output file");
+    for (id, trajectory) in trajectories.0.iter_mut() {
+        let name = format!("{}_{}", id.rank, id.index);
+        let group = file
+            .group(&name)
+            .or_else(|_| file.create_group(&name))
+            .expect("Failed to create tracer group");
+        let times: Vec<_> = trajectory.times.iter().map(|t| TracerTime(*t)).collect();
+        append_to_dataset(&group, &times);
+        append_to_dataset(&group, &trajectory.values);
+        trajectory.times.clear();
+        trajectory.values.clear();
+    }
+}
+
+/// Appends all entries of `data` to the (resizable) dataset.
+fn append_to_dataset<T: ToDataset + Named>(group: &Group, data: &[T]) {
+    let dataset = group.dataset(T::name()).unwrap_or_else(|_| {
+        let dataset = group
+            .new_dataset::<T>()
+            .shape(0..)
+            .chunk(TRACER_CHUNK_SIZE)
+            .create(T::name())
+            .expect("Failed to create tracer dataset");
+        add_dimension_attrs::<T>(&dataset);
+        dataset
+    });
+    let num_written = dataset.size();
+    let new_size = num_written + data.len();
+    dataset
+        .resize(new_size)
+        .expect("Failed to resize tracer dataset");
+    dataset
+        .write_slice(data, num_written..new_size)
+        .expect("Failed to write to tracer dataset");
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::*;
+
+    use super::TracerPlugin;
+    use super::TracerTrajectories;
+    use crate::components::Position;
+    use crate::prelude::LocalParticle;
+    use crate::prelude::ParticleId;
+    use crate::prelude::Particles;
+    use crate::prelude::Stages;
+    use crate::prelude::StartupStages;
+    use crate::prelude::WorldRank;
+    use crate::simulation_plugin::SimulationTime;
+    use crate::test_utils::TestSimulationBuilder;
+    use crate::units::MVec;
+    use crate::units::Time;
+    use crate::units::VecLength;
+
+    fn spawn_particles_system(mut commands: Commands, rank: Res<WorldRank>) {
+        for index in 0..3 {
+            commands.spawn((
+                LocalParticle,
+                ParticleId {
+                    index,
+                    rank: **rank,
+                },
+                Position(VecLength::new_unchecked(MVec::ONE * index as f64)),
+            ));
+        }
+    }
+
+    fn move_particles_system(
+        mut particles: Particles<&mut Position>,
+        mut time: ResMut<SimulationTime>,
+    ) {
+        for mut pos in particles.iter_mut() {
+            pos.0 += VecLength::new_unchecked(MVec::X);
+        }
+        time.0 += Time::seconds(1.0);
+    }
+
+    #[test]
+    fn tracer_trajectory_is_recorded_at_every_step() {
+        let num_steps = 4;
+        let sim = TestSimulationBuilder::new()
+            .parameter_file_contents(
+                "
+tracers:
+  ids:
+    - index: 1
+      rank: 0
+",
+            )
+            .setup(|sim| {
+                sim.insert_resource(SimulationTime(Time::zero()))
+                    .add_startup_system_to_stage(StartupStages::ReadInput, spawn_particles_system)
+                    .add_system_to_stage(Stages::Initial, move_particles_system)
+                    .add_plugin(TracerPlugin::<Position>::default());
+            })
+            .run_n_steps(num_steps);
+        if !sim.unwrap_resource::<WorldRank>().is_main() {
+            return;
+        }
+        let trajectories = &sim.unwrap_resource::<TracerTrajectories<Position>>().0;
+        assert_eq!(trajectories.len(), 1);
+        let trajectory = &trajectories[&ParticleId::test(1)];
+        assert_eq!(trajectory.times.len(), num_steps);
+        assert_eq!(trajectory.values.len(), num_steps);
+        for (step, (time, pos)) in trajectory
+            .times
+            .iter()
+            .zip(trajectory.values.iter())
+            .enumerate()
+        {
+            let expected_x = 1.0 + (step + 1) as f64;
+            assert_eq!(*time, Time::seconds((step + 1) as f64));
+            assert_eq!(pos.value_unchecked().x, expected_x);
+            assert_eq!(pos.value_unchecked().y, 1.0);
+        }
+    }
+}
@@ -14,23 +14,25 @@
 #![allow(clippy::unneeded_wildcard_pattern)]
 #![allow(clippy::new_without_default)]
 
-mod chemistry;
+pub mod chemistry;
 mod command_line_options;
 pub mod communication;
 pub mod components;
+pub mod correlation;
 pub mod cosmology;
 pub mod dimension;
 pub mod domain;
 mod extent;
 pub mod hash_map;
 pub mod io;
+mod memory;
 /// Debug printing utilities for MPI simulations
 pub mod mpi_log;
 mod parameter_plugin;
 /// Contains all the parameter types of the simulation.
 pub mod parameters;
 mod particle;
-mod peano_hilbert;
+pub mod peano_hilbert;
 mod performance;
 pub mod prelude;
 mod quadtree;
@@ -39,6 +41,7 @@ mod simulation_box;
 mod simulation_builder;
 pub mod simulation_plugin;
 pub mod source_systems;
+mod stage_profiling;
 mod stages;
 pub mod sweep;
 pub mod time_spec;
@@ -34,6 +34,7 @@ use subsweep::prelude::*;
 use subsweep::simulation_plugin::remove_components_system;
 use subsweep::source_systems::SourcePlugin;
 use subsweep::source_systems::Sources;
+use subsweep::sweep::add_sweep_plugin;
 use subsweep::sweep::grid::Cell;
 use subsweep::units::Dimensionless;
 use subsweep::units::Mass;
@@ -137,9 +138,9 @@ fn main() {
                 },
                 ..Default::default()
             },
-        ))
-        .add_plugin(SweepPlugin)
-        .run();
+        ));
+    add_sweep_plugin(&mut sim);
+    sim.run();
 }
 
 #[derive(H5Type, Component, Debug, Clone, Equivalence, Deref, DerefMut, From, Default, Named)]
@@ -0,0 +1,194 @@
+use std::fs;
+
+use bevy_ecs::prelude::*;
+use derive_custom::subsweep_parameters;
+use derive_custom::Named;
+use log::debug;
+use log::info;
+use mpi::traits::Equivalence;
+
+use crate::communication::MpiWorld;
+use crate::communication::SizedCommunicator;
+use crate::domain::QuadTree;
+use crate::particle::HaloParticles;
+use crate::prelude::Particles;
+use crate::prelude::Stages;
+use crate::simulation::Simulation;
+use crate::simulation::SubsweepPlugin;
+use crate::sweep::grid::Cell;
+use crate::voronoi::NumTetras;
+
+/// Parameters for the memory usage report.
+#[derive(Default)]
+#[subsweep_parameters("memory")]
+pub struct MemoryParameters {
+    /// Log the resident memory of each rank (along with the
+    /// maximum across all ranks) every `report_interval` timesteps.
+    /// Disabled if not given.
+    #[serde(default)]
+    pub report_interval: Option<usize>,
+    /// In addition to the maximum, log the particle counts and
+    /// memory of every rank as a table on the main rank. Only
+    /// used if `report_interval` is given.
+    #[serde(default)]
+    pub log_per_rank: bool,
+}
+
+#[derive(Resource, Equivalence, Clone, Copy, Debug, Default)]
+pub struct MemoryUsage {
+    /// Resident memory in bytes.
+    pub resident_memory: u64,
+    pub num_particles: usize,
+    pub num_haloes: usize,
+    pub num_cells: usize,
+    pub num_tree_nodes: usize,
+    pub num_local_tetras: usize,
+    pub num_halo_tetras: usize,
+}
+
+impl MemoryUsage {
+    fn max(self, other: Self) -> Self {
+        Self {
+            resident_memory: self.resident_memory.max(other.resident_memory),
+            num_particles: self.num_particles.max(other.num_particles),
+            num_haloes: self.num_haloes.max(other.num_haloes),
+            num_cells: self.num_cells.max(other.num_cells),
+            num_tree_nodes: self.num_tree_nodes.max(other.num_tree_nodes),
+            num_local_tetras: self.num_local_tetras.max(other.num_local_tetras),
+            num_halo_tetras: self.num_halo_tetras.max(other.num_halo_tetras),
+        }
+    }
+}
+
+#[derive(Named)]
+pub struct MemoryPlugin;
+
+impl SubsweepPlugin for MemoryPlugin {
+    fn build_everywhere(&self, sim: &mut Simulation) {
+        let parameters = sim.add_parameter_type_and_get_result::<MemoryParameters>();
+        if parameters.report_interval.is_some() {
+            sim.insert_resource(MemoryUsage::default())
+                .add_system_to_stage(Stages::Final, report_memory_usage_system);
+        }
+    }
+}
+
+/// Reads the resident set size from /proc. Returns None on
+/// platforms without procfs.
+fn get_resident_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kilobytes: u64 = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+fn report_memory_usage_system(
+    particles: Particles<()>,
+    haloes: HaloParticles<()>,
+    cells: Query<&Cell>,
+    tree: Option<Res<QuadTree>>,
+    num_tetras: Option<Res<NumTetras>>,
+    parameters: Res<MemoryParameters>,
+    mut usage: ResMut<MemoryUsage>,
+    mut num_steps: Local<usize>,
+) {
+    let report_interval = parameters.report_interval.unwrap();
+    *num_steps += 1;
+    if (*num_steps - 1) % report_interval != 0 {
+        return;
+    }
+    *usage = MemoryUsage {
+        resident_memory: get_resident_memory().unwrap_or(0),
+        num_particles: particles.iter().count(),
+        num_haloes: haloes.iter().count(),
+        num_cells: cells.iter().count(),
+        num_tree_nodes: tree.map(|tree| tree.num_nodes()).unwrap_or(0),
+        num_local_tetras: num_tetras.as_ref().map(|n| n.local).unwrap_or(0),
+        num_halo_tetras: num_tetras.as_ref().map(|n| n.halo).unwrap_or(0),
+    };
+    debug!("Memory usage on this rank: {:?}", *usage);
+    let mut world = MpiWorld::<MemoryUsage>::new();
+    let all_usages = world.all_gather(&usage);
+    if parameters.log_per_rank && world.rank() == 0 {
+        log_per_rank(&all_usages);
+    }
+    let max = all_usages
+        .into_iter()
+        .fold(MemoryUsage::default(), MemoryUsage::max);
+    info!(
+        "Max memory usage per rank: {:.1} MB ({} particles, {} haloes, {} cells, {} tree nodes, {} local tetras, {} halo tetras)",
+        max.resident_memory as f64 / 1e6,
+        max.num_particles,
+        max.num_haloes,
+        max.num_cells,
+        max.num_tree_nodes,
+        max.num_local_tetras,
+        max.num_halo_tetras,
+    );
+}
+
+fn log_per_rank(all_usages: &[MemoryUsage]) {
+    info!(
+        "{:>6} {:>12} {:>12} {:>10} {:>12} {:>12} {:>12} {:>12}",
+        "Rank",
+        "Memory [MB]",
+        "Particles",
+        "Haloes",
+        "Cells",
+        "Tree nodes",
+        "Local tetras",
+        "Halo tetras"
+    );
+    for (rank, usage) in all_usages.iter().enumerate() {
+        info!(
+            "{:>6} {:>12.1} {:>12} {:>10} {:>12} {:>12} {:>12} {:>12}",
+            rank,
+            usage.resident_memory as f64 / 1e6,
+            usage.num_particles,
+            usage.num_haloes,
+            usage.num_cells,
+            usage.num_tree_nodes,
+            usage.num_local_tetras,
+            usage.num_halo_tetras,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::prelude::*;
+
+    use super::report_memory_usage_system;
+    use super::MemoryParameters;
+    use super::MemoryUsage;
+    use crate::prelude::LocalParticle;
+    use crate::test_utils::run_system_on_world;
+    use crate::voronoi::NumTetras;
+
+    #[test]
+    fn memory_usage_is_reported() {
+        let mut world = World::new();
+        for _ in 0..5 {
+            world.spawn(LocalParticle);
+        }
+        world.insert_resource(MemoryParameters {
+            report_interval: Some(1),
+            log_per_rank: true,
+        });
+        world.insert_resource(MemoryUsage::default());
+        world.insert_resource(NumTetras { local: 7, halo: 3 });
+        run_system_on_world(&mut world, report_memory_usage_system);
+        let usage = world.resource::<MemoryUsage>();
+        assert!(usage.resident_memory > 0);
+        assert_eq!(usage.num_particles, 5);
+        assert_eq!(usage.num_cells, 0);
+        assert_eq!(usage.num_local_tetras, 7);
+        assert_eq!(usage.num_halo_tetras, 3);
+    }
+}
@@ -71,34 +71,51 @@ fn extract_from_section<T: SubsweepParameters>(
     // The following is a workaround for deserializing a serde_yaml::Value,
     // which fails when visiting dimensionless quantities (which will be interpreted as floats)
     insert_overrides(section_value, overrides);
-    serde_yaml::from_str(&serde_yaml::to_string(section_value).unwrap()).unwrap_or_else(|err| {
-        panic!(
-            "Failed to read parameter file section \"{:?}\": \n{}",
-            T::section_name(),
-            err
-        )
-    })
+    let extracted: T = serde_yaml::from_str(&serde_yaml::to_string(section_value).unwrap())
+        .unwrap_or_else(|err| {
+            panic!(
+                "Failed to read parameter file section \"{:?}\": \n{}",
+                T::section_name(),
+                err
+            )
+        });
+    // Store the parameters as they are actually used, including
+    // the default values of omitted fields.
+    *section_value = serde_yaml::to_value(&extracted).unwrap();
+    extracted
 }
 
-/// Constructs a map of the form
+/// Constructs a value of the form
 /// key1: key2: key3: ... key_n: Value
+/// where each key that is a list index constructs a list instead of
+/// a map. Since the list is new, the index needs to be 0.
 /// If keys is empty, returns value
-fn construct_sub_mapping(keys: &[String], value: Value) -> Value {
+fn construct_sub_value(keys: &[String], value: Value) -> Value {
     if keys.is_empty() {
         value
+    } else if is_list_index(&keys[0]) {
+        let mut sequence = vec![];
+        set_sequence_entry_by_keys(&mut sequence, keys, value);
+        Value::Sequence(sequence)
     } else {
         let mut map = Mapping::default();
         map.insert(
             Value::String(keys[0].clone()),
-            construct_sub_mapping(&keys[1..], value),
+            construct_sub_value(&keys[1..], value),
         );
         Value::Mapping(map)
     }
 }
 
+fn is_list_index(key: &str) -> bool {
+    key.parse::<usize>().is_ok()
+}
+
 fn set_sublevel_value_by_keys(value: &mut Value, keys: &[String], target_value: Value) {
     if keys.is_empty() {
         *value = target_value;
+    } else if let Value::Sequence(sequence) = value {
+        set_sequence_entry_by_keys(sequence, keys, target_value);
     } else {
         let mapping = value.as_mapping_mut();
         match mapping {
@@ -107,7 +124,7 @@ fn set_sublevel_value_by_keys(value: &mut Value, keys: &[String], target_value:
                 None => {
                     mapping.insert(
                         Value::String(keys[0].clone()),
-                        construct_sub_mapping(&keys[1..], target_value),
+                        construct_sub_value(&keys[1..], target_value),
                     );
                 }
             },
@@ -116,6 +133,28 @@ fn set_sublevel_value_by_keys(value: &mut Value, keys: &[String], target_value:
     }
 }
 
+/// Lists are addressed by the index of the entry. An index one past
+/// the end of the list appends a new entry.
+fn set_sequence_entry_by_keys(sequence: &mut Vec<Value>, keys: &[String], target_value: Value) {
+    let index: usize = keys[0].parse().unwrap_or_else(|_| {
+        panic!(
+            "Invalid parameter override: Expected list index, found `{}`",
+            keys[0]
+        )
+    });
+    if index < sequence.len() {
+        set_sublevel_value_by_keys(&mut sequence[index], &keys[1..], target_value);
+    } else if index == sequence.len() {
+        sequence.push(construct_sub_value(&keys[1..], target_value));
+    } else {
+        panic!(
+            "Invalid parameter override: Index {} out of range for list of length {}",
+            index,
+            sequence.len()
+        );
+    }
+}
+
 impl ParameterFileContents {
     pub fn new(contents: String) -> Self {
         let sections = serde_yaml::from_str(&contents)
@@ -151,14 +190,31 @@ impl ParameterFileContents {
             .cloned()
     }
 
+    /// The merged parameters as yaml, with the sections sorted by
+    /// name.
     pub fn contents(&self) -> String {
+        let mut sections: Vec<_> = self.sections.iter().collect();
+        sections.sort_by_key(|(name, _)| *name);
         let mut map = serde_yaml::Mapping::default();
-        for (name, value) in self.sections.iter() {
+        for (name, value) in sections {
             map.insert(Value::String(name.into()), value.clone());
         }
         serde_yaml::to_string(&map).unwrap()
     }
 
+    /// A hash of the merged parameters which does not depend on
+    /// the order of the sections. Uses 64 bit FNV-1a, so that the
+    /// hash is the same across platforms and compiler versions.
+    pub fn hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+        self.contents()
+            .bytes()
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+
     pub(super) fn extract_parameter_struct<T: SubsweepParameters>(&mut self) -> T {
         let section_name = T::unwrap_section_name();
         let overrides_this_section = self
@@ -184,6 +240,9 @@ mod tests {
 
     use super::Override;
     use super::ParameterFileContents;
+    use crate::domain::Extent;
+    use crate::units::Length;
+    use crate::units::Time;
 
     #[subsweep_parameters("x")]
     struct X {
@@ -204,6 +263,78 @@ mod tests {
         assert_eq!(x.b, 2);
     }
 
+    #[test]
+    #[should_panic(expected = "unknown field `c`")]
+    fn misspelled_key_is_rejected() {
+        let mut contents = ParameterFileContents::new("x:\n  a: 1\n  b: 2\n  c: 3".into());
+        contents.extract_parameter_struct::<X>();
+    }
+
+    #[test]
+    fn contents_include_defaults_and_overrides() {
+        #[subsweep_parameters("v")]
+        struct V {
+            a: usize,
+            #[serde(default)]
+            b: usize,
+        }
+
+        let mut contents = ParameterFileContents::new("v:\n  a: 1".into());
+        contents.with_overrides(vec![Override {
+            section: "v".into(),
+            keys: vec!["a".into()],
+            value: 5.into(),
+        }]);
+        contents.extract_parameter_struct::<V>();
+        let resolved =
+            ParameterFileContents::new(contents.contents()).extract_parameter_struct::<V>();
+        assert_eq!(resolved.a, 5);
+        assert_eq!(resolved.b, 0);
+        assert!(contents.contents().contains("b: 0"));
+    }
+
+    #[test]
+    fn contents_with_quantities_and_extents_round_trip() {
+        #[subsweep_parameters("q")]
+        struct Q {
+            time: Time,
+            length: Length,
+            extent: Extent,
+            #[serde(default)]
+            optional_time: Option<Time>,
+        }
+
+        let mut contents =
+            ParameterFileContents::new("q:\n  time: 3 s\n  length: 2000 m\n  extent: 5 m".into());
+        contents.with_overrides(vec![Override {
+            section: "q".into(),
+            keys: vec!["optional_time".into()],
+            value: "7 s".into(),
+        }]);
+        let original = contents.extract_parameter_struct::<Q>();
+        let resolved =
+            ParameterFileContents::new(contents.contents()).extract_parameter_struct::<Q>();
+        assert_eq!(resolved.time, Time::seconds(3.0));
+        assert_eq!(resolved.length, Length::meters(2000.0));
+        assert_eq!(resolved.optional_time, Some(Time::seconds(7.0)));
+        assert_eq!(resolved.extent.min, original.extent.min);
+        assert_eq!(resolved.extent.max, original.extent.max);
+        assert_eq!(
+            resolved.extent.max - resolved.extent.min,
+            Extent::cube_from_side_length(Length::meters(5.0)).side_lengths()
+        );
+    }
+
+    #[test]
+    fn hash_depends_on_contents_but_not_on_section_order() {
+        let hash = |contents: &str| ParameterFileContents::new(contents.into()).hash();
+        assert_eq!(hash("x:\n  a: 1\ny: 2"), hash("y: 2\nx:\n  a: 1"));
+        assert_ne!(hash("x:\n  a: 1\ny: 2"), hash("x:\n  a: 2\ny: 2"));
+        // The hash is written to the output files, so it must not
+        // change between runs or builds.
+        assert_eq!(hash("x:\n  a: 1"), 0x90ee2c44724f22db);
+    }
+
     #[test]
     fn r#override_in_omitted_section() {
         let mut contents = ParameterFileContents::new("{}".into());
@@ -258,4 +389,115 @@ mod tests {
         assert_eq!(y.a, 5);
         assert_eq!(y.b, 2);
     }
+
+    #[test]
+    fn r#override_list_entries() {
+        #[subsweep_parameters]
+        struct Entry {
+            a: usize,
+            b: usize,
+        }
+
+        #[subsweep_parameters("z")]
+        struct Z {
+            paths: Vec<String>,
+            nested: Vec<Entry>,
+        }
+
+        let mut contents = ParameterFileContents::new(
+            "z:\n  paths: [a, b]\n  nested:\n    - a: 1\n      b: 2\n    - a: 3\n      b: 4".into(),
+        );
+        contents.with_overrides(vec![
+            Override {
+                section: "z".into(),
+                keys: vec!["paths".into(), "1".into()],
+                value: "c".into(),
+            },
+            Override {
+                section: "z".into(),
+                keys: vec!["paths".into(), "2".into()],
+                value: "d".into(),
+            },
+            Override {
+                section: "z".into(),
+                keys: vec!["nested".into(), "1".into(), "b".into()],
+                value: 5.into(),
+            },
+        ]);
+        let z = contents.extract_parameter_struct::<Z>();
+        assert_eq!(z.paths, vec!["a", "c", "d"]);
+        assert_eq!(z.nested[0].a, 1);
+        assert_eq!(z.nested[0].b, 2);
+        assert_eq!(z.nested[1].a, 3);
+        assert_eq!(z.nested[1].b, 5);
+    }
+
+    #[test]
+    fn r#override_creates_missing_lists() {
+        #[subsweep_parameters]
+        struct Entry {
+            a: usize,
+        }
+
+        #[subsweep_parameters("u")]
+        struct U {
+            #[serde(default)]
+            paths: Vec<String>,
+            #[serde(default)]
+            nested: Vec<Entry>,
+        }
+
+        let mut contents = ParameterFileContents::new("u: {}".into());
+        contents.with_overrides(vec![
+            Override {
+                section: "u".into(),
+                keys: vec!["paths".into(), "0".into()],
+                value: "a".into(),
+            },
+            Override {
+                section: "u".into(),
+                keys: vec!["nested".into(), "0".into(), "a".into()],
+                value: 3.into(),
+            },
+        ]);
+        let u = contents.extract_parameter_struct::<U>();
+        assert_eq!(u.paths, vec!["a"]);
+        assert_eq!(u.nested.len(), 1);
+        assert_eq!(u.nested[0].a, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 1 out of range for list of length 0")]
+    fn r#override_missing_list_with_nonzero_index() {
+        #[subsweep_parameters("t")]
+        struct T {
+            #[serde(default)]
+            paths: Vec<String>,
+        }
+
+        let mut contents = ParameterFileContents::new("t: {}".into());
+        contents.with_overrides(vec![Override {
+            section: "t".into(),
+            keys: vec!["paths".into(), "1".into()],
+            value: "a".into(),
+        }]);
+        contents.extract_parameter_struct::<T>();
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 3 out of range for list of length 2")]
+    fn r#override_list_entry_out_of_range() {
+        #[subsweep_parameters("w")]
+        struct W {
+            paths: Vec<String>,
+        }
+
+        let mut contents = ParameterFileContents::new("w:\n  paths: [a, b]".into());
+        contents.with_overrides(vec![Override {
+            section: "w".into(),
+            keys: vec!["paths".into(), "3".into()],
+            value: "c".into(),
+        }]);
+        contents.extract_parameter_struct::<W>();
+    }
 }
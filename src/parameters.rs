@@ -1,8 +1,12 @@
+pub use crate::chemistry::hydrogen_only::ChemistryParameters;
 pub use crate::cosmology::Cosmology;
+pub use crate::domain::DomainParameters;
 pub use crate::io::input::InputParameters;
 pub use crate::io::output::parameters::Fields;
 pub use crate::io::output::parameters::HandleExistingOutput;
 pub use crate::io::output::parameters::OutputParameters;
+pub use crate::io::tracer::TracerParameters;
+pub use crate::memory::MemoryParameters;
 pub use crate::prelude::SimulationBox;
 pub use crate::simulation_box::SimulationBoxParameters;
 pub use crate::simulation_plugin::SimulationParameters;
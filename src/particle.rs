@@ -6,6 +6,8 @@ use bevy_ecs::prelude::Query;
 use bevy_ecs::prelude::With;
 use log::debug;
 use mpi::traits::Equivalence;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::communication::Rank;
 use crate::components::Position;
@@ -15,7 +17,19 @@ use crate::prelude::StartupStages;
 use crate::simulation::SubsweepPlugin;
 
 #[derive(
-    Component, Clone, Debug, PartialEq, Eq, Hash, Equivalence, Copy, Named, PartialOrd, Ord,
+    Component,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Equivalence,
+    Copy,
+    Named,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
 )]
 #[name = "id"]
 pub struct ParticleId {
@@ -1,3 +1,7 @@
+use std::ops::Add;
+use std::ops::Mul;
+use std::ops::Sub;
+
 use glam::DVec2;
 use glam::DVec3;
 use mpi::datatype::UserDatatype;
@@ -29,6 +33,19 @@ unsafe impl Equivalence for PeanoKey3d {
     }
 }
 
+/// The fraction of the size of the extent by which it is padded on
+/// each side before computing keys, so that points on its boundary
+/// still map to valid keys.
+const PADDING_FRACTION: f64 = 0.001;
+
+fn pad<V>(min: V, max: V) -> (V, V)
+where
+    V: Copy + Add<Output = V> + Sub<Output = V> + Mul<f64, Output = V>,
+{
+    let padding = (max - min) * PADDING_FRACTION;
+    (min - padding, max + padding)
+}
+
 fn get_integer_position_2d(pos: DVec2) -> (u64, u64) {
     (
         (pos.x * NUM_SUBDIVISIONS_2D as f64) as u64,
@@ -46,8 +63,7 @@ fn get_integer_position_3d(pos: DVec3) -> (u128, u128, u128) {
 
 impl PeanoKey2d {
     pub fn from_point_and_min_max(pos: DVec2, min: DVec2, max: DVec2) -> Self {
-        let min_padded = min - (max - min) * 0.001;
-        let max_padded = max + (max - min) * 0.001;
+        let (min_padded, max_padded) = pad(min, max);
         Self::from_scaled_vec((pos - min_padded) / (max_padded - min_padded))
     }
 
@@ -56,6 +72,17 @@ impl PeanoKey2d {
         Self::from_integer_pos(integer_pos)
     }
 
+    /// The inverse of
+    /// [from_point_and_min_max](Self::from_point_and_min_max), up to
+    /// the resolution of the key: Returns the center of the smallest
+    /// cell which maps to this key.
+    pub fn cell_center(self, min: DVec2, max: DVec2) -> DVec2 {
+        let (x, y) = self.to_integer_pos();
+        let scaled = (DVec2::new(x as f64, y as f64) + 0.5) / NUM_SUBDIVISIONS_2D as f64;
+        let (min_padded, max_padded) = pad(min, max);
+        min_padded + scaled * (max_padded - min_padded)
+    }
+
     // Source: https://en.wikipedia.org/wiki/Hilbert_curve
     fn from_integer_pos((mut x, mut y): (u64, u64)) -> Self {
         let mut s = NUM_SUBDIVISIONS_2D / 2;
@@ -79,12 +106,28 @@ impl PeanoKey2d {
             std::mem::swap(x, y);
         }
     }
+
+    fn to_integer_pos(self) -> (u64, u64) {
+        let mut t = self.0;
+        let mut x = 0;
+        let mut y = 0;
+        let mut s = 1;
+        while s < NUM_SUBDIVISIONS_2D {
+            let rx = 1 & (t / 2);
+            let ry = 1 & (t ^ rx);
+            Self::rot(s, &mut x, &mut y, rx, ry);
+            x += s * rx;
+            y += s * ry;
+            t /= 4;
+            s *= 2;
+        }
+        (x, y)
+    }
 }
 
 impl PeanoKey3d {
     pub fn from_point_and_min_max(pos: DVec3, min: DVec3, max: DVec3) -> Self {
-        let min_padded = min - (max - min) * 0.001;
-        let max_padded = max + (max - min) * 0.001;
+        let (min_padded, max_padded) = pad(min, max);
         Self::from_scaled_vec((pos - min_padded) / (max_padded - min_padded))
     }
 
@@ -93,6 +136,17 @@ impl PeanoKey3d {
         Self::from_integer_pos(integer_pos)
     }
 
+    /// The inverse of
+    /// [from_point_and_min_max](Self::from_point_and_min_max), up to
+    /// the resolution of the key: Returns the center of the smallest
+    /// cell which maps to this key.
+    pub fn cell_center(self, min: DVec3, max: DVec3) -> DVec3 {
+        let (x, y, z) = self.to_integer_pos();
+        let scaled = (DVec3::new(x as f64, y as f64, z as f64) + 0.5) / NUM_SUBDIVISIONS_3D as f64;
+        let (min_padded, max_padded) = pad(min, max);
+        min_padded + scaled * (max_padded - min_padded)
+    }
+
     fn from_integer_pos((x, y, z): (u128, u128, u128)) -> Self {
         let mut rotation: usize = 0;
         let mut key: u128 = 0;
@@ -111,6 +165,23 @@ impl PeanoKey3d {
         }
         PeanoKey3d(key)
     }
+
+    fn to_integer_pos(self) -> (u128, u128, u128) {
+        let mut rotation: usize = 0;
+        let (mut x, mut y, mut z) = (0, 0, 0);
+        for level in (0..NUM_BITS_PER_DIMENSION_3D).rev() {
+            let subpix = (self.0 >> (3 * level)) & 7;
+            let pix = SUBPIX_TABLE[rotation]
+                .iter()
+                .position(|entry| *entry == subpix)
+                .unwrap();
+            x = (x << 1) | ((pix >> 2) & 1) as u128;
+            y = (y << 1) | ((pix >> 1) & 1) as u128;
+            z = (z << 1) | (pix & 1) as u128;
+            rotation = ROTATION_TABLE[rotation][pix];
+        }
+        (x, y, z)
+    }
 }
 
 // Source: Arepo code / Martin Reinecke
@@ -218,27 +289,12 @@ const SUBPIX_TABLE: [[u128; 8]; 48] = [
 
 #[cfg(test)]
 mod tests {
+    use glam::DVec2;
+    use glam::DVec3;
+
     use super::PeanoKey2d;
-    use super::NUM_SUBDIVISIONS_2D;
-
-    impl PeanoKey2d {
-        fn to_integer_pos(self) -> (u64, u64) {
-            let mut t = self.0;
-            let mut x = 0;
-            let mut y = 0;
-            let mut s = 1;
-            while s < NUM_SUBDIVISIONS_2D {
-                let rx = 1 & (t / 2);
-                let ry = 1 & (t ^ rx);
-                Self::rot(s, &mut x, &mut y, rx, ry);
-                x += s * rx;
-                y += s * ry;
-                t /= 4;
-                s *= 2;
-            }
-            (x, y)
-        }
-    }
+    use super::PeanoKey3d;
+
     #[test]
     fn peano_hilbert_map_is_isomorphic() {
         for x in 0..30 {
@@ -248,4 +304,33 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn peano_hilbert_map_is_isomorphic_3d() {
+        for x in 0..10 {
+            for y in 0..10 {
+                for z in 0..10 {
+                    let pos = (x * 12345, y * 6789, z << 30);
+                    let d = PeanoKey3d::from_integer_pos(pos);
+                    assert_eq!(d.to_integer_pos(), pos);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cell_center_is_close_to_point() {
+        let min = DVec3::new(-1.0, 0.0, 2.0);
+        let max = DVec3::new(1.0, 5.0, 3.0);
+        for pos in [min, max, DVec3::new(0.3, 4.1, 2.7)] {
+            let key = PeanoKey3d::from_point_and_min_max(pos, min, max);
+            assert!((key.cell_center(min, max) - pos).length() < 1e-10);
+        }
+        let min = DVec2::new(-1.0, 0.0);
+        let max = DVec2::new(1.0, 5.0);
+        for pos in [min, max, DVec2::new(0.3, 4.1)] {
+            let key = PeanoKey2d::from_point_and_min_max(pos, min, max);
+            assert!((key.cell_center(min, max) - pos).length() < 1e-8);
+        }
+    }
 }
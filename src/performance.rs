@@ -127,6 +127,11 @@ impl Performance {
             .total()
     }
 
+    /// The total run time recorded for `name`, if any.
+    pub(crate) fn try_total(&self, name: &str) -> Option<Time> {
+        self.results.get(name).map(|result| result.total())
+    }
+
     pub fn record_number<N: Into<String>>(&mut self, name: N, val: impl TryInto<i32>) {
         match val.try_into() {
             Ok(val) => self.results.insert(name.into(), Result::Number(val)),
@@ -18,7 +18,9 @@ pub use crate::simulation_builder::SimulationBuilder;
 pub use crate::simulation_plugin::Stages;
 pub use crate::simulation_plugin::StartupStages;
 pub use crate::simulation_plugin::StopSimulationEvent;
+pub use crate::stage_profiling::StageProfilingPlugin;
 pub use crate::sweep::SweepPlugin;
+pub use crate::sweep::SweepSystemLabel;
 pub use crate::units;
 pub use crate::units::helpers::Float;
 pub use crate::units::helpers::MVec;
@@ -0,0 +1,152 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::LeafDataType;
+use super::Node;
+use super::QuadTree;
+use crate::units::Length;
+use crate::units::VecLength;
+
+/// A leaf in the heap of the closest leaves found so far, ordered
+/// by its distance so that the furthest one is on top.
+struct Candidate<'a, L> {
+    distance: Length,
+    leaf: &'a L,
+}
+
+impl<'a, L> PartialEq for Candidate<'a, L> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<'a, L> Eq for Candidate<'a, L> {}
+
+impl<'a, L> PartialOrd for Candidate<'a, L> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a, L> Ord for Candidate<'a, L> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .value_unchecked()
+            .total_cmp(&other.distance.value_unchecked())
+    }
+}
+
+impl<N, L: LeafDataType> QuadTree<N, L> {
+    /// Returns the `k` leaves closest to `center` (or all leaves, if
+    /// the tree contains fewer than `k`) together with their
+    /// distance, sorted by distance. Does not take periodic images
+    /// into account.
+    pub fn k_nearest(&self, center: VecLength, k: usize) -> Vec<(Length, &L)> {
+        let mut candidates = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.collect_k_nearest(&center, k, &mut candidates);
+        }
+        candidates
+            .into_sorted_vec()
+            .into_iter()
+            .map(|candidate| (candidate.distance, candidate.leaf))
+            .collect()
+    }
+
+    fn collect_k_nearest<'a>(
+        &'a self,
+        center: &VecLength,
+        k: usize,
+        candidates: &mut BinaryHeap<Candidate<'a, L>>,
+    ) {
+        if candidates.len() == k
+            && self.extent.distance_to(center) > candidates.peek().unwrap().distance
+        {
+            return;
+        }
+        match self.node {
+            Node::Tree(ref children) => {
+                let mut children: Vec<_> = children
+                    .iter()
+                    .map(|child| (child.extent.distance_to(center), child))
+                    .collect();
+                children.sort_by(|(d1, _), (d2, _)| {
+                    d1.value_unchecked().total_cmp(&d2.value_unchecked())
+                });
+                for (_, child) in children {
+                    child.collect_k_nearest(center, k, candidates);
+                }
+            }
+            Node::Leaf(ref leaf) => {
+                for leaf in leaf.iter() {
+                    let candidate = Candidate {
+                        distance: leaf.pos().distance(center),
+                        leaf,
+                    };
+                    if candidates.len() < k {
+                        candidates.push(candidate);
+                    } else if candidate < *candidates.peek().unwrap() {
+                        candidates.pop();
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "3d")]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use crate::domain::extent::Extent3d;
+    use crate::domain::LeafData;
+    use crate::prelude::ParticleId;
+    use crate::quadtree::QuadTree;
+    use crate::quadtree::QuadTreeConfig;
+    use crate::units::VecLength;
+
+    #[test]
+    fn k_nearest_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(1338);
+        let particles: Vec<_> = (0..300)
+            .map(|i| LeafData {
+                id: ParticleId::test(i),
+                pos: VecLength::meters(
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                    rng.gen_range(0.0..1.0),
+                ),
+            })
+            .collect();
+        let extent = Extent3d::from_positions(particles.iter().map(|leaf| &leaf.pos)).unwrap();
+        let tree: QuadTree<(), _> =
+            QuadTree::new(&QuadTreeConfig::default(), particles.clone(), &extent);
+        for k in [0, 1, 10, 32, 400] {
+            for _ in 0..20 {
+                let center = VecLength::meters(
+                    rng.gen_range(-0.5..1.5),
+                    rng.gen_range(-0.5..1.5),
+                    rng.gen_range(-0.5..1.5),
+                );
+                let mut direct: Vec<_> = particles
+                    .iter()
+                    .map(|particle| (particle.pos.distance(&center), particle.id))
+                    .collect();
+                direct.sort_by(|(d1, _), (d2, _)| {
+                    d1.value_unchecked().total_cmp(&d2.value_unchecked())
+                });
+                direct.truncate(k);
+                let from_tree: Vec<_> = tree
+                    .k_nearest(center, k)
+                    .into_iter()
+                    .map(|(distance, particle)| (distance, particle.id))
+                    .collect();
+                assert_eq!(from_tree, direct);
+            }
+        }
+    }
+}
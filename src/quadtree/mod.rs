@@ -1,4 +1,5 @@
 pub mod config;
+mod k_nearest;
 mod node_index;
 pub mod radius_search;
 
@@ -105,6 +106,18 @@ impl<N: NodeDataType<L>, L: LeafDataType> QuadTree<N, L> {
         }
     }
 
+    pub fn num_nodes(&self) -> usize {
+        match self.node {
+            Node::Tree(ref children) => {
+                1 + children
+                    .iter()
+                    .map(|child| child.num_nodes())
+                    .sum::<usize>()
+            }
+            Node::Leaf(_) => 1,
+        }
+    }
+
     pub fn depth_first_map_leaf<'a>(&'a self, closure: &mut impl FnMut(&'a Extent, &'a [L])) {
         match self.node {
             Node::Tree(ref node) => {
@@ -55,6 +55,15 @@ impl<N, L: LeafDataType> QuadTree<N, L> {
     }
 }
 
+impl<N, L: LeafDataType> QuadTree<N, L> {
+    /// Returns all leaves within `radius` of `center`. Unlike
+    /// [QuadTree::iter_particles_in_radius], this does not take
+    /// periodic images into account.
+    pub fn find_within_radius(&self, center: VecLength, radius: Length) -> Vec<&L> {
+        TreeIter::new(self, RadiusSearch { center, radius }).collect()
+    }
+}
+
 impl<N, L> QuadTree<N, L> {
     pub fn iter(&self) -> TreeIter<N, L, EntireTree> {
         TreeIter::new(self, EntireTree)
@@ -219,6 +228,22 @@ impl<'a, N, L: LeafDataType> SearchCriterion<N, L> for PeriodicRadiusSearch<'a>
     }
 }
 
+#[derive(Debug)]
+struct RadiusSearch {
+    center: VecLength,
+    radius: Length,
+}
+
+impl<N, L: LeafDataType> SearchCriterion<N, L> for RadiusSearch {
+    fn should_visit_node(&self, tree: &QuadTree<N, L>) -> bool {
+        tree.extent.overlaps_sphere(&self.center, self.radius)
+    }
+
+    fn should_include_leaf(&self, particle: &L) -> bool {
+        particle.pos().distance(&self.center) < self.radius
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "3d")]
 mod tests {
@@ -347,4 +372,32 @@ mod tests {
             assert_eq!(tree_entities, direct_entities);
         }
     }
+
+    #[test]
+    fn non_periodic_radius_search() {
+        let radius = Length::meters(2.0);
+        let particles: Vec<_> = get_particles(12, 12)
+            .into_iter()
+            .map(|particle| LeafData {
+                id: particle.id,
+                pos: particle.pos,
+            })
+            .collect();
+        let extent = Extent3d::from_positions(particles.iter().map(|leaf| &leaf.pos)).unwrap();
+        let tree: QuadTree<(), _> =
+            QuadTree::new(&QuadTreeConfig::default(), particles.clone(), &extent);
+        for particle in particles.iter() {
+            let tree_neighbours: HashSet<_> = tree
+                .find_within_radius(particle.pos, radius)
+                .into_iter()
+                .map(|particle| particle.id)
+                .collect();
+            let direct_neighbours: HashSet<_> =
+                direct_neighbour_search(&particles, &particle.pos, &radius)
+                    .into_iter()
+                    .map(|particle| particle.id)
+                    .collect();
+            assert_eq!(tree_neighbours, direct_neighbours);
+        }
+    }
 }
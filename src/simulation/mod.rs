@@ -3,9 +3,11 @@ mod subsweep_plugin;
 use bevy_app::prelude::App;
 use bevy_app::prelude::Plugin;
 use bevy_app::prelude::PluginGroup;
+use bevy_app::StartupSchedule;
 use bevy_ecs::event::Event;
 use bevy_ecs::prelude::Component;
 use bevy_ecs::prelude::Mut;
+use bevy_ecs::prelude::Schedule;
 use bevy_ecs::prelude::Stage;
 use bevy_ecs::prelude::StageLabel;
 use bevy_ecs::prelude::SystemSet;
@@ -17,6 +19,7 @@ use bevy_ecs::schedule::SystemDescriptor;
 use bevy_ecs::schedule::SystemLabelId;
 use bevy_ecs::system::Resource;
 use derive_traits::SubsweepParameters;
+use log::info;
 use log::warn;
 use mpi::traits::Equivalence;
 use mpi::traits::MatchesRaw;
@@ -28,6 +31,8 @@ use crate::hash_map::HashMap;
 use crate::hash_map::HashSet;
 use crate::io::input::ComponentInput;
 use crate::io::input::DatasetInputPlugin;
+use crate::io::output::timer::DatasetTimer;
+use crate::io::output::timer::OutputCadence;
 use crate::io::output::OutputPlugin;
 use crate::io::to_dataset::ToDataset;
 use crate::io::DatasetDescriptor;
@@ -45,6 +50,9 @@ pub struct Simulation {
     ordering_labels: HashMap<&'static str, Vec<SystemLabelId>>,
     pub read_initial_conditions: bool,
     pub write_output: bool,
+    pub restart: bool,
+    pub dry_run: bool,
+    pub max_steps: Option<usize>,
 }
 
 impl Default for Simulation {
@@ -58,6 +66,9 @@ impl Default for Simulation {
             ordering_labels: HashMap::default(),
             read_initial_conditions: false,
             write_output: false,
+            restart: false,
+            dry_run: false,
+            max_steps: None,
         }
     }
 }
@@ -88,6 +99,21 @@ impl Simulation {
         self
     }
 
+    pub fn restart(&mut self, restart: bool) -> &mut Self {
+        self.restart = restart;
+        self
+    }
+
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn max_steps(&mut self, max_steps: Option<usize>) -> &mut Self {
+        self.max_steps = max_steps;
+        self
+    }
+
     pub fn already_added<P: Named>(&mut self) -> bool {
         !self.labels.insert(P::name())
     }
@@ -308,9 +334,28 @@ impl Simulation {
         {
             self.validate();
         }
+        if self.dry_run {
+            self.run_startup_schedule();
+            info!("Dry run finished, exiting before the first timestep.");
+            return;
+        }
         self.app.run();
     }
 
+    /// Runs only the startup stages (reading the initial conditions,
+    /// domain decomposition, grid construction, ...) and none of the
+    /// main stages. The startup schedule is a stage of the main
+    /// schedule, so this cannot be done by sending an `AppExit` from
+    /// a startup system: the runner only checks for it after the
+    /// main stages of the same update have run.
+    fn run_startup_schedule(&mut self) {
+        self.app
+            .schedule
+            .get_stage_mut::<Schedule>(StartupSchedule)
+            .unwrap()
+            .run(&mut self.app.world);
+    }
+
     pub fn update(&mut self) {
         self.app.update()
     }
@@ -430,10 +475,22 @@ impl Simulation {
                 self.add_plugin(DatasetInputPlugin::<T>::from_descriptor(descriptor));
             }
             ComponentInput::Derived => {}
+            ComponentInput::OptionalWithDefault(descriptor) => {
+                self.add_plugin(DatasetInputPlugin::<T>::optional_from_descriptor(
+                    descriptor,
+                ));
+            }
         }
         self
     }
 
+    /// Sets how often the dataset of a component is written to the
+    /// snapshots. Can be called before or after the component is
+    /// registered. Ignored if the snapshots are checkpoints.
+    pub fn set_output_cadence<T: 'static>(&mut self, cadence: OutputCadence) -> &mut Self {
+        self.insert_resource(DatasetTimer::<T>::new(cadence))
+    }
+
     pub fn add_required_component<T>(&mut self) -> &mut Self
     where
         T: Equivalence + ToDataset + Component + Named,
@@ -500,7 +557,13 @@ impl Simulation {
 
 #[cfg(test)]
 mod tests {
+    use bevy_ecs::prelude::ResMut;
+    use bevy_ecs::prelude::Resource;
+
     use crate::named::Named;
+    use crate::performance::Performance;
+    use crate::prelude::Stages;
+    use crate::prelude::StartupStages;
     use crate::simulation::Simulation;
     use crate::simulation::SubsweepPlugin;
 
@@ -528,4 +591,28 @@ parameters1:
         sim.add_parameter_file_contents(contents.into());
         sim.run();
     }
+
+    #[test]
+    fn dry_run_only_runs_startup_stages() {
+        #[derive(Resource, Default)]
+        struct NumRuns {
+            startup: usize,
+            main: usize,
+        }
+        let mut sim = Simulation::default();
+        sim.add_parameter_file_contents("{}".into())
+            .insert_non_send_resource(Performance::default())
+            .insert_resource(NumRuns::default())
+            .add_startup_system_to_stage(StartupStages::Final, |mut num_runs: ResMut<NumRuns>| {
+                num_runs.startup += 1
+            })
+            .add_system_to_stage(Stages::Initial, |mut num_runs: ResMut<NumRuns>| {
+                num_runs.main += 1
+            })
+            .dry_run(true);
+        sim.run_without_finalize();
+        let num_runs = sim.unwrap_resource::<NumRuns>();
+        assert_eq!(num_runs.startup, 1);
+        assert_eq!(num_runs.main, 0);
+    }
 }
@@ -5,6 +5,7 @@ use derive_more::DerefMut;
 use derive_more::From;
 use derive_more::Into;
 
+use crate::dimension::ActiveWrapType;
 use crate::domain::Extent;
 use crate::parameters::Cosmology;
 use crate::prelude::Float;
@@ -61,12 +62,18 @@ fn periodic_wrap_component(v: Float, min: Float, max: Float) -> Float {
 }
 
 fn minimize_component(v: Float, length: Float) -> Float {
+    minimize_component_with_wrap(v, length).0
+}
+
+/// Also returns the wrap which needs to be applied to the second
+/// point to obtain the minimized component.
+fn minimize_component_with_wrap(v: Float, length: Float) -> (Float, WrapType) {
     if v > length / 2.0 {
-        v - length
+        (v - length, WrapType::Plus)
     } else if v < -length / 2.0 {
-        v + length
+        (v + length, WrapType::Minus)
     } else {
-        v
+        (v, WrapType::NoWrap)
     }
 }
 
@@ -126,6 +133,36 @@ impl SimulationBox {
         dist
     }
 
+    /// Like [periodic_distance_vec](Self::periodic_distance_vec), but
+    /// also returns the periodic image of `p2` that is closest to
+    /// `p1`, i.e. `p1 - (p2 + translation) = distance`, where
+    /// `translation` is the translation of the returned wrap type.
+    pub fn periodic_distance_vec_with_wrap(
+        &self,
+        p1: &VecLength,
+        p2: &VecLength,
+    ) -> (VecLength, ActiveWrapType) {
+        let mut dist = *p1 - *p2;
+        let side_lengths = self.side_lengths();
+        let mut wrap_type = ActiveWrapType::no_wrap();
+        (dist.0.x, wrap_type.x) = minimize_component_with_wrap(
+            dist.x().value_unchecked(),
+            side_lengths.x().value_unchecked(),
+        );
+        (dist.0.y, wrap_type.y) = minimize_component_with_wrap(
+            dist.y().value_unchecked(),
+            side_lengths.y().value_unchecked(),
+        );
+        #[cfg(not(feature = "2d"))]
+        {
+            (dist.0.z, wrap_type.z) = minimize_component_with_wrap(
+                dist.z().value_unchecked(),
+                side_lengths.z().value_unchecked(),
+            );
+        }
+        (dist, wrap_type)
+    }
+
     pub fn periodic_distance(&self, p1: &VecLength, p2: &VecLength) -> Length {
         self.periodic_distance_vec(p1, p2).length()
     }
@@ -266,6 +303,7 @@ pub(crate) mod tests {
 
     use crate::domain::Extent;
     use crate::parameters::SimulationBox;
+    use crate::simulation_box::WrapType;
     use crate::test_utils::assert_is_close;
     use crate::test_utils::assert_vec_is_close;
     use crate::test_utils::get_particles;
@@ -333,6 +371,36 @@ pub(crate) mod tests {
         check_dist(&box_, (0.0, 0.0, 3.1), (0.0, 0.0, 2.9), 0.2);
     }
 
+    #[test]
+    fn periodic_distance_vec_with_wrap() {
+        let box_: SimulationBox = Extent::from_min_max(
+            VecLength::meters(0.0, 0.0, 0.0),
+            VecLength::meters(1.0, 2.0, 3.0),
+        )
+        .into();
+        let particles = get_particles(5, 5);
+        for p1 in particles.iter() {
+            for p2 in particles.iter() {
+                let p1 = box_.periodic_wrap(p1.pos);
+                let p2 = box_.periodic_wrap(p2.pos);
+                let (dist, wrap_type) = box_.periodic_distance_vec_with_wrap(&p1, &p2);
+                assert_vec_is_close(dist, box_.periodic_distance_vec(&p1, &p2));
+                let (_, image) = box_
+                    .iter_periodic_images(p2)
+                    .find(|(t, _)| *t == wrap_type)
+                    .unwrap();
+                assert_vec_is_close(dist, p1 - image);
+            }
+        }
+        let (_, wrap_type) = box_.periodic_distance_vec_with_wrap(
+            &VecLength::meters(0.9, 0.1, 1.5),
+            &VecLength::meters(0.1, 1.9, 1.5),
+        );
+        assert_eq!(wrap_type.x, WrapType::Plus);
+        assert_eq!(wrap_type.y, WrapType::Minus);
+        assert_eq!(wrap_type.z, WrapType::NoWrap);
+    }
+
     #[test]
     fn periodic_distance_is_symmetric() {
         let particles = get_particles(5, 5);
@@ -358,4 +426,73 @@ pub(crate) mod tests {
             }
         }
     }
+
+    #[test]
+    fn periodic_images_near_corner_of_rectangular_box() {
+        let box_: SimulationBox = Extent::from_min_max(
+            VecLength::meters(0.0, 0.0, 0.0),
+            VecLength::meters(1.0, 2.0, 3.0),
+        )
+        .into();
+        let extended_box = Extent::from_min_max(
+            VecLength::meters(-0.3, -0.3, -0.3),
+            VecLength::meters(1.3, 2.3, 3.3),
+        );
+        let point = VecLength::meters(0.05, 0.1, 0.2);
+        let images: Vec<_> = box_
+            .iter_periodic_images(point)
+            .filter(|(_, image)| extended_box.contains(image))
+            .collect();
+        assert_eq!(images.len(), 8);
+        let offset = |wrap: WrapType, side_length: f64| match wrap {
+            WrapType::NoWrap => 0.0,
+            WrapType::Plus => side_length,
+            WrapType::Minus => panic!("Found image on the wrong side of the box"),
+        };
+        for (wrap, image) in images {
+            let expected = VecLength::meters(
+                0.05 + offset(wrap.x, 1.0),
+                0.1 + offset(wrap.y, 2.0),
+                0.2 + offset(wrap.z, 3.0),
+            );
+            assert_vec_is_close(image, expected);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "2d")]
+mod tests_2d {
+    use crate::domain::Extent;
+    use crate::parameters::SimulationBox;
+    use crate::simulation_box::WrapType;
+    use crate::test_utils::assert_vec_is_close;
+    use crate::units::VecLength;
+
+    #[test]
+    fn periodic_images_near_corner_of_rectangular_box() {
+        let box_: SimulationBox =
+            Extent::from_min_max(VecLength::meters(0.0, 0.0), VecLength::meters(1.0, 2.0)).into();
+        let extended_box =
+            Extent::from_min_max(VecLength::meters(-0.3, -0.3), VecLength::meters(1.3, 2.3));
+        let point = VecLength::meters(0.95, 0.1);
+        let images: Vec<_> = box_
+            .iter_periodic_images(point)
+            .filter(|(_, image)| extended_box.contains(image))
+            .collect();
+        assert_eq!(images.len(), 4);
+        for (wrap, image) in images {
+            let x_offset = match wrap.x {
+                WrapType::NoWrap => 0.0,
+                WrapType::Minus => -1.0,
+                WrapType::Plus => panic!("Found image on the wrong side of the box"),
+            };
+            let y_offset = match wrap.y {
+                WrapType::NoWrap => 0.0,
+                WrapType::Plus => 2.0,
+                WrapType::Minus => panic!("Found image on the wrong side of the box"),
+            };
+            assert_vec_is_close(image, VecLength::meters(0.95 + x_offset, 0.1 + y_offset));
+        }
+    }
 }
@@ -5,7 +5,6 @@ use std::path::PathBuf;
 
 use bevy_core::prelude::TaskPoolOptions;
 use bevy_ecs::schedule::ReportExecutionOrderAmbiguities;
-use clap::Parser;
 use derive_custom::subsweep_parameters;
 use log::LevelFilter;
 use simplelog::ColorChoice;
@@ -22,8 +21,10 @@ use super::domain::DomainPlugin;
 use super::simulation_plugin::SimulationPlugin;
 use crate::communication::BaseCommunicationPlugin;
 use crate::communication::MPI_UNIVERSE;
+use crate::io::bundle::read_bundle_parameters;
 use crate::io::output::make_output_dirs;
 use crate::io::output::parameters::OutputParameters;
+use crate::memory::MemoryPlugin;
 use crate::parameter_plugin::parameter_file_contents::Override;
 use crate::prelude::WorldRank;
 use crate::prelude::WorldSize;
@@ -32,9 +33,13 @@ use crate::simulation::Simulation;
 pub struct SimulationBuilder {
     pub num_worker_threads: Option<usize>,
     pub parameter_file_path: Option<PathBuf>,
+    pub bundle_path: Option<PathBuf>,
     pub verbosity: usize,
     pub read_initial_conditions: bool,
     pub write_output: bool,
+    pub restart: bool,
+    pub dry_run: bool,
+    pub max_steps: Option<usize>,
     pub log: bool,
     pub parameter_overrides: Vec<Override>,
     base_communication: Option<BaseCommunicationPlugin>,
@@ -53,9 +58,13 @@ impl Default for SimulationBuilder {
         Self {
             num_worker_threads: None,
             parameter_file_path: None,
+            bundle_path: None,
             verbosity: 0,
             read_initial_conditions: true,
             write_output: true,
+            restart: false,
+            dry_run: false,
+            max_steps: None,
             log: true,
             base_communication: None,
             parameter_overrides: vec![],
@@ -87,7 +96,7 @@ impl SimulationBuilder {
     }
 
     pub fn update_from_command_line_options(&mut self) -> &mut Self {
-        self.with_command_line_options(&CommandLineOptions::parse())
+        self.with_command_line_options(&CommandLineOptions::parse_and_validate())
     }
 
     pub fn parameters_from_relative_path(
@@ -107,9 +116,17 @@ impl SimulationBuilder {
         if let Some(num_worker_threads) = opts.num_worker_threads {
             self.num_worker_threads(Some(num_worker_threads));
         }
-        self.parameter_file_path(&opts.parameter_file_path);
+        if let Some(ref parameter_file_path) = opts.parameter_file_path {
+            self.parameter_file_path(parameter_file_path);
+        }
+        if let Some(ref bundle_path) = opts.bundle {
+            self.bundle_path(bundle_path);
+        }
         self.verbosity(opts.verbosity);
-        self.parameter_overrides = opts.parameter_overrides.clone();
+        self.restart(opts.restart);
+        self.dry_run(opts.dry_run);
+        self.max_steps(opts.max_steps);
+        self.parameter_overrides = opts.all_parameter_overrides();
         self
     }
 
@@ -123,6 +140,14 @@ impl SimulationBuilder {
         self
     }
 
+    /// Read the parameters and the initial conditions from a bundle
+    /// (see [write_bundle](crate::io::bundle::write_bundle)). Takes
+    /// precedence over the parameter file path.
+    pub fn bundle_path(&mut self, bundle_path: &Path) -> &mut Self {
+        self.bundle_path = Some(bundle_path.to_owned());
+        self
+    }
+
     pub fn verbosity(&mut self, verbosity: usize) -> &mut Self {
         self.verbosity = verbosity;
         self
@@ -138,6 +163,31 @@ impl SimulationBuilder {
         self
     }
 
+    /// Continue a previous run from the checkpoint given as
+    /// initial conditions. The simulation time and the timestep
+    /// levels of the particles are restored from the checkpoint
+    /// instead of starting from zero.
+    pub fn restart(&mut self, restart: bool) -> &mut Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Only run the startup stages, i.e. read the parameters and
+    /// initial conditions, construct the domain decomposition and the
+    /// grid and run the checks on them, then exit before the first
+    /// timestep.
+    pub fn dry_run(&mut self, dry_run: bool) -> &mut Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Stop the simulation after the given number of timesteps, or
+    /// at the final time, whichever comes first.
+    pub fn max_steps(&mut self, max_steps: Option<usize>) -> &mut Self {
+        self.max_steps = max_steps;
+        self
+    }
+
     pub fn require_parameter_file(&mut self, require_parameter_file: bool) -> &mut Self {
         self.require_parameter_file = require_parameter_file;
         self
@@ -149,17 +199,22 @@ impl SimulationBuilder {
     }
 
     pub fn build_with_sim<'a>(&self, sim: &'a mut Simulation) -> &'a mut Simulation {
-        if let Some(ref file) = self.parameter_file_path {
+        if let Some(ref bundle) = self.bundle_path {
+            sim.add_parameter_file_contents(read_bundle_parameters(bundle));
+        } else if let Some(ref file) = self.parameter_file_path {
             sim.add_parameters_from_file(file);
         } else {
             if self.require_parameter_file {
-                panic!("No parameter file given. Use the --params argument to pass one.");
+                panic!("No parameter file given. Pass one as an argument or use --bundle.");
             }
             sim.add_parameter_file_contents("{}".into());
         }
         sim.with_parameter_overrides(self.parameter_overrides.clone());
         sim.read_initial_conditions(self.read_initial_conditions)
             .write_output(self.write_output)
+            .restart(self.restart)
+            .dry_run(self.dry_run)
+            .max_steps(self.max_steps)
             .maybe_add_plugin(self.base_communication.clone());
         let rank = **sim.get_resource::<WorldRank>().unwrap();
         let world_size = **sim.get_resource::<WorldSize>().unwrap();
@@ -170,6 +225,7 @@ impl SimulationBuilder {
         self.log_setup(sim, rank, world_size, &output_params);
         sim.add_plugin(SimulationPlugin)
             .add_plugin(DomainPlugin)
+            .add_plugin(MemoryPlugin)
             .insert_resource(ReportExecutionOrderAmbiguities);
         self.add_default_bevy_plugins(sim);
         sim
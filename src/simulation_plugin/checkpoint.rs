@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use bevy_ecs::prelude::Res;
+use bevy_ecs::prelude::ResMut;
+use bevy_ecs::prelude::Resource;
+use derive_more::Deref;
+use hdf5::H5Type;
+use log::info;
+
+use super::SimulationTime;
+use crate::impl_attribute;
+use crate::io::input::attribute::read_attribute;
+use crate::io::input::InputParameters;
+use crate::io::output::timer::Timer;
+use crate::io::output::ToAttribute;
+use crate::named::Named;
+use crate::parameters::OutputParameters;
+
+/// Marks a snapshot which contains everything required to
+/// continue the simulation from it (see
+/// [SimulationBuilder::restart](crate::prelude::SimulationBuilder::restart)).
+/// While this is set, static datasets and datasets with an
+/// [OutputCadence](crate::io::output::timer::OutputCadence) are written
+/// to every snapshot.
+#[derive(H5Type, Clone, Copy, Deref, Named, Resource)]
+#[repr(transparent)]
+#[name = "checkpoint"]
+pub struct Checkpoint(pub bool);
+
+impl_attribute!(Checkpoint, bool);
+
+pub(super) fn read_checkpoint_time(file: &Path) -> SimulationTime {
+    let is_checkpoint = hdf5::File::open(file)
+        .and_then(|f| f.attr(Checkpoint::name()))
+        .is_ok()
+        && *read_attribute::<Checkpoint>(file);
+    assert!(
+        is_checkpoint,
+        "Cannot restart from {file:?}: The file is not a checkpoint."
+    );
+    read_attribute::<SimulationTime>(file)
+}
+
+pub(super) fn restore_simulation_time_system(
+    input_parameters: Res<InputParameters>,
+    output_parameters: Res<OutputParameters>,
+    mut time: ResMut<SimulationTime>,
+    timer: Option<ResMut<Timer>>,
+) {
+    let file = input_parameters
+        .all_input_files()
+        .next()
+        .expect("No input files to restart from.");
+    *time = read_checkpoint_time(&file);
+    info!("Restarting from {:?} at time {:?}", file, **time);
+    if let Some(mut timer) = timer {
+        timer.skip_to(**time, &output_parameters);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use bevy_ecs::prelude::World;
+    use hdf5::File;
+
+    use super::read_checkpoint_time;
+    use super::restore_simulation_time_system;
+    use super::Checkpoint;
+    use crate::io::input::InputParameters;
+    use crate::io::output::timer::Timer;
+    use crate::named::Named;
+    use crate::parameters::OutputParameters;
+    use crate::simulation_plugin::SimulationTime;
+    use crate::test_utils::run_system_on_world;
+    use crate::test_utils::temp_test_dir;
+    use crate::units::Time;
+
+    fn write_file(name: &str, checkpoint: Option<bool>) -> PathBuf {
+        let path = temp_test_dir(name).join("snapshot.hdf5");
+        let file = File::create(&path).unwrap();
+        file.new_attr::<Time>()
+            .shape(())
+            .create(SimulationTime::name())
+            .and_then(|attr| attr.write_scalar(&Time::seconds(5.0)))
+            .unwrap();
+        if let Some(checkpoint) = checkpoint {
+            file.new_attr::<bool>()
+                .shape(())
+                .create(Checkpoint::name())
+                .and_then(|attr| attr.write_scalar(&checkpoint))
+                .unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn time_is_read_from_checkpoint() {
+        let path = write_file("checkpoint", Some(true));
+        assert_eq!(*read_checkpoint_time(&path), Time::seconds(5.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a checkpoint")]
+    fn restart_from_snapshot_without_checkpoint_attribute_fails() {
+        let path = write_file("no_checkpoint", None);
+        read_checkpoint_time(&path);
+    }
+
+    #[test]
+    fn restart_continues_snapshot_numbers_and_output_times() {
+        let output_dir = temp_test_dir("restart_output");
+        for snapshot in ["000", "001"] {
+            fs::create_dir_all(output_dir.join("snapshots").join(snapshot)).unwrap();
+        }
+        let mut world = World::new();
+        world.insert_resource::<OutputParameters>(
+            serde_yaml::from_str(&format!(
+                "output_dir: {}\ntime_between_snapshots: 2 s\nhandle_existing_output: append",
+                output_dir.to_str().unwrap()
+            ))
+            .unwrap(),
+        );
+        world.insert_resource(InputParameters {
+            paths: vec![write_file("restart_checkpoint", Some(true))],
+            ..Default::default()
+        });
+        world.insert_resource(SimulationTime(Time::zero()));
+        run_system_on_world(&mut world, Timer::initialize_system);
+        run_system_on_world(&mut world, restore_simulation_time_system);
+        assert_eq!(**world.resource::<SimulationTime>(), Time::seconds(5.0));
+        let timer = world.resource::<Timer>();
+        assert_eq!(timer.snapshot_num(), 2);
+        assert_eq!(timer.next_output_time(), Time::seconds(6.0));
+    }
+}
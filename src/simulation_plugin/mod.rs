@@ -1,3 +1,4 @@
+mod checkpoint;
 mod parameters;
 mod time;
 
@@ -6,6 +7,8 @@ use bevy_ecs::prelude::*;
 use log::info;
 use mpi::traits::Equivalence;
 
+use self::checkpoint::restore_simulation_time_system;
+pub use self::checkpoint::Checkpoint;
 pub use self::parameters::SimulationParameters;
 pub use self::time::SimulationTime;
 use crate::components::Position;
@@ -87,6 +90,24 @@ impl SubsweepPlugin for SimulationPlugin {
             .add_system_to_stage(Stages::AfterSweep, write_simulated_time_system)
             .add_system_to_stage(Stages::Final, exit_system)
             .add_system_to_stage(Stages::Initial, stop_simulation_system);
+        if let Some(max_steps) = sim.max_steps {
+            sim.add_system_to_stage(
+                Stages::Initial,
+                move |num_steps: Local<usize>, stop_sim: EventWriter<StopSimulationEvent>| {
+                    stop_after_max_steps_system(num_steps, stop_sim, max_steps)
+                },
+            );
+        }
+        if sim.restart {
+            assert!(
+                sim.read_initial_conditions,
+                "Restarting requires reading the checkpoint as initial conditions."
+            );
+            sim.add_startup_system_to_stage(
+                StartupStages::ReadInput,
+                restore_simulation_time_system,
+            );
+        }
         let cosmology = sim.get_parameters::<Cosmology>();
         if let Cosmology::Cosmological { .. } = cosmology {
             sim.add_startup_system_to_stage(
@@ -133,6 +154,19 @@ fn stop_simulation_system(
     }
 }
 
+/// Stops the simulation after `max_steps` timesteps, independently
+/// of the final time. At least one timestep is always run.
+fn stop_after_max_steps_system(
+    mut num_steps: Local<usize>,
+    mut stop_sim: EventWriter<StopSimulationEvent>,
+    max_steps: usize,
+) {
+    *num_steps += 1;
+    if *num_steps >= max_steps {
+        stop_sim.send(StopSimulationEvent);
+    }
+}
+
 fn show_time_system(time: Res<SimulationTime>, cosmology: Res<Cosmology>) {
     let time_spec = TimeSpec::new(**time, &cosmology);
     match time_spec {
@@ -196,3 +230,28 @@ fn set_cosmological_time_variables_system(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::event::Events;
+    use bevy_ecs::prelude::*;
+
+    use super::stop_after_max_steps_system;
+    use super::StopSimulationEvent;
+
+    #[test]
+    fn simulation_stops_after_max_steps() {
+        let mut world = World::new();
+        world.init_resource::<Events<StopSimulationEvent>>();
+        let mut stage = SystemStage::single_threaded().with_system(
+            |num_steps: Local<usize>, stop_sim: EventWriter<StopSimulationEvent>| {
+                stop_after_max_steps_system(num_steps, stop_sim, 3)
+            },
+        );
+        for step in 1..=3 {
+            stage.run(&mut world);
+            let stopped = !world.resource::<Events<StopSimulationEvent>>().is_empty();
+            assert_eq!(stopped, step == 3);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::Write;
+use std::fs;
+
+use bevy_ecs::prelude::*;
+use log::info;
+
+use crate::communication::MpiWorld;
+use crate::communication::WorldRank;
+use crate::io::output::parameters::OutputParameters;
+use crate::named::Named;
+use crate::performance::Performance;
+use crate::simulation::Simulation;
+use crate::simulation::SubsweepPlugin;
+use crate::simulation_plugin::Stages;
+use crate::simulation_plugin::StopSimulationEvent;
+use crate::stages::stage_names;
+
+const STAGE_TIMINGS_FILENAME: &str = "stage_timings.csv";
+
+/// Reports the total wall time spent in each stage at the end of the
+/// simulation. The minimum, mean and maximum over all ranks are
+/// logged and written to `stage_timings.csv` in the output
+/// directory.
+#[derive(Named)]
+pub struct StageProfilingPlugin;
+
+impl SubsweepPlugin for StageProfilingPlugin {
+    fn build_everywhere(&self, sim: &mut Simulation) {
+        sim.add_system_to_stage(Stages::Final, report_stage_timings_system);
+    }
+}
+
+/// Wall times in seconds.
+struct StageTiming {
+    stage: String,
+    min: f64,
+    mean: f64,
+    max: f64,
+}
+
+impl StageTiming {
+    fn from_run_times(stage: String, run_times: &[f64]) -> Self {
+        Self {
+            stage,
+            min: run_times.iter().copied().fold(f64::INFINITY, f64::min),
+            mean: run_times.iter().sum::<f64>() / run_times.len() as f64,
+            max: run_times.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+fn gather_stage_timings(performance: &Performance) -> Vec<StageTiming> {
+    let mut communicator = MpiWorld::<f64>::new();
+    stage_names()
+        .into_iter()
+        .map(|stage| {
+            let local = performance
+                .try_total(&stage)
+                .map(|time| time.in_seconds())
+                .unwrap_or(0.0);
+            let run_times = communicator.all_gather(&local);
+            StageTiming::from_run_times(stage, &run_times)
+        })
+        .collect()
+}
+
+fn to_csv(timings: &[StageTiming]) -> String {
+    let mut csv = "stage,min,mean,max\n".to_string();
+    for timing in timings {
+        writeln!(
+            csv,
+            "{},{},{},{}",
+            timing.stage, timing.min, timing.mean, timing.max
+        )
+        .unwrap();
+    }
+    csv
+}
+
+fn report_stage_timings_system(
+    mut stop_sim: EventReader<StopSimulationEvent>,
+    performance: NonSend<Performance>,
+    world_rank: Res<WorldRank>,
+    parameters: Option<Res<OutputParameters>>,
+) {
+    if stop_sim.iter().count() == 0 {
+        return;
+    }
+    let timings = gather_stage_timings(&performance);
+    if !world_rank.is_main() {
+        return;
+    }
+    info!(
+        "{:<30} {:>12} {:>12} {:>12}",
+        "Stage", "min [s]", "mean [s]", "max [s]"
+    );
+    for timing in timings.iter() {
+        info!(
+            "{:<30} {:>12.3} {:>12.3} {:>12.3}",
+            timing.stage, timing.min, timing.mean, timing.max
+        );
+    }
+    if let Some(parameters) = parameters {
+        fs::write(
+            parameters.output_dir.join(STAGE_TIMINGS_FILENAME),
+            to_csv(&timings),
+        )
+        .unwrap_or_else(|e| panic!("Failed to write stage timings to file. {}", e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_csv;
+    use super::StageTiming;
+
+    #[test]
+    fn stage_timings_are_written_as_csv() {
+        let timings = [
+            StageTiming::from_run_times("Sweep".into(), &[1.0, 3.0, 2.0]),
+            StageTiming::from_run_times("Output".into(), &[0.5]),
+        ];
+        assert_eq!(
+            to_csv(&timings),
+            "stage,min,mean,max\nSweep,1,2,3\nOutput,0.5,0.5,0.5\n"
+        );
+    }
+}
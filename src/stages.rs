@@ -80,6 +80,33 @@ fn get_startup_stages() -> [StageLabelId; 13] {
     ]
 }
 
+/// The name under which the run time of a stage is recorded in
+/// [Performance]. The names of the startup stages are prefixed,
+/// since some of them share their name with one of the main stages
+/// (e.g. `Initial` and `Final`).
+fn stage_name(stage: StageLabelId, is_startup: bool) -> String {
+    let name = stage.as_str().rsplit("::").next().unwrap();
+    if is_startup {
+        format!("Startup{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// The names under which the run times of all stages are recorded
+/// in [Performance].
+pub(crate) fn stage_names() -> Vec<String> {
+    get_startup_stages()
+        .into_iter()
+        .map(|stage| stage_name(stage, true))
+        .chain(
+            get_stages()
+                .into_iter()
+                .map(|stage| stage_name(stage, false)),
+        )
+        .collect()
+}
+
 fn add_timers(stages: &[StageLabelId]) -> Vec<StageLabelId> {
     let timers = TimerStages::all().collect::<Vec<_>>();
     let timers = timers.iter();
@@ -98,31 +125,60 @@ fn add_timers(stages: &[StageLabelId]) -> Vec<StageLabelId> {
 pub fn create_schedule() -> Schedule {
     let mut schedule = Schedule::default();
     let mut startup_schedule = Schedule::default().with_run_criteria(ShouldRun::once);
-    make_schedule_from_stage_labels(&mut startup_schedule, &add_timers(&get_startup_stages()));
+    make_schedule_from_stage_labels(
+        &mut startup_schedule,
+        &add_timers(&get_startup_stages()),
+        true,
+    );
     schedule.add_stage(StartupSchedule, startup_schedule);
-    make_schedule_from_stage_labels(&mut schedule, &add_timers(&get_stages()));
+    make_schedule_from_stage_labels(&mut schedule, &add_timers(&get_stages()), false);
     schedule
 }
 
-fn make_schedule_from_stage_labels(schedule: &mut Schedule, labels: &[StageLabelId]) {
+fn make_schedule_from_stage_labels(
+    schedule: &mut Schedule,
+    labels: &[StageLabelId],
+    is_startup: bool,
+) {
     for stage in labels {
         schedule.add_stage(stage.as_label(), SystemStage::single_threaded());
     }
-    add_stage_timers_for_stages(schedule, labels);
+    add_stage_timers_for_stages(schedule, labels, is_startup);
 }
 
-fn add_stage_timers_for_stages(schedule: &mut Schedule, stages: &[StageLabelId]) {
+fn add_stage_timers_for_stages(schedule: &mut Schedule, stages: &[StageLabelId], is_startup: bool) {
     let is_timer_stage =
         |stage: StageLabelId| TimerStages::all().any(|timer_stage| stage == timer_stage.as_label());
     for window in stages.windows(3) {
         let (s1, s2, s3) = (window[0], window[1], window[2]);
         if !is_timer_stage(s2) {
+            let name = stage_name(s2, is_startup);
+            let start_name = name.clone();
             schedule.add_system_to_stage(s1, move |mut timers: ResMut<Performance>| {
-                timers.start(s2.as_str());
+                timers.start(start_name.clone());
             });
             schedule.add_system_to_stage(s3, move |mut timers: ResMut<Performance>| {
-                timers.stop(s2.as_str());
+                timers.stop(name.clone());
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::stage_names;
+
+    #[test]
+    fn stage_names_are_unique() {
+        let names = stage_names();
+        for (i, name) in names.iter().enumerate() {
+            assert!(
+                !names[i + 1..].contains(name),
+                "Duplicate stage name {}",
+                name
+            );
+        }
+        assert!(names.contains(&"StartupInitial".to_string()));
+        assert!(names.contains(&"Initial".to_string()));
+    }
+}
@@ -65,6 +65,22 @@ impl<T> ActiveList<T> {
             })
     }
 
+    /// The number of items at each timestep level. Note that the
+    /// items that are active at a given level are those at this and
+    /// all higher levels (see [Self::num_active]).
+    pub fn occupancy(&self) -> Vec<usize> {
+        assert!(self.valid);
+        self.bins.iter().map(|bin| bin.len()).collect()
+    }
+
+    pub fn num_active(&self, current_level: TimestepLevel) -> usize {
+        assert!(self.valid);
+        self.bins[current_level.0..self.max_num_levels]
+            .iter()
+            .map(|bin| bin.len())
+            .sum()
+    }
+
     pub fn enumerate_with_levels(&self) -> impl Iterator<Item = (ParticleId, TimestepLevel, &T)> {
         self.levels
             .iter()
@@ -75,11 +91,11 @@ impl<T> ActiveList<T> {
 
     pub fn enumerate_with_levels_mut(
         &mut self,
-    ) -> impl Iterator<Item = (ParticleId, &mut TimestepLevel, &T)> {
+    ) -> impl Iterator<Item = (ParticleId, &mut TimestepLevel, &mut T)> {
         self.valid = false;
         self.levels
             .iter_mut()
-            .zip(self.items.iter())
+            .zip(self.items.iter_mut())
             .enumerate()
             .map(|(i, (level, t))| {
                 (
@@ -152,3 +168,28 @@ impl<T> ActiveList<T> {
         self.valid = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ActiveList;
+    use crate::hash_map::HashMap;
+    use crate::particle::ParticleId;
+    use crate::sweep::timestep_level::TimestepLevel;
+
+    #[test]
+    fn occupancy_per_level() {
+        let items: HashMap<_, _> = (0..6).map(|i| (ParticleId::test(i), i)).collect();
+        let mut list = ActiveList::new(items, 4, TimestepLevel(0));
+        assert_eq!(list.occupancy(), vec![6, 0, 0, 0]);
+        let levels = [0, 2, 2, 1, 2, 0];
+        for (i, level) in levels.iter().enumerate() {
+            list.set_level(ParticleId::test(i), TimestepLevel(*level));
+        }
+        list.update_bins();
+        assert_eq!(list.occupancy(), vec![2, 1, 3, 0]);
+        assert_eq!(list.num_active(TimestepLevel(0)), 6);
+        assert_eq!(list.num_active(TimestepLevel(1)), 4);
+        assert_eq!(list.num_active(TimestepLevel(2)), 3);
+        assert_eq!(list.num_active(TimestepLevel(3)), 0);
+    }
+}
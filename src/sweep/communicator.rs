@@ -38,14 +38,11 @@ impl<C: Chemistry> SweepCommunicator<C> {
     }
 
     pub fn count_remaining_to_send(&self) -> usize {
-        self.send_buffers
-            .iter()
-            .map(|(_, buffer)| buffer.len())
-            .sum()
+        self.send_buffers.size()
     }
 
     pub fn update_pending_requests(&mut self) {
-        for rank in self.communicator.other_ranks() {
+        for rank in self.requests.ranks() {
             if self.requests[rank]
                 .map(|request| self.request_completed(request))
                 .unwrap_or(true)
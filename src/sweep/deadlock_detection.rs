@@ -1,3 +1,5 @@
+use std::fmt::Write;
+
 use log::debug;
 use log::warn;
 use mpi::traits::Equivalence;
@@ -88,7 +90,7 @@ impl<C: Chemistry> Sweep<C> {
         if num_to_solve == 0 {
             return;
         }
-        let num_initial_tasks = self.to_solve.len();
+        let num_initial_tasks = self.to_solve.len() + self.to_pass_through.len();
         let mut ex = MpiWorld::new_custom_tag(DEADLOCK_DETECTION_TAG);
         let total: usize = ex.all_gather_sum(&num_initial_tasks);
         assert!(
@@ -122,6 +124,60 @@ impl<C: Chemistry> Sweep<C> {
         }
         debug!("Checked dependencies, no deadlock found.");
     }
+
+    /// The quantities that change whenever the sweep makes progress,
+    /// i.e. solves a task or sends/receives fluxes.
+    pub(super) fn progress_state(&self) -> (usize, usize, usize) {
+        (
+            self.to_solve_count.total(),
+            self.remaining_to_send_count(),
+            self.remaining_to_receive_count(),
+        )
+    }
+
+    pub(super) fn describe_stalled_state(&self) -> String {
+        let mut description = String::new();
+        let (num_missing_upwind, num_waiting_sites) = self
+            .sites
+            .enumerate_active(self.current_level)
+            .map(|(_, site)| site.num_missing_upwind.total())
+            .filter(|num_missing| *num_missing > 0)
+            .fold((0, 0), |(total, num_sites), num_missing| {
+                (total + num_missing, num_sites + 1)
+            });
+        writeln!(
+            description,
+            "Sweep state on rank {} at level {}:",
+            self.rank, self.current_level.0
+        )
+        .unwrap();
+        writeln!(
+            description,
+            "  tasks left to solve: {} ({} queued), by direction: {:?}",
+            self.to_solve_count.total(),
+            self.to_solve.len(),
+            self.to_solve_count
+        )
+        .unwrap();
+        writeln!(
+            description,
+            "  missing upwind fluxes: {} in {} cells",
+            num_missing_upwind, num_waiting_sites
+        )
+        .unwrap();
+        writeln!(
+            description,
+            "  outstanding sends: {}",
+            self.remaining_to_send_count()
+        )
+        .unwrap();
+        for (rank, num) in self.to_receive_count.iter() {
+            if *num > 0 {
+                writeln!(description, "  expecting {} fluxes from rank {}", num, rank).unwrap();
+            }
+        }
+        description
+    }
 }
 
 fn print_diff(set1: &HashSet<Dependency>, set2: &HashSet<Dependency>) {
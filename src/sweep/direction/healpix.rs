@@ -1,3 +1,5 @@
+use std::f64::consts::PI;
+
 pub const DIRECTION_BINS_16: [[f64; 3]; 16] = [
     [-0.887773, 0.0580969, -0.456601],
     [-0.674667, -0.714656, -0.18464],
@@ -229,3 +231,32 @@ pub const DIRECTION_BINS_84: [[f64; 3]; 84] = [
     [-0.808676, 0.342276, 0.478425],
     [0.504395, 0.676467, 0.536635],
 ];
+
+/// The centers of the pixels of a HEALPix tesselation of the unit
+/// sphere with `nside = 2^level`, in ring order.
+pub fn pixel_centers(level: usize) -> Vec<[f64; 3]> {
+    let nside = 1usize << level;
+    let to_cartesian = |z: f64, phi: f64| {
+        let r = (1.0 - z * z).sqrt();
+        [r * phi.cos(), r * phi.sin(), z]
+    };
+    let polar_ring = |ring: usize| {
+        let z = 1.0 - (ring * ring) as f64 / (3 * nside * nside) as f64;
+        (1..=4 * ring).map(move |j| (z, PI / (2 * ring) as f64 * (j as f64 - 0.5)))
+    };
+    let north = (1..nside).flat_map(polar_ring);
+    let equatorial = (nside..=3 * nside).flat_map(|ring| {
+        let z = 4.0 / 3.0 - (2 * ring) as f64 / (3 * nside) as f64;
+        let shift = ((ring - nside + 1) % 2) as f64;
+        (1..=4 * nside).map(move |j| (z, PI / (2 * nside) as f64 * (j as f64 - shift / 2.0)))
+    });
+    let south = (1..nside)
+        .rev()
+        .flat_map(polar_ring)
+        .map(|(z, phi)| (-z, phi));
+    north
+        .chain(equatorial)
+        .chain(south)
+        .map(|(z, phi)| to_cartesian(z, phi))
+        .collect()
+}
@@ -17,8 +17,8 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use super::parameters::DirectionsSpecification;
+use super::parameters::SweepParameters;
 use super::Sweep;
-use crate::chemistry::hydrogen_only::HydrogenOnly;
 use crate::chemistry::Chemistry;
 use crate::prelude::Simulation;
 use crate::units::Dimensionless;
@@ -74,6 +74,21 @@ impl Directions {
         }
     }
 
+    #[cfg(feature = "2d")]
+    fn from_refinement_level(level: usize) -> Self {
+        Self::from_num(4 << level)
+    }
+
+    #[cfg(not(feature = "2d"))]
+    fn from_refinement_level(level: usize) -> Self {
+        Self {
+            directions: healpix::pixel_centers(level)
+                .into_iter()
+                .map(|[x, y, z]| Direction(MVec::new(x, y, z) * Dimensionless::dimensionless(1.0)))
+                .collect(),
+        }
+    }
+
     pub fn enumerate(&self) -> impl Iterator<Item = (DirectionIndex, &Direction)> {
         self.directions
             .iter()
@@ -108,6 +123,15 @@ impl From<&DirectionsSpecification> for Directions {
     }
 }
 
+impl From<&SweepParameters> for Directions {
+    fn from(parameters: &SweepParameters) -> Self {
+        match parameters.direction_refinement {
+            Some(level) => Self::from_refinement_level(level),
+            None => (&parameters.directions).into(),
+        }
+    }
+}
+
 #[derive(Resource, Clone, Deref, DerefMut)]
 pub struct DirectionsRng(StdRng);
 
@@ -155,8 +179,8 @@ fn multiply_by_matrix(vec: &mut MVec, matrix: &[[f64; 3]; 3]) {
 }
 
 // See nbubis' reply in https://math.stackexchange.com/questions/442418/random-generation-of-rotation-matrices
-pub(super) fn rotate_directions_system(
-    mut solver: NonSendMut<Option<Sweep<HydrogenOnly>>>,
+pub(super) fn rotate_directions_system<C: Chemistry>(
+    mut solver: NonSendMut<Option<Sweep<C>>>,
     mut rng: ResMut<DirectionsRng>,
 ) {
     let solver = (*solver).as_mut().unwrap();
@@ -167,9 +191,9 @@ pub(super) fn rotate_directions_system(
     }
     let new_dirs = solver.directions.directions.clone();
     for site in solver.sites.iter_mut() {
-        remap(&mut site.incoming_total_rate, &old_dirs, &new_dirs);
-        remap(&mut site.outgoing_total_rate, &old_dirs, &new_dirs);
-        remap(&mut site.periodic_source, &old_dirs, &new_dirs);
+        remap::<C>(&mut site.incoming_total_rate, &old_dirs, &new_dirs);
+        remap::<C>(&mut site.outgoing_total_rate, &old_dirs, &new_dirs);
+        remap::<C>(&mut site.periodic_source, &old_dirs, &new_dirs);
     }
 }
 
@@ -187,11 +211,7 @@ fn kernel_f(d1: &Direction, dirs: &[Direction]) -> Vec<f64> {
         .collect()
 }
 
-fn remap(
-    values: &mut [<HydrogenOnly as Chemistry>::Photons],
-    old_dirs: &[Direction],
-    new_dirs: &[Direction],
-) {
+fn remap<C: Chemistry>(values: &mut [C::Photons], old_dirs: &[Direction], new_dirs: &[Direction]) {
     let num_dirs = old_dirs.len();
     let kernel = (0..num_dirs)
         .map(|i| kernel_f(&old_dirs[i], &new_dirs))
@@ -199,7 +219,7 @@ fn remap(
     let old_values = values.iter().cloned().collect::<Vec<_>>();
     for i in 0..num_dirs {
         for j in 0..num_dirs {
-            values[i] = old_values[j] * kernel[i][j];
+            values[i] = old_values[j].clone() * kernel[i][j];
         }
     }
 }
@@ -216,6 +236,7 @@ mod tests {
 
     use super::get_random_rotation_matrix;
     use super::multiply_by_matrix;
+    use super::Directions;
     use crate::test_utils::assert_float_is_close;
     use crate::units::MVec;
     use crate::voronoi::math::utils::determinant3x3;
@@ -240,4 +261,28 @@ mod tests {
             assert_float_is_close(v.length(), 1.0);
         }
     }
+
+    #[cfg(feature = "2d")]
+    fn num_directions_at_refinement_level(level: usize) -> usize {
+        4 * 2usize.pow(level as u32)
+    }
+
+    #[cfg(not(feature = "2d"))]
+    fn num_directions_at_refinement_level(level: usize) -> usize {
+        12 * 4usize.pow(level as u32)
+    }
+
+    #[test]
+    fn refinement_level_gives_evenly_distributed_unit_directions() {
+        for level in 0..3 {
+            let directions = Directions::from_refinement_level(level);
+            assert_eq!(directions.len(), num_directions_at_refinement_level(level));
+            let mut sum = MVec::ZERO;
+            for (_, dir) in directions.enumerate() {
+                assert_float_is_close(dir.0 .0.length(), 1.0);
+                sum += dir.0 .0;
+            }
+            assert!(sum.length() < 1e-10);
+        }
+    }
 }
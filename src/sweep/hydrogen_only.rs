@@ -0,0 +1,281 @@
+use bevy_ecs::prelude::*;
+use bevy_ecs::system::lifetimeless::Read;
+use bevy_ecs::system::lifetimeless::SQuery;
+use bevy_ecs::system::lifetimeless::SRes;
+use bevy_ecs::system::lifetimeless::Write;
+use bevy_ecs::system::SystemParamItem;
+
+use super::init_optional_chemistry_component;
+use super::init_optional_component;
+use super::site::Site;
+use super::time_series::compute_time_series_system;
+use super::time_series::num_particles_at_timestep_levels_system;
+use super::time_series::HydrogenIonizationMassAverage;
+use super::time_series::HydrogenIonizationVolumeAverage;
+use super::time_series::PhotoionizationRateVolumeAverage;
+use super::time_series::TemperatureMassAverage;
+use super::time_series::TemperatureVolumeAverage;
+use super::time_series::WeightedPhotoionizationRateVolumeAverage;
+use super::Rate;
+use super::Sweep;
+use super::SweepParameters;
+use crate::chemistry::frequency_bins::FrequencyBins;
+use crate::chemistry::hydrogen_only::multi_frequency::HydrogenOnlyMultiFrequency;
+use crate::chemistry::hydrogen_only::multi_frequency::MultiFrequencyParameters;
+use crate::chemistry::hydrogen_only::ChemistryParameters;
+use crate::chemistry::hydrogen_only::HydrogenOnly;
+use crate::chemistry::hydrogen_only::HydrogenOnlySpecies;
+use crate::chemistry::hydrogen_only::Solver;
+use crate::chemistry::Chemistry;
+use crate::chemistry::SweepChemistry;
+use crate::components;
+use crate::components::CollisionalIonizationRate;
+use crate::components::HeatingRate;
+use crate::components::IonizationTime;
+use crate::components::IonizedHydrogenFraction;
+use crate::components::PhotoionizationRate;
+use crate::components::RecombinationRate;
+use crate::components::Source;
+use crate::components::Timestep;
+use crate::cosmology::Cosmology;
+use crate::io::output::parameters::is_desired_field;
+use crate::io::time_series::TimeSeriesPlugin;
+use crate::prelude::*;
+use crate::units;
+use crate::units::Dimensionless;
+
+/// Registers the components, parameters and time series which are
+/// shared by all chemistries with [HydrogenOnlySpecies].
+fn build_hydrogen_only_species<C: Chemistry>(sim: &mut Simulation) {
+    // A restarted simulation continues with the species stored in
+    // the checkpoint.
+    if sim.restart {
+        sim.add_required_component::<IonizedHydrogenFraction>()
+            .add_required_component::<components::Temperature>();
+    } else {
+        sim.add_derived_component::<IonizedHydrogenFraction>()
+            .add_derived_component::<components::Temperature>();
+    }
+    sim.add_derived_component::<Source>()
+        .add_derived_component::<components::Mass>()
+        .add_derived_component::<components::PhotonRate>()
+        .add_plugin(TimeSeriesPlugin::<HydrogenIonizationMassAverage>::default())
+        .add_plugin(TimeSeriesPlugin::<HydrogenIonizationVolumeAverage>::default())
+        .add_plugin(TimeSeriesPlugin::<TemperatureMassAverage>::default())
+        .add_plugin(TimeSeriesPlugin::<TemperatureVolumeAverage>::default())
+        .add_plugin(TimeSeriesPlugin::<PhotoionizationRateVolumeAverage>::default())
+        .add_plugin(TimeSeriesPlugin::<WeightedPhotoionizationRateVolumeAverage>::default())
+        .add_parameter_type::<ChemistryParameters>();
+    if sim.write_output {
+        sim.add_system_to_stage(
+            Stages::AfterSweep,
+            compute_time_series_system.before(num_particles_at_timestep_levels_system::<C>),
+        );
+    }
+    init_optional_component::<Timestep>(sim);
+    init_optional_component::<IonizationTime>(sim);
+}
+
+fn hydrogen_only_species_are_written(sim: &Simulation) -> bool {
+    is_desired_field::<IonizedHydrogenFraction>(sim)
+        && is_desired_field::<components::Temperature>(sim)
+}
+
+type HydrogenOnlyInitParam = (
+    SRes<SweepParameters>,
+    SRes<ChemistryParameters>,
+    SRes<Cosmology>,
+    SQuery<
+        (
+            Read<ParticleId>,
+            Read<IonizedHydrogenFraction>,
+            Read<components::Temperature>,
+            Read<Source>,
+        ),
+        With<LocalParticle>,
+    >,
+);
+
+type HydrogenOnlyUpdateParam = (
+    SQuery<
+        (
+            Read<ParticleId>,
+            Write<IonizedHydrogenFraction>,
+            Write<components::Temperature>,
+        ),
+        With<LocalParticle>,
+    >,
+    SQuery<(Read<ParticleId>, Write<Timestep>), With<LocalParticle>>,
+    SQuery<(Read<ParticleId>, Write<components::PhotonRate>), With<LocalParticle>>,
+    SQuery<(Read<ParticleId>, Write<IonizationTime>), With<LocalParticle>>,
+);
+
+/// The species and the source rate of every local particle.
+fn get_initial_hydrogen_only_species(
+    param: &SystemParamItem<'_, '_, HydrogenOnlyInitParam>,
+) -> Vec<(ParticleId, HydrogenOnlySpecies, units::PhotonRate)> {
+    let (_, _, _, particles) = param;
+    particles
+        .iter()
+        .map(|(id, ionized_hydrogen_fraction, temperature, source)| {
+            (
+                *id,
+                HydrogenOnlySpecies::new(**ionized_hydrogen_fraction, **temperature),
+                **source,
+            )
+        })
+        .collect()
+}
+
+/// Writes the species and the total incoming rate (as given by
+/// `total_rate`) of the sites back to the components.
+fn update_hydrogen_only_components<'a, C>(
+    param: &mut SystemParamItem<'_, '_, HydrogenOnlyUpdateParam>,
+    time: units::Time,
+    get_site: impl Fn(ParticleId) -> &'a Site<C>,
+    total_rate: impl Fn(&Site<C>) -> units::PhotonRate,
+) where
+    C: Chemistry<Species = HydrogenOnlySpecies> + 'a,
+{
+    let (sites, timesteps, rates, ionization_times) = param;
+    for (id, mut fraction, mut temperature) in sites.iter_mut() {
+        let site = get_site(*id);
+        **fraction = site.species.ionized_hydrogen_fraction;
+        **temperature = site.species.temperature;
+    }
+    for (id, mut timestep) in timesteps.iter_mut() {
+        **timestep = get_site(*id).species.timestep;
+    }
+    for (id, mut rate) in rates.iter_mut() {
+        **rate = total_rate(get_site(*id));
+    }
+    for (id, mut ionization_time) in ionization_times.iter_mut() {
+        let site = get_site(*id);
+        if site.species.ionized_hydrogen_fraction > 0.5
+            && **ionization_time == *IonizationTime::default()
+        {
+            **ionization_time = time;
+        }
+    }
+}
+
+impl SweepChemistry for HydrogenOnly {
+    type InitParam = HydrogenOnlyInitParam;
+    type UpdateParam = HydrogenOnlyUpdateParam;
+
+    fn build(sim: &mut Simulation) {
+        build_hydrogen_only_species::<HydrogenOnly>(sim);
+        init_optional_chemistry_component::<HeatingRate>(sim);
+        init_optional_chemistry_component::<RecombinationRate>(sim);
+        init_optional_chemistry_component::<CollisionalIonizationRate>(sim);
+        init_optional_chemistry_component::<PhotoionizationRate>(sim);
+    }
+
+    fn species_are_written(sim: &Simulation) -> bool {
+        hydrogen_only_species_are_written(sim)
+    }
+
+    fn new(param: &SystemParamItem<'_, '_, HydrogenOnlyInitParam>) -> Self {
+        let (sweep_parameters, chemistry_parameters, cosmology, _) = param;
+        HydrogenOnly {
+            rate_threshold: sweep_parameters.significant_rate_threshold,
+            scale_factor: cosmology.scale_factor(),
+            timestep_safety_factor: sweep_parameters.chemistry_timestep_safety_factor,
+            prevent_cooling: sweep_parameters.prevent_cooling,
+            recombination_coefficient: chemistry_parameters.recombination_coefficient,
+            clumping_factor: chemistry_parameters.clumping_factor,
+        }
+    }
+
+    fn get_initial_sites(
+        param: &SystemParamItem<'_, '_, HydrogenOnlyInitParam>,
+    ) -> Vec<(ParticleId, HydrogenOnlySpecies, Rate<Self>)> {
+        get_initial_hydrogen_only_species(param)
+    }
+
+    fn update_components<'a>(
+        param: &mut SystemParamItem<'_, '_, HydrogenOnlyUpdateParam>,
+        time: units::Time,
+        get_site: impl Fn(ParticleId) -> &'a Site<Self>,
+    ) where
+        Self: 'a,
+    {
+        update_hydrogen_only_components(param, time, get_site, |site| {
+            site.incoming_total_rate.iter().copied().sum()
+        });
+    }
+}
+
+impl<const N: usize> SweepChemistry for HydrogenOnlyMultiFrequency<N> {
+    type InitParam = (HydrogenOnlyInitParam, SRes<MultiFrequencyParameters>);
+    type UpdateParam = HydrogenOnlyUpdateParam;
+
+    fn build(sim: &mut Simulation) {
+        build_hydrogen_only_species::<Self>(sim);
+        sim.add_parameter_type::<MultiFrequencyParameters>();
+    }
+
+    fn species_are_written(sim: &Simulation) -> bool {
+        hydrogen_only_species_are_written(sim)
+    }
+
+    fn new(param: &SystemParamItem<'_, '_, Self::InitParam>) -> Self {
+        let (hydrogen_only_param, parameters) = param;
+        Self {
+            hydrogen_only: HydrogenOnly::new(hydrogen_only_param),
+            cross_sections: parameters.cross_sections(),
+        }
+    }
+
+    fn get_initial_sites(
+        param: &SystemParamItem<'_, '_, Self::InitParam>,
+    ) -> Vec<(ParticleId, HydrogenOnlySpecies, Rate<Self>)> {
+        let (hydrogen_only_param, parameters) = param;
+        let spectrum = parameters.spectrum();
+        get_initial_hydrogen_only_species(hydrogen_only_param)
+            .into_iter()
+            .map(|(id, species, source)| {
+                (id, species, FrequencyBins::from_spectrum(source, &spectrum))
+            })
+            .collect()
+    }
+
+    fn update_components<'a>(
+        param: &mut SystemParamItem<'_, '_, HydrogenOnlyUpdateParam>,
+        time: units::Time,
+        get_site: impl Fn(ParticleId) -> &'a Site<Self>,
+    ) where
+        Self: 'a,
+    {
+        update_hydrogen_only_components(param, time, get_site, |site| {
+            site.incoming_total_rate
+                .iter()
+                .map(|rate| rate.total())
+                .sum()
+        });
+    }
+}
+
+impl Sweep<HydrogenOnly> {
+    pub fn get_solver(&self, id: ParticleId, scale_factor: Dimensionless) -> Solver {
+        let cell = self.cells.get(id);
+        let site = self.sites.get(id);
+        let rate: Rate<HydrogenOnly> = self
+            .directions
+            .enumerate()
+            .map(|(dir, _)| site.get_rate(self.directions.len(), dir))
+            .sum();
+        Solver {
+            ionized_hydrogen_fraction: site.species.ionized_hydrogen_fraction,
+            temperature: site.species.temperature,
+            density: site.density,
+            volume: cell.volume,
+            length: cell.size,
+            rate,
+            scale_factor: scale_factor,
+            floor: None,
+            recombination_coefficient: self.chemistry.recombination_coefficient,
+            clumping_factor: self.chemistry.clumping_factor,
+        }
+    }
+}
@@ -5,8 +5,10 @@ mod count_by_dir;
 mod deadlock_detection;
 mod direction;
 pub mod grid;
+mod hydrogen_only;
 mod parameters;
-pub(crate) mod site;
+pub mod site;
+mod stats;
 mod task;
 #[cfg(test)]
 mod tests;
@@ -14,7 +16,12 @@ mod time_series;
 pub mod timestep_level;
 mod timestep_state;
 
+use std::marker::PhantomData;
+use std::time::Instant;
+
 use bevy_ecs::prelude::*;
+use bevy_ecs::system::StaticSystemParam;
+use bevy_ecs::system::SystemParamItem;
 use derive_more::Into;
 use hdf5::H5Type;
 use log::info;
@@ -38,46 +45,41 @@ use self::grid::ParticleType;
 use self::grid::RemoteNeighbour;
 use self::grid::RemotePeriodicNeighbour;
 use self::site::Site;
+use self::stats::SweepStats;
 pub use self::task::RateData;
 use self::task::Task;
-use self::time_series::compute_time_series_system;
 use self::time_series::num_particles_at_timestep_levels_system;
-use self::time_series::HydrogenIonizationMassAverage;
-use self::time_series::HydrogenIonizationVolumeAverage;
 use self::time_series::NumParticlesAtTimestepLevels;
-use self::time_series::PhotoionizationRateVolumeAverage;
-use self::time_series::TemperatureMassAverage;
-use self::time_series::TemperatureVolumeAverage;
-use self::time_series::WeightedPhotoionizationRateVolumeAverage;
 use self::timestep_level::TimestepLevel;
 use self::timestep_state::TimestepState;
+use crate::chemistry::hydrogen_only::multi_frequency::HydrogenOnlyMultiFrequency;
+use crate::chemistry::hydrogen_only::multi_frequency::MultiFrequencyParameters;
+use crate::chemistry::hydrogen_only::ChemistryParameters;
 use crate::chemistry::hydrogen_only::HydrogenOnly;
-use crate::chemistry::hydrogen_only::HydrogenOnlySpecies;
-use crate::chemistry::hydrogen_only::Solver;
 use crate::chemistry::timescale::Timescale;
 use crate::chemistry::timescale::TimescaleCounter;
 use crate::chemistry::Chemistry;
+use crate::chemistry::ChemistryNetwork;
 use crate::chemistry::Photons;
+use crate::chemistry::SweepChemistry;
 use crate::communication::DataByRank;
 use crate::communication::ExchangeCommunicator;
 use crate::communication::MpiWorld;
 use crate::communication::Rank;
 use crate::communication::SizedCommunicator;
 use crate::components;
-use crate::components::CollisionalIonizationRate;
 use crate::components::Density;
-use crate::components::HeatingRate;
-use crate::components::IonizationTime;
-use crate::components::IonizedHydrogenFraction;
-use crate::components::PhotoionizationRate;
+use crate::components::DesiredTimestep;
 use crate::components::PhotonRate;
-use crate::components::RecombinationRate;
 use crate::components::Source;
-use crate::components::Timestep;
-use crate::cosmology::Cosmology;
+use crate::domain::CompactParticleIdsLabel;
+use crate::domain::DomainParameters;
+use crate::domain::ParticleIdsCompacted;
 use crate::hash_map::HashMap;
 use crate::io::output::parameters::is_desired_field;
 use crate::io::output::parameters::OutputParameters;
+use crate::io::output::Attribute;
+use crate::io::output::OutputPlugin;
 use crate::io::time_series::TimeSeriesPlugin;
 use crate::io::to_dataset::ToDataset;
 use crate::particle::HaloParticles;
@@ -85,6 +87,7 @@ use crate::particle::ParticleId;
 use crate::performance::Performance;
 use crate::prelude::*;
 use crate::simulation::SubsweepPlugin;
+use crate::simulation_plugin::Checkpoint;
 use crate::simulation_plugin::SimulationTime;
 use crate::units::Dimensionless;
 use crate::units::Mass;
@@ -107,64 +110,129 @@ type Queue<T> = Vec<T>;
 type Cells = ActiveList<Cell>;
 type Sites<C> = ActiveList<Site<C>>;
 
+/// Solves the radiative transfer and the chemistry `C` with the
+/// sweep method.
 #[derive(Named)]
-pub struct SweepPlugin;
+pub struct SweepPlugin<C: SweepChemistry = HydrogenOnly> {
+    _marker: PhantomData<C>,
+}
+
+impl<C: SweepChemistry> Default for SweepPlugin<C> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Adds the [SweepPlugin] for the chemistry network which is
+/// selected by the `network` entry of the [ChemistryParameters].
+pub fn add_sweep_plugin(sim: &mut Simulation) {
+    let network = sim
+        .add_parameter_type_and_get_result::<ChemistryParameters>()
+        .network;
+    match network {
+        ChemistryNetwork::HydrogenOnly => sim.add_plugin(SweepPlugin::<HydrogenOnly>::default()),
+        ChemistryNetwork::HydrogenOnlyMultiFrequency => {
+            let num_bins = sim
+                .add_parameter_type_and_get_result::<MultiFrequencyParameters>()
+                .cross_sections
+                .len();
+            match num_bins {
+                2 => sim.add_plugin(SweepPlugin::<HydrogenOnlyMultiFrequency<2>>::default()),
+                3 => sim.add_plugin(SweepPlugin::<HydrogenOnlyMultiFrequency<3>>::default()),
+                4 => sim.add_plugin(SweepPlugin::<HydrogenOnlyMultiFrequency<4>>::default()),
+                _ => panic!(
+                    "Invalid multi_frequency parameters: Found {num_bins} frequency bins, but only 2, 3 or 4 bins are supported."
+                ),
+            }
+        }
+    };
+}
 
 #[derive(Resource, derive_more::Deref, derive_more::DerefMut)]
 pub struct IsFirstTime(bool);
 
+/// The (local) order in which the tasks were solved during the
+/// last sweep step, across all timestep levels. Only present if
+/// `record_solve_order` is set in the [SweepParameters].
+#[derive(Resource, Default, Debug)]
+pub struct SolveOrder(pub Vec<(ParticleId, DirectionIndex)>);
+
+/// Label of the system which runs the sweep in [Stages::Sweep].
+/// Systems which are ordered `.after(SweepSystemLabel)` see the
+/// converged incoming [PhotonRate](components::PhotonRate) and the
+/// updated ionization state of every local cell.
+#[derive(SystemLabel)]
+pub struct SweepSystemLabel;
+
+#[derive(SystemLabel)]
+struct InitSweepSystemLabel;
+
 #[derive(Debug, Equivalence, PartialEq, Eq, Hash)]
 pub struct TimestepLevelData {
     level: TimestepLevel,
     id: ParticleId,
 }
 
-impl SubsweepPlugin for SweepPlugin {
+impl<C: SweepChemistry> SubsweepPlugin for SweepPlugin<C> {
     fn build_everywhere(&self, sim: &mut Simulation) {
         let parameters = sim
-            .add_derived_component::<IonizedHydrogenFraction>()
-            .add_derived_component::<Source>()
             .add_derived_component::<Density>()
-            .add_derived_component::<components::Mass>()
-            .add_derived_component::<components::PhotonRate>()
-            .add_derived_component::<components::Temperature>()
-            .add_plugin(TimeSeriesPlugin::<HydrogenIonizationMassAverage>::default())
-            .add_plugin(TimeSeriesPlugin::<HydrogenIonizationVolumeAverage>::default())
-            .add_plugin(TimeSeriesPlugin::<TemperatureMassAverage>::default())
-            .add_plugin(TimeSeriesPlugin::<TemperatureVolumeAverage>::default())
-            .add_plugin(TimeSeriesPlugin::<PhotoionizationRateVolumeAverage>::default())
-            .add_plugin(TimeSeriesPlugin::<WeightedPhotoionizationRateVolumeAverage>::default())
             .add_plugin(TimeSeriesPlugin::<NumParticlesAtTimestepLevels>::default())
             .insert_resource(IsFirstTime(true))
-            .insert_non_send_resource(Option::<Sweep<HydrogenOnly>>::None)
-            .add_startup_system_to_stage(StartupStages::InitSweep, init_sweep_system)
-            .add_system_to_stage(Stages::Sweep, run_sweep_system)
+            .insert_non_send_resource(Option::<Sweep<C>>::None)
+            .add_startup_system_to_stage(
+                StartupStages::InitSweep,
+                init_sweep_system::<C>.label(InitSweepSystemLabel),
+            )
+            .add_system_to_stage(Stages::Sweep, run_sweep_system::<C>.label(SweepSystemLabel))
             .add_parameter_type_and_get_result::<SweepParameters>();
         if parameters.rotate_directions {
             init_directions_rng(sim);
             sim.add_system_to_stage(
                 Stages::Sweep,
-                rotate_directions_system.after(run_sweep_system),
+                rotate_directions_system::<C>.after(SweepSystemLabel),
+            );
+        }
+        if sim
+            .add_parameter_type_and_get_result::<DomainParameters>()
+            .compact_particle_ids
+        {
+            sim.add_system_to_stage(
+                Stages::Initial,
+                reinit_sweep_after_id_compaction_system::<C>.after(CompactParticleIdsLabel),
             );
         }
+        if sim.get_parameters::<SweepParameters>().record_solve_order {
+            sim.insert_resource(SolveOrder::default());
+        }
         if sim.write_output {
             sim.add_system_to_stage(
                 Stages::AfterSweep,
-                compute_time_series_system
-                    .before(num_particles_at_timestep_levels_system::<HydrogenOnly>),
-            )
-            .add_system_to_stage(
-                Stages::AfterSweep,
-                num_particles_at_timestep_levels_system::<HydrogenOnly>,
+                num_particles_at_timestep_levels_system::<C>,
             )
             .add_startup_system_to_stage(StartupStages::InitSweep, show_num_directions_system);
         }
-        init_optional_chemistry_component::<HeatingRate>(sim);
-        init_optional_chemistry_component::<RecombinationRate>(sim);
-        init_optional_chemistry_component::<CollisionalIonizationRate>(sim);
-        init_optional_chemistry_component::<PhotoionizationRate>(sim);
-        init_optional_component::<Timestep>(sim);
-        init_optional_component::<IonizationTime>(sim);
+        C::build(sim);
+        let writes_desired_timesteps = if sim.restart {
+            sim.add_required_component::<DesiredTimestep>()
+                .add_startup_system_to_stage(
+                    StartupStages::InitSweep,
+                    restore_timestep_levels_system::<C>.after(InitSweepSystemLabel),
+                );
+            sim.write_output && is_desired_field::<DesiredTimestep>(sim)
+        } else {
+            init_optional_component::<DesiredTimestep>(sim)
+        };
+        // Restarting requires the desired timesteps (from which the
+        // timestep levels are restored) and the species of the
+        // particles, so only snapshots which contain both are marked
+        // as checkpoints.
+        if writes_desired_timesteps && C::species_are_written(sim) {
+            sim.insert_resource(Checkpoint(true))
+                .add_plugin(OutputPlugin::<Attribute<Checkpoint>>::default());
+        }
     }
 }
 
@@ -175,6 +243,7 @@ struct Sweep<C: Chemistry> {
     sites: Sites<C>,
     halo_levels: HashMap<ParticleId, TimestepLevel>,
     to_solve: PriorityQueue<Task>,
+    to_pass_through: Vec<Task>,
     to_send: DataByRank<Queue<RateData<C>>>,
     to_solve_count: CountByDir,
     to_receive_count: DataByRank<usize>,
@@ -188,6 +257,11 @@ struct Sweep<C: Chemistry> {
     rank: Rank,
     timescale_counter: TimescaleCounter,
     num_tasks_to_solve_before_send_receive: usize,
+    transparent_density_threshold: Option<units::Density>,
+    watchdog_iterations: Option<usize>,
+    solve_order: Option<Vec<(ParticleId, DirectionIndex)>>,
+    stats: SweepStats,
+    max_level_decrease_per_step: Option<usize>,
 }
 
 impl<C: Chemistry> Sweep<C> {
@@ -203,9 +277,23 @@ impl<C: Chemistry> Sweep<C> {
         world_rank: Rank,
         chemistry: C,
     ) -> Sweep<C> {
+        assert!(
+            parameters.num_timestep_levels >= 1,
+            "Invalid sweep parameter num_timestep_levels = {}: At least one timestep level is required.",
+            parameters.num_timestep_levels
+        );
         let initial_level = TimestepLevel(parameters.num_timestep_levels - 1);
         let communicator = SweepCommunicator::<C>::new();
-        let timestep_state = TimestepState::new(max_timestep, parameters.num_timestep_levels);
+        assert!(
+            parameters.timestep_level_factor >= 2,
+            "Invalid sweep parameter timestep_level_factor = {}: The factor needs to be at least 2.",
+            parameters.timestep_level_factor
+        );
+        let timestep_state = TimestepState::new(
+            max_timestep,
+            parameters.num_timestep_levels,
+            parameters.timestep_level_factor,
+        );
         let halo_levels = halo_ids.into_iter().map(|id| (id, initial_level)).collect();
         let rank = communicator.rank();
         Sweep {
@@ -213,6 +301,7 @@ impl<C: Chemistry> Sweep<C> {
             sites: Sites::<C>::new(sites, parameters.num_timestep_levels, initial_level),
             halo_levels,
             to_solve: PriorityQueue::new(),
+            to_pass_through: vec![],
             to_send: DataByRank::from_size_and_rank(world_size, world_rank),
             directions,
             to_solve_count: CountByDir::empty(),
@@ -228,15 +317,30 @@ impl<C: Chemistry> Sweep<C> {
             timescale_counter: TimescaleCounter::new(parameters.max_timestep),
             num_tasks_to_solve_before_send_receive: parameters
                 .num_tasks_to_solve_before_send_receive,
+            transparent_density_threshold: parameters.transparent_density_threshold,
+            watchdog_iterations: parameters.watchdog_iterations,
+            solve_order: parameters.record_solve_order.then(Vec::new),
+            stats: SweepStats::default(),
+            max_level_decrease_per_step: parameters.max_level_decrease_per_step,
         }
     }
 
     fn count_cells_global(&mut self, level: TimestepLevel) -> usize {
-        let local_count = self.cells.enumerate_active(level).count();
+        let local_count = self.cells.num_active(level);
         let mut count_communicator = MpiWorld::new_custom_tag(91100);
         count_communicator.all_gather_sum(&CellCount(local_count))
     }
 
+    /// The global number of cells at each timestep level.
+    fn get_occupancy_global(&mut self) -> Vec<usize> {
+        let mut count_communicator = MpiWorld::new_custom_tag(91101);
+        self.cells
+            .occupancy()
+            .into_iter()
+            .map(|local_count| count_communicator.all_gather_sum(&CellCount(local_count)))
+            .collect()
+    }
+
     fn get_cell_counts_per_level(&mut self) -> Vec<usize> {
         self.timestep_state
             .iter_all_levels()
@@ -256,14 +360,20 @@ impl<C: Chemistry> Sweep<C> {
     }
 
     pub fn run_sweeps(&mut self, timers: &mut Performance) -> Time {
+        if let Some(ref mut solve_order) = self.solve_order {
+            solve_order.clear();
+        }
         let counts = self.get_cell_counts_per_level();
         self.print_cell_counts(&counts);
+        let start = Instant::now();
+        self.stats = SweepStats::default();
         for level in self.timestep_state.iter_levels_in_sweep_order() {
             if counts[level.0] > 0 {
                 self.current_level = level;
                 self.single_sweep(timers);
             }
         }
+        self.stats.log_global(start);
         self.timescale_counter.show_timestep_limiting_processes();
         let time_elapsed = self.timestep_state.current_max_timestep();
         self.timestep_state.advance_allowed_levels();
@@ -275,7 +385,7 @@ impl<C: Chemistry> Sweep<C> {
         timers.start(self.current_level);
         trace!("Level {:>2}: Sweeping.", self.current_level.0);
         self.init_counts();
-        self.to_solve = self.get_initial_tasks();
+        self.set_initial_tasks();
         if self.check_deadlock {
             self.check_deadlock();
         }
@@ -289,30 +399,49 @@ impl<C: Chemistry> Sweep<C> {
     }
 
     fn solve(&mut self) {
+        let mut num_iterations_without_progress = 0;
         while self.to_solve_count.total() > 0
             || self.remaining_to_send_count() > 0
-            || self
-                .to_receive_count
-                .iter()
-                .map(|(_, num)| num)
-                .sum::<usize>()
-                > 0
+            || self.remaining_to_receive_count() > 0
         {
+            let progress_before = self.progress_state();
             if self.to_solve.is_empty() {
                 self.receive_all_messages();
             }
+            self.pass_through_transparent_cells();
             let mut num_solved = 0;
             while let Some(task) = self.to_solve.pop() {
                 self.solve_task(task);
+                self.pass_through_transparent_cells();
                 num_solved += 1;
                 if num_solved > self.num_tasks_to_solve_before_send_receive {
                     break;
                 }
             }
+            self.stats.num_solved_tasks += num_solved;
+            self.stats.num_iterations += 1;
             self.send_all_messages();
+            if let Some(watchdog_iterations) = self.watchdog_iterations {
+                if self.progress_state() == progress_before {
+                    num_iterations_without_progress += 1;
+                } else {
+                    num_iterations_without_progress = 0;
+                }
+                if num_iterations_without_progress >= watchdog_iterations {
+                    panic!(
+                        "Sweep made no progress for {} iterations.\n{}",
+                        num_iterations_without_progress,
+                        self.describe_stalled_state()
+                    );
+                }
+            }
         }
     }
 
+    fn remaining_to_receive_count(&self) -> usize {
+        self.to_receive_count.iter().map(|(_, num)| num).sum()
+    }
+
     fn remaining_to_send_count(&self) -> usize {
         self.communicator.count_remaining_to_send()
     }
@@ -329,6 +458,7 @@ impl<C: Chemistry> Sweep<C> {
         let received = self.communicator.try_recv(rank);
         if let Some(received) = received {
             self.to_receive_count[rank] -= received.len();
+            self.stats.num_received_messages += received.len();
             for d in received.into_iter() {
                 if d.periodic {
                     self.handle_local_periodic_neighbour(d.rate, d.dir, d.id);
@@ -340,7 +470,13 @@ impl<C: Chemistry> Sweep<C> {
     }
 
     fn send_all_messages(&mut self) {
+        let num_queued_before = self.num_queued_messages();
         self.communicator.try_send_all(&mut self.to_send);
+        self.stats.num_sent_messages += num_queued_before - self.num_queued_messages();
+    }
+
+    fn num_queued_messages(&self) -> usize {
+        self.to_send.iter().map(|(_, queue)| queue.len()).sum()
     }
 
     pub fn init_counts(&mut self) {
@@ -385,16 +521,34 @@ impl<C: Chemistry> Sweep<C> {
         }
     }
 
-    fn get_initial_tasks(&self) -> PriorityQueue<Task> {
-        self.directions
+    fn set_initial_tasks(&mut self) {
+        let threshold = self.transparent_density_threshold;
+        let (to_pass_through, to_solve): (Vec<_>, Vec<_>) = self
+            .directions
             .enumerate()
             .flat_map(|(dir_index, _)| {
                 self.sites
                     .enumerate_active(self.current_level)
                     .filter(move |(_, site)| site.num_missing_upwind[dir_index] == 0)
-                    .map(move |(id, _)| Task { id, dir: dir_index })
+                    .map(move |(id, site)| {
+                        (
+                            Task { id, dir: dir_index },
+                            Self::is_transparent(threshold, site),
+                        )
+                    })
             })
-            .collect()
+            .partition(|(_, is_transparent)| *is_transparent);
+        self.to_pass_through = to_pass_through.into_iter().map(|(task, _)| task).collect();
+        self.to_solve = to_solve.into_iter().map(|(task, _)| task).collect();
+    }
+
+    /// Transparent cells do not need to be solved, so instead of
+    /// queueing them as tasks, their incoming rate is passed on to
+    /// their downwind neighbours as soon as it is complete.
+    fn pass_through_transparent_cells(&mut self) {
+        while let Some(task) = self.to_pass_through.pop() {
+            self.solve_task(task);
+        }
     }
 
     fn get_level(&self, id: ParticleId) -> TimestepLevel {
@@ -409,6 +563,12 @@ impl<C: Chemistry> Sweep<C> {
         self.get_level(id).is_active(self.current_level)
     }
 
+    fn is_transparent(threshold: Option<units::Density>, site: &Site<C>) -> bool {
+        threshold
+            .map(|threshold| site.density < threshold)
+            .unwrap_or(false)
+    }
+
     fn get_outgoing_rate(&mut self, task: &Task) -> Rate<C> {
         let cell = &self.cells.get(task.id);
         let site = self.sites.get_mut(task.id);
@@ -417,10 +577,16 @@ impl<C: Chemistry> Sweep<C> {
         // instability problems, so I'd rather prevent it.
         site.incoming_total_rate[task.dir.0].make_positive();
         let incoming_rate = site.get_rate(self.directions.len(), task.dir);
+        if Self::is_transparent(self.transparent_density_threshold, site) {
+            return incoming_rate;
+        }
         self.chemistry.get_outgoing_rate(cell, site, incoming_rate)
     }
 
     fn solve_task(&mut self, task: Task) {
+        if let Some(ref mut solve_order) = self.solve_order {
+            solve_order.push((task.id, task.dir));
+        }
         let outgoing_rate = self.get_outgoing_rate(&task);
         let site = self.sites.get_mut(task.id);
         let outgoing_rate_correction =
@@ -497,7 +663,12 @@ impl<C: Chemistry> Sweep<C> {
         if is_active {
             let num_remaining = site.num_missing_upwind.reduce(dir);
             if num_remaining == 0 {
-                self.to_solve.push(Task { dir, id: neighbour })
+                let task = Task { dir, id: neighbour };
+                if Self::is_transparent(self.transparent_density_threshold, site) {
+                    self.to_pass_through.push(task);
+                } else {
+                    self.to_solve.push(task);
+                }
             }
         }
     }
@@ -564,10 +735,15 @@ impl<C: Chemistry> Sweep<C> {
             };
             site.previous_incoming_total_rate = rate.clone();
             let rate_timescale = Timescale::photon_rate(timestep / relative_change);
-            let chemistry_timescale =
-                self.chemistry
-                    .update_abundances(site, rate, timestep, cell.volume, cell.size);
-            let change_timescale = rate_timescale.min(chemistry_timescale);
+            let change_timescale = if Self::is_transparent(self.transparent_density_threshold, site)
+            {
+                rate_timescale
+            } else {
+                let chemistry_timescale =
+                    self.chemistry
+                        .update_abundances(site, rate, timestep, cell.volume, cell.size);
+                rate_timescale.min(chemistry_timescale)
+            };
             site.change_timescale = change_timescale.time;
             self.timescale_counter.count(change_timescale);
         }
@@ -575,11 +751,22 @@ impl<C: Chemistry> Sweep<C> {
 
     fn update_timestep_levels(&mut self, timers: &mut Performance) {
         let _timer = timers.time("update levels");
+        for (_, _, site) in self.sites.enumerate_with_levels_mut() {
+            site.desired_timestep = self.timestep_safety_factor * site.change_timescale;
+        }
+        self.set_levels_from_desired_timesteps(self.max_level_decrease_per_step);
+    }
+
+    fn set_levels_from_desired_timesteps(&mut self, max_level_change: Option<usize>) {
         for (id, level, site) in self.sites.enumerate_with_levels_mut() {
-            let desired_timestep = self.timestep_safety_factor * site.change_timescale;
-            let desired_level = self
+            let mut desired_level = self
                 .timestep_state
-                .get_desired_level_from_desired_timestep(desired_timestep);
+                .get_desired_level_from_desired_timestep(site.desired_timestep);
+            if let Some(max_level_change) = max_level_change {
+                desired_level =
+                    self.timestep_state
+                        .limit_level_change(*level, desired_level, max_level_change);
+            }
             *level = desired_level;
             self.cells.set_level(id, desired_level);
         }
@@ -606,104 +793,169 @@ impl<C: Chemistry> Sweep<C> {
                 self.halo_levels.insert(level_data.id, level_data.level);
             }
         }
+        self.check_halo_levels_present();
     }
-}
 
-impl Sweep<HydrogenOnly> {
-    pub fn get_solver(&self, id: ParticleId, scale_factor: Dimensionless) -> Solver {
-        let cell = self.cells.get(id);
-        let site = self.sites.get(id);
-        let rate: Rate<HydrogenOnly> = self
-            .directions
-            .enumerate()
-            .map(|(dir, _)| site.get_rate(self.directions.len(), dir))
-            .sum();
-        Solver {
-            ionized_hydrogen_fraction: site.species.ionized_hydrogen_fraction,
-            temperature: site.species.temperature,
-            density: site.density,
-            volume: cell.volume,
-            length: cell.size,
-            rate,
-            scale_factor: scale_factor,
-            floor: None,
+    fn check_halo_levels_present(&self) {
+        for (id, cell) in self
+            .cells
+            .enumerate_with_levels()
+            .map(|(id, _, cell)| (id, cell))
+        {
+            for (_, neighbour) in cell.neighbours.iter() {
+                let remote_id = match neighbour {
+                    ParticleType::Remote(neighbour) => neighbour.id,
+                    ParticleType::RemotePeriodic(neighbour) => neighbour.id,
+                    _ => continue,
+                };
+                if !self.halo_levels.contains_key(&remote_id) {
+                    panic!(
+                        "No timestep level received for remote neighbour {remote_id:?} of local cell {id:?}. Was it dropped during the halo exchange?"
+                    );
+                }
+            }
         }
     }
 }
 
-fn init_sweep_system(
-    mut solver: NonSendMut<Option<Sweep<HydrogenOnly>>>,
-    cells_query: Particles<(&ParticleId, &Cell)>,
-    sites_query: Particles<(
-        Entity,
-        &ParticleId,
-        &Density,
-        &IonizedHydrogenFraction,
-        &components::Temperature,
-        &Source,
-    )>,
+fn init_sweep_system<C: SweepChemistry>(
+    mut solver: NonSendMut<Option<Sweep<C>>>,
+    cells: Particles<(&ParticleId, &Cell, &Density)>,
     haloes: HaloParticles<&ParticleId>,
     sweep_parameters: Res<SweepParameters>,
     world_rank: Res<WorldRank>,
     world_size: Res<WorldSize>,
-    cosmology: Res<Cosmology>,
+    chemistry_param: StaticSystemParam<C::InitParam>,
 ) {
-    let directions: Directions = (&sweep_parameters.directions).into();
+    *solver = Some(new_sweep(
+        &cells,
+        &haloes,
+        &sweep_parameters,
+        **world_rank,
+        **world_size,
+        &*chemistry_param,
+    ));
+}
+
+fn new_sweep<C: SweepChemistry>(
+    cells_query: &Particles<(&ParticleId, &Cell, &Density)>,
+    haloes: &HaloParticles<&ParticleId>,
+    sweep_parameters: &SweepParameters,
+    world_rank: Rank,
+    world_size: usize,
+    chemistry_param: &SystemParamItem<'_, '_, C::InitParam>,
+) -> Sweep<C> {
+    let directions: Directions = sweep_parameters.into();
     let cells: HashMap<_, _> = cells_query
         .iter()
-        .map(|(id, cell)| (*id, cell.clone()))
+        .map(|(id, cell, _)| (*id, cell.clone()))
         .collect();
-    let sites: HashMap<_, _> = sites_query
+    let densities: HashMap<_, _> = cells_query
         .iter()
-        .map(
-            |(_, id, density, ionized_hydrogen_fraction, temperature, source)| {
-                (
-                    *id,
-                    Site::<HydrogenOnly>::new(
-                        &directions,
-                        HydrogenOnlySpecies::new(**ionized_hydrogen_fraction, **temperature),
-                        **density,
-                        **source,
-                    ),
-                )
-            },
-        )
+        .map(|(id, _, density)| (*id, **density))
+        .collect();
+    let sites: HashMap<_, _> = C::get_initial_sites(chemistry_param)
+        .into_iter()
+        .map(|(id, species, source)| {
+            (
+                id,
+                Site::<C>::new(&directions, species, densities[&id], source),
+            )
+        })
         .collect();
     let halo_ids: Vec<_> = haloes.iter().copied().collect();
     #[cfg(test)]
     assert!(!cells.is_empty() && !sites.is_empty());
-    *solver = Some(Sweep::new(
+    Sweep::new(
         directions,
         cells,
         sites,
         halo_ids,
         sweep_parameters.max_timestep,
         sweep_parameters.timestep_safety_factor,
+        sweep_parameters,
+        world_size,
+        world_rank,
+        C::new(chemistry_param),
+    )
+}
+
+/// Sets the timestep levels of the particles according to the
+/// desired timesteps read from the checkpoint, instead of starting
+/// all particles on the level with the smallest timestep.
+fn restore_timestep_levels_system<C: SweepChemistry>(
+    mut solver: NonSendMut<Option<Sweep<C>>>,
+    desired_timesteps: Particles<(&ParticleId, &DesiredTimestep)>,
+) {
+    restore_timestep_levels(
+        (*solver).as_mut().unwrap(),
+        desired_timesteps
+            .iter()
+            .map(|(id, desired_timestep)| (*id, **desired_timestep)),
+    );
+}
+
+fn restore_timestep_levels<C: Chemistry>(
+    solver: &mut Sweep<C>,
+    desired_timesteps: impl Iterator<Item = (ParticleId, Time)>,
+) {
+    for (id, desired_timestep) in desired_timesteps {
+        solver.sites.get_mut(id).desired_timestep = desired_timestep;
+    }
+    // All particles are still on the initial level here, which the
+    // restored levels should not be limited by. The restarted
+    // simulation begins with all levels synchronized, so all of them
+    // are allowed.
+    solver.timestep_state.allow_all_levels();
+    solver.set_levels_from_desired_timesteps(None);
+}
+
+/// All state of the solver is keyed by the particle ids, so it is
+/// rebuilt from the components of the particles after the ids have
+/// been compacted. The timestep levels are restored if the desired
+/// timesteps are stored as components, otherwise all particles
+/// start on the level with the smallest timestep again.
+fn reinit_sweep_after_id_compaction_system<C: SweepChemistry>(
+    mut solver: NonSendMut<Option<Sweep<C>>>,
+    cells: Particles<(&ParticleId, &Cell, &Density)>,
+    haloes: HaloParticles<&ParticleId>,
+    desired_timesteps: Particles<(&ParticleId, &DesiredTimestep)>,
+    sweep_parameters: Res<SweepParameters>,
+    world_rank: Res<WorldRank>,
+    world_size: Res<WorldSize>,
+    chemistry_param: StaticSystemParam<C::InitParam>,
+    mut compacted: EventReader<ParticleIdsCompacted>,
+) {
+    if compacted.iter().count() == 0 {
+        return;
+    }
+    let mut sweep = new_sweep(
+        &cells,
+        &haloes,
         &sweep_parameters,
-        **world_size,
         **world_rank,
-        HydrogenOnly {
-            rate_threshold: sweep_parameters.significant_rate_threshold,
-            scale_factor: cosmology.scale_factor(),
-            timestep_safety_factor: sweep_parameters.chemistry_timestep_safety_factor,
-            prevent_cooling: sweep_parameters.prevent_cooling,
-        },
-    ));
+        **world_size,
+        &*chemistry_param,
+    );
+    if !desired_timesteps.is_empty() {
+        restore_timestep_levels(
+            &mut sweep,
+            desired_timesteps
+                .iter()
+                .map(|(id, desired_timestep)| (*id, **desired_timestep)),
+        );
+    }
+    *solver = Some(sweep);
 }
 
-fn run_sweep_system(
-    mut solver: NonSendMut<Option<Sweep<HydrogenOnly>>>,
-    mut sites: Particles<(
-        &ParticleId,
-        &mut IonizedHydrogenFraction,
-        &mut components::Temperature,
-    )>,
-    mut timesteps: Particles<(&ParticleId, &mut Timestep)>,
-    mut ionization_times: Particles<(&ParticleId, &mut IonizationTime)>,
-    mut rates: Particles<(&ParticleId, &mut components::PhotonRate)>,
+fn run_sweep_system<C: SweepChemistry>(
+    mut solver: NonSendMut<Option<Sweep<C>>>,
+    mut desired_timesteps: Particles<(&ParticleId, &mut DesiredTimestep)>,
     mut time: ResMut<SimulationTime>,
     mut timers: NonSendMut<Performance>,
     mut is_first: ResMut<IsFirstTime>,
+    solve_order: Option<ResMut<SolveOrder>>,
+    mut chemistry_param: StaticSystemParam<C::UpdateParam>,
 ) {
     // This is a slightly hacky way of making sure that we can output
     // the ICS. The first time this system would run, it doesn't run so that
@@ -715,27 +967,13 @@ fn run_sweep_system(
     let solver = (*solver).as_mut().unwrap();
     let time_elapsed = solver.run_sweeps(&mut timers);
     **time += time_elapsed;
-    for (id, mut fraction, mut temperature) in sites.iter_mut() {
-        let site = solver.sites.get_mut(*id);
-        **fraction = site.species.ionized_hydrogen_fraction;
-        **temperature = site.species.temperature;
-    }
-    for (id, mut timestep) in timesteps.iter_mut() {
-        let site = solver.sites.get(*id);
-        **timestep = site.species.timestep;
-    }
-    for (id, mut rate) in rates.iter_mut() {
-        let site = solver.sites.get(*id);
-        **rate = site.incoming_total_rate.iter().copied().sum();
-    }
-    for (id, mut ionization_time) in ionization_times.iter_mut() {
-        let site = solver.sites.get(*id);
-        if site.species.ionized_hydrogen_fraction > 0.5
-            && **ionization_time == *IonizationTime::default()
-        {
-            **ionization_time = **time;
-        }
+    if let Some(mut solve_order) = solve_order {
+        solve_order.0 = solver.solve_order.clone().unwrap();
+    }
+    for (id, mut desired_timestep) in desired_timesteps.iter_mut() {
+        **desired_timestep = solver.sites.get(*id).desired_timestep;
     }
+    C::update_components(&mut *chemistry_param, **time, |id| solver.sites.get(id));
 }
 
 fn initialize_optional_component_system<C: Component + Named + Default>(
@@ -775,7 +1013,7 @@ where
     if init_optional_component::<C>(sim) {
         sim.add_system_to_stage(
             Stages::Sweep,
-            sweep_optional_output_system::<C>.after(run_sweep_system),
+            sweep_optional_output_system::<C>.after(SweepSystemLabel),
         );
     }
 }
@@ -800,5 +1038,5 @@ fn show_num_directions_system(
     parameters: Res<SweepParameters>,
     mut performance_data: ResMut<Performance>,
 ) {
-    performance_data.record_number("num_sweep_directions", parameters.directions.num());
+    performance_data.record_number("num_sweep_directions", Directions::from(&*parameters).len());
 }
@@ -1,5 +1,6 @@
 use derive_custom::subsweep_parameters;
 
+use crate::units::Density;
 use crate::units::Dimensionless;
 use crate::units::PhotonRate;
 use crate::units::Time;
@@ -11,8 +12,12 @@ pub struct SweepParameters {
     /// sweep.
     pub directions: DirectionsSpecification,
     /// Number of timestep levels to use (the minimum timestep
-    /// is t_max * 2^(-num_timestep_levels))
+    /// is t_max * timestep_level_factor^(-num_timestep_levels))
     pub num_timestep_levels: usize,
+    /// The factor by which the timestep decreases from one timestep
+    /// level to the next.
+    #[serde(default = "default_timestep_level_factor")]
+    pub timestep_level_factor: usize,
     /// Whether to run with periodic boundary conditions.
     pub periodic: bool,
     /// The maximum allowed timestep.
@@ -43,6 +48,34 @@ pub struct SweepParameters {
     /// for incoming tasks for too long.
     #[serde(default = "default_num_tasks_to_solve_before_send_receive")]
     pub num_tasks_to_solve_before_send_receive: usize,
+    /// Cells with a density below this threshold are treated as
+    /// fully transparent: incoming flux passes through them
+    /// unattenuated and their abundances are never updated.
+    #[serde(default)]
+    pub transparent_density_threshold: Option<Density>,
+    /// If set, the sweep aborts with a dump of its state once it
+    /// has made no progress for this many iterations of its main
+    /// loop. Since ranks can legitimately wait for a while for
+    /// incoming fluxes, this should be large.
+    #[serde(default)]
+    pub watchdog_iterations: Option<usize>,
+    /// Whether to record the order in which the tasks are solved
+    /// during each sweep step. The order is made available in the
+    /// [SolveOrder](crate::sweep::SolveOrder) resource.
+    #[serde(default)]
+    pub record_solve_order: bool,
+    /// If set, the directions are generated by a subdivision of the
+    /// sphere at this refinement level: A HEALPix tesselation with
+    /// 12 * 4^level directions in 3D and 4 * 2^level evenly spaced
+    /// directions in 2D. Takes precedence over `directions`.
+    #[serde(default)]
+    pub direction_refinement: Option<usize>,
+    /// If set, the timestep level of a particle can change by at
+    /// most this many levels per step, both towards smaller and
+    /// towards larger timesteps. Particles are still moved to the
+    /// lowest currently allowed level if necessary.
+    #[serde(default)]
+    pub max_level_decrease_per_step: Option<usize>,
 }
 
 #[subsweep_parameters]
@@ -52,6 +85,35 @@ pub enum DirectionsSpecification {
     Explicit(Vec<VecDimensionless>),
 }
 
+impl SweepParameters {
+    /// Parameters for tests and benchmarks: A single timestep level
+    /// with a maximum timestep of 1 ms, no periodic boundaries and
+    /// without preventing cooling. All other values are the defaults
+    /// used for a parameter file.
+    pub fn test(directions: DirectionsSpecification) -> Self {
+        Self {
+            directions,
+            num_timestep_levels: 1,
+            timestep_level_factor: default_timestep_level_factor(),
+            periodic: false,
+            max_timestep: Time::seconds(1e-3),
+            rotate_directions: default_rotate_directions(),
+            significant_rate_threshold: PhotonRate::zero(),
+            timestep_safety_factor: default_timestep_factor(),
+            chemistry_timestep_safety_factor: default_timestep_factor(),
+            check_deadlock: false,
+            prevent_cooling: false,
+            num_tasks_to_solve_before_send_receive: default_num_tasks_to_solve_before_send_receive(
+            ),
+            transparent_density_threshold: None,
+            watchdog_iterations: None,
+            record_solve_order: false,
+            direction_refinement: None,
+            max_level_decrease_per_step: None,
+        }
+    }
+}
+
 impl DirectionsSpecification {
     pub fn num(&self) -> usize {
         match self {
@@ -65,6 +127,10 @@ fn default_rotate_directions() -> bool {
     false
 }
 
+fn default_timestep_level_factor() -> usize {
+    2
+}
+
 fn default_timestep_factor() -> Dimensionless {
     Dimensionless::percent(10.0)
 }
@@ -19,6 +19,7 @@ pub struct Site<C: Chemistry> {
     pub species: Species<C>,
     pub density: Density,
     pub change_timescale: Time,
+    pub desired_timestep: Time,
     source: C::Photons,
 }
 
@@ -39,6 +40,7 @@ impl<C: Chemistry> Site<C> {
             periodic_source: directions.enumerate().map(|_| C::Photons::zero()).collect(),
             previous_incoming_total_rate: C::Photons::zero(),
             change_timescale: Time::zero(),
+            desired_timestep: Time::zero(),
         }
     }
 
@@ -0,0 +1,42 @@
+use std::time::Instant;
+
+use derive_more::Add;
+use derive_more::Sum;
+use log::info;
+use mpi::traits::Equivalence;
+
+use crate::communication::MpiWorld;
+
+/// What happened on this rank during all sweeps of a single
+/// timestep, i.e. during the calls of `Sweep::solve` for every
+/// level.
+#[derive(Clone, Copy, Debug, Default, Add, Sum, Equivalence)]
+pub(super) struct SweepStats {
+    pub num_solved_tasks: usize,
+    pub num_sent_messages: usize,
+    pub num_received_messages: usize,
+    pub num_iterations: usize,
+}
+
+impl SweepStats {
+    /// Sums the stats over all ranks and logs them, together with
+    /// the wall clock time of the slowest rank since `start`. This
+    /// communicates, so it is only done once per timestep. Needs to
+    /// be called on every rank.
+    pub(super) fn log_global(&self, start: Instant) {
+        let mut stats_communicator = MpiWorld::new_custom_tag(91102);
+        let total: SweepStats = stats_communicator.all_gather_sum(self);
+        let mut time_communicator = MpiWorld::new_custom_tag(91103);
+        let wall_time: f64 = time_communicator
+            .all_gather_max(&start.elapsed().as_secs_f64())
+            .unwrap();
+        info!(
+            "Sweep: Solved {:>10} tasks in {:>6} iterations, sent {:>8} and received {:>8} messages ({:.3} s)",
+            total.num_solved_tasks,
+            total.num_iterations,
+            total.num_sent_messages,
+            total.num_received_messages,
+            wall_time,
+        );
+    }
+}
@@ -24,13 +24,19 @@ pub struct RateData<C: Chemistry> {
 
 impl PartialOrd for Task {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.dir.partial_cmp(&other.dir)
+        Some(self.cmp(other))
     }
 }
 
+/// Tasks are ordered by direction first. Ties are broken by the
+/// particle id (lower ids are solved first), so that the order in
+/// which the tasks are solved does not depend on the order in which
+/// they were queued.
 impl Ord for Task {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+        self.dir
+            .cmp(&other.dir)
+            .then_with(|| other.id.cmp(&self.id))
     }
 }
 
@@ -1,25 +1,63 @@
 use bevy_ecs::prelude::Commands;
+use bevy_ecs::prelude::IntoSystemDescriptor;
 use bevy_ecs::prelude::Res;
+use bevy_ecs::prelude::ResMut;
+use bevy_ecs::prelude::Resource;
+use bevy_ecs::prelude::With;
+use bevy_ecs::system::lifetimeless::Read;
+use bevy_ecs::system::lifetimeless::SQuery;
+use bevy_ecs::system::lifetimeless::Write;
+use bevy_ecs::system::SystemParamItem;
 
 use super::grid::init_cartesian_grid_system;
+use super::grid::Cell;
 use super::grid::NumCellsSpec;
+use super::site::Site;
+use super::InitSweepSystemLabel;
+use super::Sweep;
+use crate::chemistry::hydrogen_only::multi_frequency::HydrogenOnlyMultiFrequency;
+use crate::chemistry::hydrogen_only::multi_frequency::MultiFrequencyParameters;
+use crate::chemistry::hydrogen_only::ChemistryParameters;
+use crate::chemistry::hydrogen_only::HydrogenOnly;
+use crate::chemistry::hydrogen_only::Solver;
+use crate::chemistry::timescale::Timescale;
+use crate::chemistry::Chemistry;
+use crate::chemistry::SweepChemistry;
+use crate::communication::MpiWorld;
+use crate::components;
+use crate::components::Source;
+use crate::cosmology::Cosmology;
 use crate::parameters::SimulationBox;
 use crate::parameters::SimulationParameters;
 use crate::parameters::SweepParameters;
+use crate::performance::Performance;
+use crate::prelude::LocalParticle;
+use crate::prelude::ParticleId;
+use crate::prelude::Particles;
+use crate::prelude::Stages;
 use crate::prelude::StartupStages;
 use crate::prelude::WorldRank;
 use crate::prelude::WorldSize;
 use crate::simulation::Simulation;
+use crate::simulation_plugin::SimulationTime;
+use crate::sweep::add_sweep_plugin;
 use crate::sweep::initialize_sweep_test_components_system;
 use crate::sweep::parameters::DirectionsSpecification;
 use crate::sweep::SweepPlugin;
-use crate::test_utils::build_local_communication_sim_with_custom_logic;
+use crate::sweep::SweepSystemLabel;
+use crate::test_utils::run_on_rank_counts;
+use crate::test_utils::TestSimulationBuilder;
+use crate::units::CrossSection;
 use crate::units::Dimensionless;
 use crate::units::Length;
 use crate::units::MVec;
 use crate::units::PhotonRate;
+use crate::units::SourceRate;
 use crate::units::Time;
 use crate::units::VecDimensionless;
+use crate::units::Volume;
+use crate::units::VolumeRate;
+use crate::units::NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION;
 
 struct SweepSetup {
     dirs: Vec<VecDimensionless>,
@@ -28,31 +66,33 @@ struct SweepSetup {
     box_: SimulationBox,
 }
 
-fn setup_sweep_sim(sim: &mut Simulation, setup: SweepSetup) -> &mut Simulation {
+fn setup_sweep_sim<C: SweepChemistry>(sim: &mut Simulation, setup: SweepSetup) -> &mut Simulation {
+    add_sweep_setup(sim, setup).add_plugin(SweepPlugin::<C>::default())
+}
+
+/// Everything that the [SweepPlugin] needs, without the plugin
+/// itself.
+fn add_sweep_setup(sim: &mut Simulation, setup: SweepSetup) -> &mut Simulation {
     sim.add_parameter_file_contents("{}".into())
         .add_parameters_explicitly(setup.box_.clone())
         .add_parameters_explicitly(SweepParameters {
-            directions: DirectionsSpecification::Explicit(setup.dirs.clone()),
-            rotate_directions: false,
             num_timestep_levels: setup.num_timestep_levels,
-            significant_rate_threshold: PhotonRate::zero(),
             timestep_safety_factor: setup.timestep_safety_factor,
             chemistry_timestep_safety_factor: setup.timestep_safety_factor,
-            check_deadlock: false,
-            periodic: false,
-            max_timestep: Time::seconds(1e-3),
-            prevent_cooling: false,
-            num_tasks_to_solve_before_send_receive: 10000,
+            ..SweepParameters::test(DirectionsSpecification::Explicit(setup.dirs.clone()))
         })
         .add_parameters_explicitly(SimulationParameters { final_time: None })
+        // Usually provided by the SimulationPlugin
+        .add_parameters_explicitly(Cosmology::NonCosmological)
+        .insert_resource(SimulationTime(Time::zero()))
+        .insert_non_send_resource(Performance::default())
         .add_startup_system_to_stage(
             StartupStages::InsertComponentsAfterGrid,
             initialize_sweep_test_components_system,
         )
-        .add_plugin(SweepPlugin)
 }
 
-fn build_cartesian_sweep_sim(
+fn build_cartesian_sweep_sim<C: SweepChemistry>(
     sim: &mut Simulation,
     dirs: Vec<VecDimensionless>,
     num_cells: usize,
@@ -74,7 +114,7 @@ fn build_cartesian_sweep_sim(
             periodic,
         )
     };
-    setup_sweep_sim(
+    setup_sweep_sim::<C>(
         sim,
         SweepSetup {
             dirs,
@@ -87,48 +127,981 @@ fn build_cartesian_sweep_sim(
 }
 
 #[test]
-#[ignore]
 fn simple_sweep() {
-    for num_ranks in 1..10 {
-        for num_timestep_levels in 1..3 {
-            for periodic in [false, true] {
-                println!("Running on {}", num_ranks);
-                build_local_communication_sim_with_custom_logic(
-                    move |sim: &mut Simulation| {
-                        build_cartesian_sweep_sim(
-                            sim,
-                            vec![MVec::ONE * Dimensionless::dimensionless(1.0)],
-                            10,
-                            num_timestep_levels,
-                            periodic,
-                        )
-                    },
-                    |sim| {
-                        sim.update();
-                    },
-                    num_ranks,
-                );
-            }
+    if run_on_rank_counts(concat!(module_path!(), "::simple_sweep"), 1..=3) {
+        return;
+    }
+    for num_timestep_levels in 1..3 {
+        for periodic in [false, true] {
+            TestSimulationBuilder::new()
+                .setup(move |sim| {
+                    build_cartesian_sweep_sim::<HydrogenOnly>(
+                        sim,
+                        vec![MVec::ONE * Dimensionless::dimensionless(1.0)],
+                        10,
+                        num_timestep_levels,
+                        periodic,
+                    )
+                })
+                .run_n_steps(1);
         }
     }
 }
 
 #[test]
-#[ignore]
 fn sweep_along_grid_axes_does_not_deadlock_or_crash() {
-    build_local_communication_sim_with_custom_logic(
-        |sim: &mut Simulation| {
-            build_cartesian_sweep_sim(
+    if run_on_rank_counts(
+        concat!(
+            module_path!(),
+            "::sweep_along_grid_axes_does_not_deadlock_or_crash"
+        ),
+        1..=3,
+    ) {
+        return;
+    }
+    TestSimulationBuilder::new()
+        .setup(|sim| {
+            build_cartesian_sweep_sim::<HydrogenOnly>(
                 sim,
                 vec![MVec::X * Dimensionless::dimensionless(1.0)],
                 5,
                 1,
                 false,
             )
+        })
+        .run_n_steps(1);
+}
+
+#[derive(Resource, Default)]
+struct TotalIncomingRate(Option<PhotonRate>);
+
+fn add_source_system(mut sources: Particles<&mut Source>) {
+    if let Some(mut source) = sources.iter_mut().next() {
+        **source = SourceRate::photons_per_second(1e50);
+    }
+}
+
+fn total_incoming_rate_system(
+    rates: Particles<&components::PhotonRate>,
+    mut total: ResMut<TotalIncomingRate>,
+) {
+    total.0 = Some(rates.iter().map(|rate| **rate).sum());
+}
+
+#[test]
+fn system_after_sweep_sees_converged_rates() {
+    let mut sim = TestSimulationBuilder::new()
+        .setup(|sim| {
+            build_cartesian_sweep_sim::<HydrogenOnly>(
+                sim,
+                vec![MVec::X * Dimensionless::dimensionless(1.0)],
+                5,
+                1,
+                false,
+            );
+            sim.insert_resource(TotalIncomingRate::default())
+                .add_startup_system_to_stage(
+                    StartupStages::InitSweep,
+                    add_source_system.before(InitSweepSystemLabel),
+                )
+                .add_system_to_stage(
+                    Stages::Sweep,
+                    total_incoming_rate_system.after(SweepSystemLabel),
+                );
+        })
+        // The sweep does not run during the first update, so that
+        // the initial conditions can be written.
+        .run_n_steps(2);
+    let world = sim.world();
+    let mut rates = world.query_filtered::<&components::PhotonRate, With<LocalParticle>>();
+    let expected: PhotonRate = rates.iter(world).map(|rate| **rate).sum();
+    let total = sim.unwrap_resource::<TotalIncomingRate>().0.unwrap();
+    assert!(expected > PhotonRate::zero());
+    assert_eq!(total, expected);
+}
+
+fn run_cartesian_sweep_with_source<C: SweepChemistry>(
+    setup: impl FnOnce(&mut Simulation) + 'static,
+) -> Vec<(ParticleId, Dimensionless)> {
+    let mut sim = TestSimulationBuilder::new()
+        .setup(setup)
+        .setup(|sim| {
+            build_cartesian_sweep_sim::<C>(
+                sim,
+                vec![MVec::X * Dimensionless::dimensionless(1.0)],
+                5,
+                1,
+                false,
+            );
+            sim.add_startup_system_to_stage(
+                StartupStages::InitSweep,
+                add_source_system.before(InitSweepSystemLabel),
+            );
+        })
+        .run_n_steps(2);
+    let world = sim.world();
+    let mut fractions: Vec<_> = world
+        .query_filtered::<(&ParticleId, &components::IonizedHydrogenFraction), With<LocalParticle>>(
+        )
+        .iter(world)
+        .map(|(id, fraction)| (*id, **fraction))
+        .collect();
+    fractions.sort_by_key(|(id, _)| *id);
+    fractions
+}
+
+/// With two bins which both have the cross section of the hydrogen
+/// only network, the multi frequency network reproduces its
+/// result.
+#[test]
+fn multi_frequency_sweep_matches_hydrogen_only() {
+    let hydrogen_only = run_cartesian_sweep_with_source::<HydrogenOnly>(|_| {});
+    let multi_frequency = run_cartesian_sweep_with_source::<HydrogenOnlyMultiFrequency<2>>(|sim| {
+        sim.add_parameters_explicitly(MultiFrequencyParameters {
+            cross_sections: vec![NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION; 2],
+            spectrum: vec![Dimensionless::dimensionless(0.5); 2],
+        });
+    });
+    assert!(hydrogen_only
+        .iter()
+        .any(|(_, fraction)| *fraction > Dimensionless::dimensionless(1e-10)));
+    assert_eq!(hydrogen_only.len(), multi_frequency.len());
+    for ((id1, fraction1), (id2, fraction2)) in hydrogen_only.iter().zip(multi_frequency.iter()) {
+        assert_eq!(id1, id2);
+        assert!(((*fraction1 - *fraction2) / *fraction1).abs().value() < 1e-10);
+    }
+}
+
+#[test]
+fn multi_frequency_network_is_selected_from_parameters() {
+    let mut sim = Simulation::default();
+    add_sweep_setup(
+        &mut sim,
+        SweepSetup {
+            dirs: vec![MVec::X * Dimensionless::dimensionless(1.0)],
+            num_timestep_levels: 1,
+            timestep_safety_factor: Dimensionless::zero(),
+            box_: SimulationBox::cube_from_side_length(Length::meters(1.0)),
         },
-        |sim| {
-            sim.update();
-        },
-        2,
+    )
+    .add_parameters_explicitly(
+        serde_yaml::from_str::<ChemistryParameters>("network: hydrogen_only_multi_frequency")
+            .unwrap(),
+    )
+    .add_parameters_explicitly(MultiFrequencyParameters {
+        cross_sections: vec![NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION; 3],
+        spectrum: vec![Dimensionless::dimensionless(1.0 / 3.0); 3],
+    });
+    add_sweep_plugin(&mut sim);
+    assert!(sim
+        .get_non_send_resource::<Option<Sweep<HydrogenOnlyMultiFrequency<3>>>>()
+        .is_some());
+    assert!(sim
+        .get_non_send_resource::<Option<Sweep<HydrogenOnly>>>()
+        .is_none());
+}
+
+fn illuminating_source_system(mut sources: Particles<&mut Source>) {
+    for mut source in sources.iter_mut() {
+        **source = SourceRate::photons_per_second(1e24);
+    }
+}
+
+/// Runs a single cell containing a source with the chemistry
+/// network selected by `network`, until the cell is in ionization
+/// equilibrium. Returns a solver for the final state of the cell
+/// which is illuminated by `absorbed_rate`, the part of the source
+/// that the network absorbs with the cross section of the hydrogen
+/// only network.
+fn illuminated_cell_equilibrium(
+    network: &str,
+    absorbed_rate: PhotonRate,
+    setup: impl FnOnce(&mut Simulation) + 'static,
+) -> Solver {
+    let cell_size = Length::meters(0.1);
+    // A constant coefficient, so that the test does not depend on
+    // the temperature dependence of the recombination.
+    let recombination_coefficient = VolumeRate::centimeters_cubed_per_s(2.6e-13);
+    let chemistry_parameters = ChemistryParameters {
+        recombination_coefficient: Some(recombination_coefficient),
+        ..serde_yaml::from_str::<ChemistryParameters>(&format!("network: {network}")).unwrap()
+    };
+    let grid_setup = move |commands: Commands,
+                           box_size: Res<SimulationBox>,
+                           world_size: Res<WorldSize>,
+                           world_rank: Res<WorldRank>| {
+        init_cartesian_grid_system(
+            commands,
+            box_size,
+            NumCellsSpec::CellSize(cell_size),
+            world_size,
+            world_rank,
+            false,
+        )
+    };
+    let mut sim = TestSimulationBuilder::new()
+        .setup(setup)
+        .setup(move |sim| {
+            add_sweep_setup(
+                sim,
+                SweepSetup {
+                    dirs: vec![MVec::X * Dimensionless::dimensionless(1.0)],
+                    num_timestep_levels: 1,
+                    timestep_safety_factor: Dimensionless::dimensionless(0.1),
+                    box_: SimulationBox::cube_from_side_length(cell_size),
+                },
+            )
+            .add_parameters_explicitly(chemistry_parameters)
+            .add_startup_system(grid_setup)
+            .add_startup_system_to_stage(
+                StartupStages::InitSweep,
+                illuminating_source_system.before(InitSweepSystemLabel),
+            );
+            add_sweep_plugin(sim);
+        })
+        .run_n_steps(100);
+    let world = sim.world();
+    let (cell, density, fraction, temperature) = world
+        .query_filtered::<(
+            &Cell,
+            &components::Density,
+            &components::IonizedHydrogenFraction,
+            &components::Temperature,
+        ), With<LocalParticle>>()
+        .single(world);
+    Solver {
+        ionized_hydrogen_fraction: **fraction,
+        temperature: **temperature,
+        density: **density,
+        volume: cell.volume,
+        length: cell.size,
+        rate: absorbed_rate,
+        scale_factor: Dimensionless::dimensionless(1.0),
+        floor: None,
+        recombination_coefficient: Some(recombination_coefficient),
+        clumping_factor: Dimensionless::dimensionless(1.0),
+    }
+}
+
+fn assert_in_ionization_equilibrium(solver: &Solver) {
+    // The photoionization rate does not depend on the timestep.
+    let timestep = Time::seconds(1e-3);
+    let electron_density = solver.electron_number_density();
+    let ionization_rate = (solver.photoionization_rate(timestep)
+        + solver.collisional_ionization_rate() * electron_density)
+        * solver.neutral_hydrogen_number_density();
+    let recombination_rate =
+        solver.recombination_rate() * electron_density * solver.ionized_hydrogen_number_density();
+    assert!(
+        ((ionization_rate - recombination_rate) / recombination_rate)
+            .abs()
+            .value()
+            < 1e-3,
+        "Not in ionization equilibrium: {solver:?}"
     );
 }
+
+/// Selecting a network by name runs the sweep with that network:
+/// Half of the photons of the multi frequency network are in a bin
+/// which is not absorbed at all, so the illuminated cell reaches
+/// the equilibrium of a source of half the rate and is less ionized
+/// than with the hydrogen only network.
+#[test]
+fn networks_selected_by_name_reach_their_equilibrium_in_illuminated_cell() {
+    let source = PhotonRate::photons_per_second(1e24);
+    let hydrogen_only = illuminated_cell_equilibrium("hydrogen_only", source, |_| {});
+    let multi_frequency =
+        illuminated_cell_equilibrium("hydrogen_only_multi_frequency", source * 0.5, |sim| {
+            sim.add_parameters_explicitly(MultiFrequencyParameters {
+                cross_sections: vec![NUMBER_WEIGHTED_AVERAGE_CROSS_SECTION, CrossSection::zero()],
+                spectrum: vec![Dimensionless::dimensionless(0.5); 2],
+            });
+        });
+    assert_in_ionization_equilibrium(&hydrogen_only);
+    assert_in_ionization_equilibrium(&multi_frequency);
+    assert!(multi_frequency.ionized_hydrogen_fraction > Dimensionless::dimensionless(0.1));
+    assert!(hydrogen_only.ionized_hydrogen_fraction > multi_frequency.ionized_hydrogen_fraction);
+}
+
+/// A minimal chemistry without any species, which absorbs half of
+/// the photons passing through every cell.
+#[derive(Debug)]
+struct HalfAbsorption;
+
+impl Chemistry for HalfAbsorption {
+    type Photons = PhotonRate;
+    type Species = ();
+
+    fn get_outgoing_rate(&self, _: &Cell, _: &Site<Self>, incoming_rate: PhotonRate) -> PhotonRate {
+        incoming_rate * 0.5
+    }
+
+    fn update_abundances(
+        &self,
+        _: &mut Site<Self>,
+        _: PhotonRate,
+        timestep: Time,
+        _: Volume,
+        _: Length,
+    ) -> Timescale {
+        Timescale::photon_rate(timestep)
+    }
+}
+
+impl SweepChemistry for HalfAbsorption {
+    type InitParam = SQuery<(Read<ParticleId>, Read<Source>), With<LocalParticle>>;
+    type UpdateParam =
+        SQuery<(Read<ParticleId>, Write<components::PhotonRate>), With<LocalParticle>>;
+
+    fn build(_: &mut Simulation) {}
+
+    fn species_are_written(_: &Simulation) -> bool {
+        true
+    }
+
+    fn new(_: &SystemParamItem<'_, '_, Self::InitParam>) -> Self {
+        Self
+    }
+
+    fn get_initial_sites(
+        sources: &SystemParamItem<'_, '_, Self::InitParam>,
+    ) -> Vec<(ParticleId, (), PhotonRate)> {
+        sources
+            .iter()
+            .map(|(id, source)| (*id, (), **source))
+            .collect()
+    }
+
+    fn update_components<'a>(
+        rates: &mut SystemParamItem<'_, '_, Self::UpdateParam>,
+        _: Time,
+        get_site: impl Fn(ParticleId) -> &'a Site<Self>,
+    ) where
+        Self: 'a,
+    {
+        for (id, mut rate) in rates.iter_mut() {
+            **rate = get_site(*id).incoming_total_rate.iter().copied().sum();
+        }
+    }
+}
+
+fn same_source_everywhere_system(mut sources: Particles<&mut Source>) {
+    for mut source in sources.iter_mut() {
+        **source = SourceRate::photons_per_second(1.0);
+    }
+}
+
+/// Every row of cells along the sweep direction is a chain in which
+/// each cell passes on half of its incoming photons plus its own
+/// source, so the incoming rate of the i-th cell of a row is
+/// 1 - 0.5^i photons per second.
+#[test]
+fn sweep_with_custom_chemistry() {
+    let num_cells_per_row = 5;
+    let mut sim = TestSimulationBuilder::new()
+        .setup(move |sim| {
+            build_cartesian_sweep_sim::<HalfAbsorption>(
+                sim,
+                vec![MVec::X * Dimensionless::dimensionless(1.0)],
+                num_cells_per_row,
+                1,
+                false,
+            );
+            sim.add_startup_system_to_stage(
+                StartupStages::InitSweep,
+                same_source_everywhere_system.before(InitSweepSystemLabel),
+            );
+        })
+        .run_n_steps(2);
+    let world = sim.world();
+    let mut counts = vec![0; num_cells_per_row];
+    for rate in world
+        .query_filtered::<&components::PhotonRate, With<LocalParticle>>()
+        .iter(world)
+    {
+        let index = (0..num_cells_per_row)
+            .find(|i| (rate.in_photons_per_second() - (1.0 - 0.5f64.powi(*i as i32))).abs() < 1e-10)
+            .unwrap_or_else(|| panic!("Unexpected rate: {:?}", **rate));
+        counts[index] += 1;
+    }
+    let counts: Vec<usize> = counts
+        .iter()
+        .map(|count| MpiWorld::<usize>::new().all_gather_sum(count))
+        .collect();
+    assert!(counts[0] > 0);
+    assert!(counts.iter().all(|count| *count == counts[0]));
+}
+
+/// Sweeps on a chain of cells along the x axis on a single rank.
+#[cfg(feature = "3d")]
+mod chain {
+    use bevy_ecs::prelude::World;
+    use bevy_ecs::system::lifetimeless::Read;
+    use bevy_ecs::system::lifetimeless::SQuery;
+    use bevy_ecs::system::lifetimeless::Write;
+    use bevy_ecs::system::SystemParamItem;
+
+    use crate::chemistry::hydrogen_only::HydrogenOnly;
+    use crate::chemistry::hydrogen_only::HydrogenOnlySpecies;
+    use crate::components::DesiredTimestep;
+    use crate::parameters::SweepParameters;
+    use crate::performance::Performance;
+    use crate::prelude::LocalParticle;
+    use crate::prelude::ParticleId;
+    use crate::sweep::direction::DirectionIndex;
+    use crate::sweep::direction::Directions;
+    use crate::sweep::grid::Cell;
+    use crate::sweep::grid::Face;
+    use crate::sweep::grid::ParticleType;
+    use crate::sweep::grid::RemoteNeighbour;
+    use crate::sweep::parameters::DirectionsSpecification;
+    use crate::sweep::restore_timestep_levels_system;
+    use crate::sweep::site::Site;
+    use crate::sweep::timestep_level::TimestepLevel;
+    use crate::sweep::PriorityQueue;
+    use crate::sweep::Sweep;
+    use crate::test_utils::run_system_on_world;
+    use crate::units::Density;
+    use crate::units::Dimensionless;
+    use crate::units::Length;
+    use crate::units::MVec;
+    use crate::units::PhotonRate;
+    use crate::units::Temperature;
+    use crate::units::Time;
+
+    fn chain_parameters() -> SweepParameters {
+        SweepParameters::test(DirectionsSpecification::Explicit(vec![
+            MVec::X * Dimensionless::dimensionless(1.0),
+        ]))
+    }
+
+    fn build_chain_sweep(
+        densities: &[Density],
+        parameters: SweepParameters,
+    ) -> Sweep<HydrogenOnly> {
+        let num_cells = densities.len();
+        let cell_size = Length::meters(0.1);
+        let face = |sign: f64| Face {
+            area: cell_size * cell_size,
+            normal: MVec::X * Dimensionless::dimensionless(sign),
+        };
+        let neighbour = |index: Option<usize>| match index {
+            Some(index) if index < num_cells => ParticleType::Local(ParticleId::test(index)),
+            _ => ParticleType::Boundary,
+        };
+        let cells = (0..num_cells)
+            .map(|index| {
+                let cell = Cell {
+                    neighbours: vec![
+                        (face(-1.0), neighbour(index.checked_sub(1))),
+                        (face(1.0), neighbour(Some(index + 1))),
+                    ],
+                    size: cell_size,
+                    volume: cell_size.powi::<3>(),
+                };
+                (ParticleId::test(index), cell)
+            })
+            .collect();
+        let directions: Directions = (&parameters.directions).into();
+        let sites = densities
+            .iter()
+            .enumerate()
+            .map(|(index, density)| {
+                let source = if index == 0 {
+                    PhotonRate::photons_per_second(1e50)
+                } else {
+                    PhotonRate::zero()
+                };
+                let species = HydrogenOnlySpecies::new(
+                    Dimensionless::dimensionless(1e-10),
+                    Temperature::kelvins(1000.0),
+                );
+                (
+                    ParticleId::test(index),
+                    Site::new(&directions, species, *density, source),
+                )
+            })
+            .collect();
+        Sweep::new(
+            directions,
+            cells,
+            sites,
+            vec![],
+            parameters.max_timestep,
+            parameters.timestep_safety_factor,
+            &parameters,
+            1,
+            0,
+            HydrogenOnly {
+                rate_threshold: PhotonRate::zero(),
+                scale_factor: Dimensionless::dimensionless(1.0),
+                timestep_safety_factor: parameters.chemistry_timestep_safety_factor,
+                prevent_cooling: false,
+                recombination_coefficient: None,
+                clumping_factor: Dimensionless::dimensionless(1.0),
+            },
+        )
+    }
+
+    #[test]
+    fn transparent_cells_leave_downstream_flux_unchanged() {
+        let dense = Density::grams_per_cubic_centimeters(1e-24);
+        let diffuse = Density::grams_per_cubic_centimeters(1e-34);
+        let densities = [dense, diffuse, diffuse, diffuse, diffuse, dense];
+        let last = ParticleId::test(densities.len() - 1);
+        let mut full = build_chain_sweep(&densities, chain_parameters());
+        let mut thresholded = build_chain_sweep(
+            &densities,
+            SweepParameters {
+                transparent_density_threshold: Some(Density::grams_per_cubic_centimeters(1e-30)),
+                ..chain_parameters()
+            },
+        );
+        full.run_sweeps(&mut Performance::default());
+        thresholded.run_sweeps(&mut Performance::default());
+        let full_rate = full.sites.get(last).total_incoming_rate();
+        let thresholded_rate = thresholded.sites.get(last).total_incoming_rate();
+        assert!(full_rate > PhotonRate::zero());
+        assert!(((full_rate - thresholded_rate) / full_rate).abs().value() < 1e-10);
+        // The chemistry of the transparent cells is never touched,
+        // while it is updated in the run without a threshold.
+        let fraction = |sweep: &Sweep<HydrogenOnly>, index| {
+            sweep
+                .sites
+                .get(ParticleId::test(index))
+                .species
+                .ionized_hydrogen_fraction
+        };
+        for index in 1..densities.len() - 1 {
+            assert_eq!(
+                fraction(&thresholded, index),
+                Dimensionless::dimensionless(1e-10)
+            );
+            assert!(fraction(&full, index) > Dimensionless::dimensionless(1e-10));
+        }
+        assert!(fraction(&thresholded, 0) > Dimensionless::dimensionless(1e-10));
+        assert!(thresholded.to_pass_through.is_empty());
+    }
+
+    #[test]
+    fn solve_order_follows_upwind_direction() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 5];
+        let mut sweep = build_chain_sweep(
+            &densities,
+            SweepParameters {
+                record_solve_order: true,
+                ..chain_parameters()
+            },
+        );
+        sweep.run_sweeps(&mut Performance::default());
+        let solve_order: Vec<_> = sweep
+            .solve_order
+            .unwrap()
+            .into_iter()
+            .map(|(id, dir)| {
+                assert_eq!(dir, DirectionIndex(0));
+                id
+            })
+            .collect();
+        let expected: Vec<_> = (0..densities.len()).map(ParticleId::test).collect();
+        assert_eq!(solve_order, expected);
+    }
+
+    #[test]
+    fn repeated_sweeps_are_identical() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 6];
+        let parameters = SweepParameters {
+            directions: DirectionsSpecification::Explicit(vec![
+                MVec::X * Dimensionless::dimensionless(1.0),
+                -MVec::X * Dimensionless::dimensionless(1.0),
+            ]),
+            record_solve_order: true,
+            ..chain_parameters()
+        };
+        let run = |reverse_initial_tasks: bool| {
+            let mut sweep = build_chain_sweep(&densities, parameters.clone());
+            // Split the chain into two independent halves, so that
+            // two tasks per direction are ready at the same time.
+            sweep.cells.get_mut(ParticleId::test(2)).neighbours[1].1 = ParticleType::Boundary;
+            sweep.cells.get_mut(ParticleId::test(3)).neighbours[0].1 = ParticleType::Boundary;
+            sweep.init_counts();
+            sweep.set_initial_tasks();
+            let mut tasks: Vec<_> = sweep.to_solve.drain().collect();
+            if reverse_initial_tasks {
+                tasks.reverse();
+            }
+            sweep.to_solve = tasks.into_iter().collect();
+            sweep.solve();
+            sweep.update_chemistry(&mut Performance::default());
+            let fractions: Vec<_> = (0..densities.len())
+                .map(|index| {
+                    sweep
+                        .sites
+                        .get(ParticleId::test(index))
+                        .species
+                        .ionized_hydrogen_fraction
+                })
+                .collect();
+            (fractions, sweep.solve_order.unwrap())
+        };
+        let (fractions1, order1) = run(false);
+        let (fractions2, order2) = run(true);
+        assert_eq!(order1.len(), 2 * densities.len());
+        assert_eq!(order1, order2);
+        assert_eq!(fractions1, fractions2);
+    }
+
+    #[test]
+    fn desired_timestep_is_change_timescale_times_safety_factor() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 3];
+        let parameters = chain_parameters();
+        let mut sweep = build_chain_sweep(&densities, parameters.clone());
+        sweep.run_sweeps(&mut Performance::default());
+        // The source cell is ionized during the first sweep, so its
+        // abundance change limits the timestep.
+        let site = sweep.sites.get(ParticleId::test(0));
+        assert!(site.change_timescale > Time::zero());
+        assert!(site.change_timescale.value_unchecked().is_finite());
+        let expected = parameters.timestep_safety_factor * site.change_timescale;
+        assert!(
+            ((site.desired_timestep - expected) / expected)
+                .abs()
+                .value()
+                < 1e-10
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No timestep level received for remote neighbour")]
+    fn missing_halo_level_is_reported() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 3];
+        let mut sweep = build_chain_sweep(&densities, chain_parameters());
+        let last = ParticleId::test(densities.len() - 1);
+        sweep.cells.get_mut(last).neighbours[1].1 = ParticleType::Remote(RemoteNeighbour {
+            id: ParticleId { index: 0, rank: 1 },
+            rank: 1,
+        });
+        sweep.check_halo_levels_present();
+    }
+
+    #[test]
+    #[should_panic(expected = "Sweep made no progress")]
+    fn watchdog_fires_on_stalled_sweep() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 4];
+        let mut sweep = build_chain_sweep(
+            &densities,
+            SweepParameters {
+                watchdog_iterations: Some(100),
+                ..chain_parameters()
+            },
+        );
+        sweep.init_counts();
+        // Drop the initial tasks so that the remaining cells wait
+        // for upwind fluxes which never arrive.
+        sweep.to_solve = PriorityQueue::new();
+        sweep.solve();
+    }
+
+    #[test]
+    fn stats_count_solved_tasks() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 5];
+        let mut sweep = build_chain_sweep(&densities, chain_parameters());
+        sweep.run_sweeps(&mut Performance::default());
+        assert_eq!(sweep.stats.num_solved_tasks, densities.len());
+        assert_eq!(sweep.stats.num_sent_messages, 0);
+        assert_eq!(sweep.stats.num_received_messages, 0);
+        assert!(sweep.stats.num_iterations > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "At least one timestep level is required")]
+    fn zero_timestep_levels_are_rejected() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 2];
+        build_chain_sweep(
+            &densities,
+            SweepParameters {
+                num_timestep_levels: 0,
+                ..chain_parameters()
+            },
+        );
+    }
+
+    #[test]
+    fn single_timestep_level_sweeps_once() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 3];
+        let mut sweep = build_chain_sweep(
+            &densities,
+            SweepParameters {
+                record_solve_order: true,
+                ..chain_parameters()
+            },
+        );
+        for _ in 0..3 {
+            sweep.run_sweeps(&mut Performance::default());
+            assert_eq!(sweep.solve_order.as_ref().unwrap().len(), densities.len());
+        }
+    }
+
+    #[test]
+    fn timestep_levels_are_restored_from_desired_timesteps() {
+        let densities = [Density::grams_per_cubic_centimeters(1e-24); 3];
+        let parameters = SweepParameters {
+            num_timestep_levels: 3,
+            ..chain_parameters()
+        };
+        let max_timestep = parameters.max_timestep;
+        let desired_timesteps = [max_timestep, max_timestep * 0.5, max_timestep * 0.01];
+        let mut world = World::new();
+        for (index, desired_timestep) in desired_timesteps.iter().enumerate() {
+            world.spawn((
+                LocalParticle,
+                ParticleId::test(index),
+                DesiredTimestep(*desired_timestep),
+            ));
+        }
+        world.insert_non_send_resource(Some(build_chain_sweep(&densities, parameters)));
+        run_system_on_world(&mut world, restore_timestep_levels_system::<HydrogenOnly>);
+        let mut sweep = world
+            .remove_non_send_resource::<Option<Sweep<HydrogenOnly>>>()
+            .unwrap()
+            .unwrap();
+        for (index, level) in [0, 1, 2].into_iter().enumerate() {
+            assert_eq!(
+                sweep.cells.get_level(ParticleId::test(index)),
+                TimestepLevel(level)
+            );
+        }
+        // All levels are synchronized at the restart, so the first
+        // step covers the full maximum timestep.
+        assert_eq!(sweep.run_sweeps(&mut Performance::default()), max_timestep);
+    }
+}
+
+#[cfg(feature = "3d")]
+mod checkpoint {
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use bevy_ecs::prelude::Commands;
+    use bevy_ecs::prelude::Entity;
+    use bevy_ecs::prelude::Query;
+    use bevy_ecs::prelude::Res;
+    use bevy_ecs::prelude::With;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use super::initialize_sweep_test_components_system;
+    use crate::chemistry::hydrogen_only::HydrogenOnly;
+    use crate::components;
+    use crate::components::DesiredTimestep;
+    use crate::components::IonizedHydrogenFraction;
+    use crate::components::Position;
+    use crate::cosmology::Cosmology;
+    use crate::domain::DomainPlugin;
+    use crate::io::input::attribute::read_attribute;
+    use crate::io::input::NumParticlesTotal;
+    use crate::io::input::Reader;
+    use crate::io::output::get_output_file_name;
+    use crate::io::output::get_snapshot_dir;
+    use crate::io::output::make_output_dirs;
+    use crate::io::DatasetDescriptor;
+    use crate::io::InputDatasetDescriptor;
+    use crate::named::Named;
+    use crate::parameters::OutputParameters;
+    use crate::parameters::SweepParameters;
+    use crate::prelude::LocalParticle;
+    use crate::prelude::ParallelVoronoiGridConstruction;
+    use crate::prelude::StartupStages;
+    use crate::prelude::WorldRank;
+    use crate::simulation::Simulation;
+    use crate::simulation_plugin::Checkpoint;
+    use crate::simulation_plugin::SimulationPlugin;
+    use crate::simulation_plugin::SimulationTime;
+    use crate::sweep::parameters::DirectionsSpecification;
+    use crate::sweep::SweepPlugin;
+    use crate::test_utils::assert_float_is_close;
+    use crate::test_utils::assert_is_close;
+    use crate::test_utils::temp_test_dir;
+    use crate::test_utils::TestSimulationBuilder;
+    use crate::units;
+    use crate::units::SourceRate;
+    use crate::units::Time;
+    use crate::units::VecLength;
+    use crate::units::Volume;
+
+    const NUM_PARTICLES: usize = 20;
+
+    const CHECKPOINT_FIELDS: &str =
+        "[position, ionized_hydrogen_fraction, temperature, desired_timestep]";
+
+    fn spawn_particles_system(mut commands: Commands, rank: Res<WorldRank>) {
+        if rank.is_main() {
+            let mut rng = StdRng::seed_from_u64(1338);
+            for _ in 0..NUM_PARTICLES {
+                let pos = VecLength::meters(
+                    rng.gen_range(0.1..0.9),
+                    rng.gen_range(0.1..0.9),
+                    rng.gen_range(0.1..0.9),
+                );
+                commands.spawn((LocalParticle, Position(pos)));
+            }
+        }
+        commands.insert_resource(NumParticlesTotal(NUM_PARTICLES));
+    }
+
+    /// The species are read from the checkpoint when restarting, so
+    /// only the remaining components are inserted.
+    fn insert_restart_components_system(
+        mut commands: Commands,
+        local_particles: Query<Entity, With<LocalParticle>>,
+    ) {
+        for entity in local_particles.iter() {
+            commands.entity(entity).insert((
+                components::Density(
+                    units::Mass::kilograms(1.0e-10) / Volume::cubic_centimeters(1.0),
+                ),
+                components::Source(SourceRate::zero()),
+                components::PhotonRate(units::PhotonRate::zero()),
+            ));
+        }
+    }
+
+    fn build_checkpoint_sim(sim: &mut Simulation) {
+        sim.add_parameters_explicitly(Cosmology::NonCosmological)
+            .add_parameters_explicitly(SweepParameters {
+                num_timestep_levels: 2,
+                ..SweepParameters::test(DirectionsSpecification::Num(1))
+            })
+            .add_plugin(SimulationPlugin)
+            .add_plugin(DomainPlugin)
+            .add_plugin(ParallelVoronoiGridConstruction);
+        if sim.restart {
+            sim.add_startup_system_to_stage(
+                StartupStages::InsertComponentsAfterGrid,
+                insert_restart_components_system,
+            );
+        } else {
+            sim.add_startup_system_to_stage(StartupStages::ReadInput, spawn_particles_system)
+                .add_startup_system_to_stage(
+                    StartupStages::InsertComponentsAfterGrid,
+                    initialize_sweep_test_components_system,
+                );
+        }
+        sim.add_plugin(SweepPlugin::<HydrogenOnly>::default());
+    }
+
+    fn checkpoint_sim_builder(
+        output_dir: &Path,
+        fields: &str,
+        extra_parameters: &str,
+    ) -> TestSimulationBuilder {
+        let mut builder = TestSimulationBuilder::new();
+        builder.parameter_file_contents(&format!(
+            "output:\n  output_dir: {}\n  fields: {fields}\n{extra_parameters}",
+            output_dir.to_str().unwrap()
+        ));
+        builder
+    }
+
+    fn run(sim: &mut Simulation, num_steps: usize) -> OutputParameters {
+        let parameters = sim.get_parameters::<OutputParameters>().clone();
+        make_output_dirs(&parameters);
+        for _ in 0..num_steps {
+            sim.update();
+        }
+        parameters
+    }
+
+    fn snapshot_file(parameters: &OutputParameters, snapshot_num: usize) -> PathBuf {
+        get_snapshot_dir(parameters, snapshot_num).join(get_output_file_name(
+            parameters,
+            snapshot_num,
+            parameters.num_output_files,
+            0,
+        ))
+    }
+
+    fn read_ionized_fractions(file: &Path) -> Vec<f64> {
+        let mut fractions: Vec<f64> = Reader::full([file].into_iter())
+            .read_dataset(InputDatasetDescriptor::<IonizedHydrogenFraction>::default())
+            .map(|fraction| fraction.value())
+            .collect();
+        fractions.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        fractions
+    }
+
+    #[test]
+    fn simulation_restarts_from_written_checkpoint() {
+        let output_dir = temp_test_dir("restart_from_checkpoint").join("output");
+        let mut sim = checkpoint_sim_builder(&output_dir, CHECKPOINT_FIELDS, "")
+            .setup(|sim| {
+                sim.write_output(true);
+                build_checkpoint_sim(sim);
+            })
+            .build();
+        // The sweep does not run in the first step, so the second
+        // snapshot is the first one after a sweep.
+        let parameters = run(&mut sim, 2);
+        drop(sim);
+        let checkpoint = snapshot_file(&parameters, 1);
+        assert!(*read_attribute::<Checkpoint>(&checkpoint));
+        // Static datasets are part of every checkpoint.
+        assert!(hdf5::File::open(&checkpoint)
+            .unwrap()
+            .dataset(DatasetDescriptor::default_for::<Position>().dataset_name())
+            .is_ok());
+
+        let mut sim = checkpoint_sim_builder(
+            &output_dir,
+            CHECKPOINT_FIELDS,
+            &format!(
+                "  handle_existing_output: append\ninput:\n  paths: [{}]\n",
+                checkpoint.to_str().unwrap()
+            ),
+        )
+        .setup(|sim| {
+            sim.read_initial_conditions(true)
+                .restart(true)
+                .write_output(true);
+            build_checkpoint_sim(sim);
+        })
+        .build();
+        run(&mut sim, 2);
+        assert_is_close(
+            **sim.unwrap_resource::<SimulationTime>(),
+            Time::seconds(2e-3),
+        );
+        let world = sim.world();
+        let mut desired_timesteps = world.query_filtered::<&DesiredTimestep, With<LocalParticle>>();
+        assert_eq!(desired_timesteps.iter(world).count(), NUM_PARTICLES);
+        // The first snapshot of the restarted simulation is written
+        // before the first sweep, so it contains the species from
+        // the checkpoint.
+        let restarted = snapshot_file(&parameters, 2);
+        for (restored, stored) in read_ionized_fractions(&restarted)
+            .into_iter()
+            .zip(read_ionized_fractions(&checkpoint))
+        {
+            assert_float_is_close(restored, stored);
+        }
+        assert!(snapshot_file(&parameters, 3).exists());
+    }
+
+    #[test]
+    fn snapshots_without_species_are_not_checkpoints() {
+        let output_dir = temp_test_dir("snapshot_without_species").join("output");
+        let mut sim = checkpoint_sim_builder(&output_dir, "[position, desired_timestep]", "")
+            .setup(|sim| {
+                sim.write_output(true);
+                build_checkpoint_sim(sim);
+            })
+            .build();
+        let parameters = run(&mut sim, 1);
+        assert!(hdf5::File::open(snapshot_file(&parameters, 0))
+            .unwrap()
+            .attr(Checkpoint::name())
+            .is_err());
+        assert!(!sim.contains_resource::<Checkpoint>());
+    }
+}
@@ -14,6 +14,7 @@ use super::Sweep;
 use super::SweepParameters;
 use crate::chemistry::Chemistry;
 use crate::communication::communicator::Communicator;
+use crate::communication::ReproducibleSum;
 use crate::components;
 use crate::components::IonizedHydrogenFraction;
 use crate::components::Mass;
@@ -54,7 +55,11 @@ pub struct NumParticlesAtTimestepLevels(Vec<NumAtLevel>);
 #[derive(Serialize, Clone)]
 struct NumAtLevel {
     level: usize,
+    /// The number of cells which are active at this level, i.e.
+    /// the cells at this or any higher level.
     num: usize,
+    /// The number of cells at exactly this level.
+    occupancy: usize,
     timestep: Time,
 }
 
@@ -154,14 +159,16 @@ pub fn compute_time_series_system(
     weighted_photoionization_rate_writer.send(WeightedPhotoionizationRateVolumeAverage(average));
 }
 
+/// Sums the values over all ranks. The contributions of the ranks
+/// are summed independently of their order, so that the time series
+/// do not depend on which rank computed which partial sum.
 fn compute_global_sum<T>(i: impl Iterator<Item = T>) -> T
 where
-    T: iter::Sum<T> + Clone + Equivalence + 'static,
+    T: iter::Sum<T> + ReproducibleSum + Clone + Equivalence + 'static,
 {
     let mut comm = Communicator::new();
     let local_value: T = i.sum();
-    let value: T = comm.all_gather_sum(&local_value);
-    value
+    comm.all_gather_reproducible_sum(&local_value)
 }
 
 pub(super) fn num_particles_at_timestep_levels_system<C: Chemistry>(
@@ -171,6 +178,7 @@ pub(super) fn num_particles_at_timestep_levels_system<C: Chemistry>(
 ) {
     let solver = (*solver).as_mut().unwrap();
     let max_timestep = parameters.max_timestep;
+    let occupancy = solver.get_occupancy_global();
     writer.send(NumParticlesAtTimestepLevels(
         solver
             .timestep_state
@@ -180,7 +188,8 @@ pub(super) fn num_particles_at_timestep_levels_system<C: Chemistry>(
                 NumAtLevel {
                     level: level.0,
                     num,
-                    timestep: level.to_timestep(max_timestep),
+                    occupancy: occupancy[level.0],
+                    timestep: level.to_timestep(max_timestep, parameters.timestep_level_factor),
                 }
             })
             .collect(),
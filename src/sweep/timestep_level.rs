@@ -6,6 +6,9 @@ use mpi::traits::Equivalence;
 use crate::units::helpers::Float;
 use crate::units::Time;
 
+/// The timestep at level `n` is `max_timestep / factor^n`, where
+/// `factor` is the integer subdivision factor between two
+/// consecutive levels.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Equivalence, Hash)]
 pub struct TimestepLevel(pub usize);
 
@@ -26,11 +29,12 @@ impl SubAssign<usize> for TimestepLevel {
 impl TimestepLevel {
     pub fn from_max_timestep_and_desired_timestep(
         max_num_levels: usize,
+        factor: usize,
         max_timestep: Time,
         desired_timestep: Time,
     ) -> Self {
         let ratio = max_timestep / desired_timestep;
-        let level = ratio.log2().ceil().value() as usize;
+        let level = (ratio.log2().value() / (factor as Float).log2()).ceil() as usize;
         let result = level.clamp(0, max_num_levels - 1);
         Self(result)
     }
@@ -39,12 +43,12 @@ impl TimestepLevel {
         *self >= current_level
     }
 
-    pub fn to_timestep(&self, max_timestep: Time) -> Time {
-        max_timestep * self.as_factor()
+    pub fn to_timestep(&self, max_timestep: Time, factor: usize) -> Time {
+        max_timestep * self.as_factor(factor)
     }
 
-    pub fn as_factor(&self) -> f64 {
-        (0.5 as Float).powi(self.0 as i32)
+    pub fn as_factor(&self, factor: usize) -> f64 {
+        (1.0 / factor as Float).powi(self.0 as i32)
     }
 
     pub fn is_highest_timestep(&self) -> bool {
@@ -70,6 +74,7 @@ mod tests {
             assert_eq!(
                 TimestepLevel::from_max_timestep_and_desired_timestep(
                     max_num_levels,
+                    2,
                     Time::seconds(1.0),
                     Time::seconds(secs_desired)
                 ),
@@ -87,4 +92,26 @@ mod tests {
         check_level(5, 100.0, 0);
         check_level(5, 0.0, 4);
     }
+
+    #[test]
+    fn compute_timestep_level_with_factor_three() {
+        let check_level = |secs_desired, result| {
+            assert_eq!(
+                TimestepLevel::from_max_timestep_and_desired_timestep(
+                    5,
+                    3,
+                    Time::seconds(1.0),
+                    Time::seconds(secs_desired)
+                ),
+                TimestepLevel(result)
+            );
+        };
+        check_level(1.0, 0);
+        check_level(0.5, 1);
+        check_level(0.34, 1);
+        check_level(0.3, 2);
+        check_level(0.12, 2);
+        check_level(0.1, 3);
+        check_level(0.001, 4);
+    }
 }
@@ -5,25 +5,33 @@ use crate::units::Time;
 pub struct TimestepState {
     max_timestep: Time,
     max_num_timestep_levels: usize,
+    level_factor: usize,
     current_lowest_allowed: TimestepLevel,
-    first_update_at_highest_level_done: bool,
+    num_updates_at_lowest_allowed: usize,
 }
 
 impl TimestepState {
-    pub fn new(max_timestep: Time, max_num_timestep_levels: usize) -> Self {
+    pub fn new(max_timestep: Time, max_num_timestep_levels: usize, level_factor: usize) -> Self {
+        assert!(level_factor >= 2);
+        assert!(
+            level_factor
+                .checked_pow(max_num_timestep_levels as u32 - 1)
+                .is_some(),
+            "Too many timestep levels for a level factor of {level_factor}."
+        );
         Self {
             max_timestep,
             max_num_timestep_levels,
+            level_factor,
             current_lowest_allowed: TimestepLevel(max_num_timestep_levels - 1),
-            first_update_at_highest_level_done: false,
+            num_updates_at_lowest_allowed: 0,
         }
     }
 
     pub fn iter_levels_in_sweep_order(self) -> impl Iterator<Item = TimestepLevel> {
         let num = self.num_allowed_levels();
-        (0..(2usize.pow(num as u32 - 1))).map(move |i| {
-            self.current_lowest_allowed + self.lowest_active_from_iteration(i as u32, num as u32).0
-        })
+        (0..(self.level_factor.pow(num as u32 - 1)))
+            .map(move |i| self.current_lowest_allowed + self.lowest_active_from_iteration(i, num).0)
     }
 
     pub fn iter_allowed_levels(self) -> impl Iterator<Item = TimestepLevel> {
@@ -34,26 +42,42 @@ impl TimestepState {
         (0..self.max_num_timestep_levels).map(TimestepLevel)
     }
 
+    /// Allows all levels at once, as at the beginning of a timestep
+    /// in which all levels are synchronized.
+    pub fn allow_all_levels(&mut self) {
+        self.current_lowest_allowed = TimestepLevel(0);
+        self.num_updates_at_lowest_allowed = 0;
+    }
+
     pub fn advance_allowed_levels(&mut self) {
-        // Decrease the lowest allowed timestep level but only if
-        // we have already done one additional run with only the highest level.
-        // Doing this aligns the timesteps with the max_timestep cadence since
-        // Sum_{i=1}^n (Δt (1/2)^i) = Δt (1 - (1/2)^n)
-        if self.first_update_at_highest_level_done && self.current_lowest_allowed.0 > 0 {
+        // Decrease the lowest allowed timestep level once the elapsed
+        // time is a multiple of the timestep at the next lower level.
+        // With a level factor f, this requires f runs at the highest
+        // level and f - 1 runs at every following level, since
+        // f Δt f^(-n) + Sum_{i=1}^{n-1} (f - 1) Δt f^(-i) = Δt
+        self.num_updates_at_lowest_allowed += 1;
+        let num_required_updates =
+            if self.current_lowest_allowed.0 == self.max_num_timestep_levels - 1 {
+                self.level_factor
+            } else {
+                self.level_factor - 1
+            };
+        if self.num_updates_at_lowest_allowed >= num_required_updates
+            && self.current_lowest_allowed.0 > 0
+        {
             self.current_lowest_allowed -= 1;
-        }
-        if !self.first_update_at_highest_level_done {
-            self.first_update_at_highest_level_done = true;
+            self.num_updates_at_lowest_allowed = 0;
         }
     }
 
     pub fn timestep_at_level(self, level: TimestepLevel) -> Time {
-        level.to_timestep(self.max_timestep)
+        level.to_timestep(self.max_timestep, self.level_factor)
     }
 
     pub fn get_desired_level_from_desired_timestep(self, desired_timestep: Time) -> TimestepLevel {
         let mut level = TimestepLevel::from_max_timestep_and_desired_timestep(
             self.max_num_timestep_levels,
+            self.level_factor,
             self.max_timestep,
             desired_timestep,
         );
@@ -63,30 +87,48 @@ impl TimestepState {
         level
     }
 
+    /// Moves `current` towards `desired` by at most `max_change`
+    /// levels without going below the lowest allowed level.
+    pub fn limit_level_change(
+        self,
+        current: TimestepLevel,
+        desired: TimestepLevel,
+        max_change: usize,
+    ) -> TimestepLevel {
+        let lowest = TimestepLevel(current.0.saturating_sub(max_change));
+        let highest = TimestepLevel((current.0 + max_change).min(self.max_num_timestep_levels - 1));
+        desired
+            .min(highest)
+            .max(lowest)
+            .max(self.current_lowest_allowed)
+    }
+
     fn num_allowed_levels(&self) -> usize {
         self.max_num_timestep_levels - self.current_lowest_allowed.0
     }
 
-    fn lowest_active_from_iteration(&self, iteration: u32, num_levels: u32) -> TimestepLevel {
-        // find the lowest set bit in iteration.
-        assert!(self.max_num_timestep_levels < 32);
-        let first_bit = find_index_of_lowest_set_bit_in_int(iteration).unwrap_or(num_levels - 1);
-        TimestepLevel((num_levels - 1 - first_bit) as usize)
+    fn lowest_active_from_iteration(&self, iteration: usize, num_levels: usize) -> TimestepLevel {
+        // find the lowest non-zero digit of the iteration in base level_factor.
+        let first_digit = find_index_of_lowest_nonzero_digit(iteration, self.level_factor)
+            .unwrap_or(num_levels - 1);
+        TimestepLevel(num_levels - 1 - first_digit)
     }
 
     pub fn current_max_timestep(&self) -> Time {
-        self.max_timestep * self.current_lowest_allowed.as_factor()
+        self.max_timestep * self.current_lowest_allowed.as_factor(self.level_factor)
     }
 }
 
-fn find_index_of_lowest_set_bit_in_int(iteration: u32) -> Option<u32> {
-    for bit_num in 0..32 {
-        let mask = 1 << bit_num;
-        if iteration & mask > 0 {
-            return Some(bit_num);
-        }
+fn find_index_of_lowest_nonzero_digit(mut iteration: usize, base: usize) -> Option<usize> {
+    if iteration == 0 {
+        return None;
+    }
+    let mut digit_num = 0;
+    while iteration % base == 0 {
+        iteration /= base;
+        digit_num += 1;
     }
-    None
+    Some(digit_num)
 }
 
 #[cfg(test)]
@@ -97,7 +139,7 @@ mod tests {
 
     #[test]
     fn lowest_allowed() {
-        let mut state = TimestepState::new(Time::megayears(1.0), 5);
+        let mut state = TimestepState::new(Time::megayears(1.0), 5, 2);
         assert_eq!(state.current_lowest_allowed, TimestepLevel(4));
         state.advance_allowed_levels();
         assert_eq!(state.current_lowest_allowed, TimestepLevel(4));
@@ -113,9 +155,28 @@ mod tests {
         assert_eq!(state.current_lowest_allowed, TimestepLevel(0));
     }
 
+    #[test]
+    fn level_change_is_limited() {
+        let mut state = TimestepState::new(Time::megayears(1.0), 6, 2);
+        for _ in 0..6 {
+            state.advance_allowed_levels();
+        }
+        let limit = |current, desired, max_change| {
+            state
+                .limit_level_change(TimestepLevel(current), TimestepLevel(desired), max_change)
+                .0
+        };
+        assert_eq!(limit(3, 0, 1), 2);
+        assert_eq!(limit(3, 5, 1), 4);
+        assert_eq!(limit(3, 5, 2), 5);
+        assert_eq!(limit(3, 1, 2), 1);
+        assert_eq!(limit(3, 3, 0), 3);
+        assert_eq!(limit(3, 5, 10), 5);
+    }
+
     #[test]
     fn iter_levels_in_sweep_order_advances_properly() {
-        let mut state = TimestepState::new(Time::megayears(1.0), 5);
+        let mut state = TimestepState::new(Time::megayears(1.0), 5, 2);
         let mut get_next_levels_iter = || {
             let levels = state
                 .iter_levels_in_sweep_order()
@@ -135,4 +196,39 @@ mod tests {
             &[0, 4, 3, 4, 2, 4, 3, 4, 1, 4, 3, 4, 2, 4, 3, 4]
         );
     }
+
+    #[test]
+    fn iter_levels_in_sweep_order_with_level_factor_three() {
+        let mut state = TimestepState::new(Time::megayears(1.0), 3, 3);
+        let mut get_next_levels_iter = || {
+            let levels = state
+                .iter_levels_in_sweep_order()
+                .map(|level| level.0)
+                .collect::<Vec<_>>();
+            state.advance_allowed_levels();
+            levels
+        };
+        for _ in 0..3 {
+            assert_eq!(get_next_levels_iter(), &[2]);
+        }
+        for _ in 0..2 {
+            assert_eq!(get_next_levels_iter(), &[1, 2, 2]);
+        }
+        assert_eq!(get_next_levels_iter(), &[0, 2, 2, 1, 2, 2, 1, 2, 2]);
+        assert_eq!(get_next_levels_iter(), &[0, 2, 2, 1, 2, 2, 1, 2, 2]);
+    }
+
+    #[test]
+    fn advancing_levels_aligns_with_max_timestep() {
+        for level_factor in [2, 3, 4] {
+            let num_levels = 4;
+            let mut state = TimestepState::new(Time::seconds(1.0), num_levels, level_factor);
+            let mut time = Time::zero();
+            while state.current_lowest_allowed.0 > 0 {
+                time += state.current_max_timestep();
+                state.advance_allowed_levels();
+            }
+            assert!((time - Time::seconds(1.0)).abs() < Time::seconds(1e-10));
+        }
+    }
 }
@@ -1,14 +1,26 @@
+use std::env;
+use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 
 use bevy_ecs::prelude::*;
 use bevy_ecs::schedule::IntoSystemDescriptor;
 
+use crate::communication::BaseCommunicationPlugin;
+use crate::communication::MpiWorld;
+use crate::communication::SizedCommunicator;
+use crate::parameters::SimulationBox;
+use crate::parameters::SimulationParameters;
 use crate::prelude::Float;
 use crate::prelude::ParticleId;
 use crate::prelude::Simulation;
 use crate::units::Dimension;
+use crate::units::Length;
 use crate::units::Quantity;
+use crate::units::Time;
 
 // This is currently only used in tests with the local communication
 // but will very likely be used more, so prevent dead code warning
@@ -26,6 +38,27 @@ pub fn tests_path() -> PathBuf {
     Path::new(file!()).parent().unwrap().join("../tests")
 }
 
+/// Creates a new, empty directory in the temporary directory of the
+/// system, to which a single test can write its files. The name
+/// contains the process id and a counter, so that tests running in
+/// parallel, or in other processes at the same time, never write to
+/// the same files.
+pub fn temp_test_dir(name: &str) -> PathBuf {
+    static NUM_CREATED: AtomicUsize = AtomicUsize::new(0);
+    let index = NUM_CREATED.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join("subsweep_tests").join(format!(
+        "{}_{}_{}",
+        std::process::id(),
+        index,
+        name
+    ));
+    // Left over from an earlier process with the same id.
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir)
+        .unwrap_or_else(|e| panic!("Failed to create test directory {dir:?}: {e}"));
+    dir
+}
+
 pub fn assert_is_close<const U: Dimension>(x: Quantity<f64, U>, y: Quantity<f64, U>) {
     assert!(
         (x - y).abs().value_unchecked() < f64::EPSILON,
@@ -68,10 +101,127 @@ pub fn get_particles(n: i32, m: i32) -> Vec<crate::domain::LeafData> {
         .collect()
 }
 
-pub fn build_local_communication_sim_with_custom_logic(
-    _build: impl FnMut(&mut Simulation),
-    _check: impl FnMut(&mut Simulation),
-    _num_ranks: usize,
-) {
-    todo!()
+/// Builds a minimal simulation for tests. Every simulation
+/// contains the base communication resources, the simulation box
+/// and the simulation parameters, so that tests only need to add
+/// the plugins and systems they are actually interested in.
+///
+/// The simulation runs on the ranks of the MPI world the test binary
+/// was started with. Tests that should cover several rank counts
+/// start themselves via [`run_on_rank_counts`].
+pub struct TestSimulationBuilder {
+    parameter_file_contents: String,
+    simulation_box: SimulationBox,
+    final_time: Option<Time>,
+    setups: Vec<Box<dyn FnOnce(&mut Simulation)>>,
+}
+
+impl Default for TestSimulationBuilder {
+    fn default() -> Self {
+        Self {
+            parameter_file_contents: "{}".into(),
+            simulation_box: SimulationBox::cube_from_side_length(Length::meters(1.0)),
+            final_time: None,
+            setups: vec![],
+        }
+    }
+}
+
+impl TestSimulationBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parameter_file_contents(&mut self, contents: &str) -> &mut Self {
+        self.parameter_file_contents = contents.into();
+        self
+    }
+
+    pub fn simulation_box(&mut self, simulation_box: SimulationBox) -> &mut Self {
+        self.simulation_box = simulation_box;
+        self
+    }
+
+    pub fn final_time(&mut self, final_time: Time) -> &mut Self {
+        self.final_time = Some(final_time);
+        self
+    }
+
+    /// Adds a function which is called on the simulation after the
+    /// default resources have been inserted. Can be called multiple
+    /// times, in which case the functions are called in order.
+    pub fn setup(&mut self, setup: impl FnOnce(&mut Simulation) + 'static) -> &mut Self {
+        self.setups.push(Box::new(setup));
+        self
+    }
+
+    pub fn num_ranks() -> usize {
+        MpiWorld::<usize>::new().size()
+    }
+
+    pub fn build(&mut self) -> Simulation {
+        let world = MpiWorld::<usize>::new();
+        let mut sim = Simulation::test();
+        sim.add_parameter_file_contents(self.parameter_file_contents.clone())
+            .add_plugin(BaseCommunicationPlugin::new(world.size(), world.rank()))
+            .add_parameters_explicitly(self.simulation_box.clone())
+            .add_parameters_explicitly(SimulationParameters {
+                final_time: self.final_time,
+            });
+        for setup in self.setups.drain(..) {
+            setup(&mut sim);
+        }
+        sim
+    }
+
+    /// Builds the simulation and runs `num_steps` updates. The
+    /// startup systems are run as part of the first update.
+    pub fn run_n_steps(&mut self, num_steps: usize) -> Simulation {
+        let mut sim = self.build();
+        for _ in 0..num_steps {
+            sim.update();
+        }
+        sim
+    }
+}
+
+/// Set for the processes started by [`run_on_rank_counts`], so that
+/// they run the test body instead of starting new processes.
+const NUM_RANKS_ENV_VAR: &str = "SUBSWEEP_TEST_NUM_RANKS";
+
+/// Runs the test `name` (its full path, as given by
+/// `concat!(module_path!(), "::<test_name>")`) once for every
+/// number of ranks in `num_ranks`, by starting the test binary
+/// again via `mpirun` and filtering for exactly this test. Panics
+/// if any of the runs fails.
+///
+/// Returns true if the runs were started by this call, in which
+/// case the calling test should return immediately. Returns false
+/// if the test is already running as one of the ranks of an MPI
+/// job, in which case the caller should run the actual test body.
+pub fn run_on_rank_counts(name: &str, num_ranks: impl IntoIterator<Item = usize>) -> bool {
+    let started_by_mpirun = [NUM_RANKS_ENV_VAR, "OMPI_COMM_WORLD_SIZE", "PMI_SIZE"]
+        .iter()
+        .any(|var| env::var_os(var).is_some());
+    if started_by_mpirun {
+        return false;
+    }
+    let test_name = name
+        .strip_prefix(concat!(env!("CARGO_CRATE_NAME"), "::"))
+        .unwrap_or(name);
+    let exe = env::current_exe().unwrap();
+    for num_ranks in num_ranks {
+        let status = Command::new("mpirun")
+            .arg("-n")
+            .arg(num_ranks.to_string())
+            .arg(&exe)
+            .arg("--exact")
+            .arg(test_name)
+            .arg("--test-threads=1")
+            .env(NUM_RANKS_ENV_VAR, num_ranks.to_string())
+            .status()
+            .unwrap_or_else(|e| panic!("Failed to start mpirun for {test_name}: {e}"));
+        assert!(status.success(), "{test_name} failed on {num_ranks} ranks");
+    }
+    true
 }
@@ -25,16 +25,11 @@ use crate::voronoi::CellIndex;
 use crate::voronoi::DDimension;
 use crate::voronoi::Triangulation;
 
-/// Determines by how much all search radii should be larger than the
-/// radius of the circumcircle/sphere of the tetra, in order to prevent numerical
-/// problems due to floating point arithmetic.
-const SEARCH_SAFETY_FACTOR: f64 = 1.001;
+/// The default for [SearchRadiusConfig::safety_factor].
+pub(super) const SEARCH_SAFETY_FACTOR: f64 = 1.001;
 
-/// Determines by how much the search radii are increased between iterations.
-/// If the factor is too low, large tetras will take a long time
-/// to find all their haloes. If the factor is too high, we risk importing way
-/// too many haloes than are needed to construct the proper triangulation.
-const SEARCH_RADIUS_INCREASE_FACTOR: f64 = 1.6;
+/// The default for [SearchRadiusConfig::increase_factor].
+pub(super) const SEARCH_RADIUS_INCREASE_FACTOR: f64 = 1.6;
 
 /// By how much to decrease/increase the initial maximally allowed search radius below the
 /// "cartesian" cell size of side_length / num_particles_per_dimension
@@ -47,6 +42,30 @@ pub fn get_characteristic_length<D: Dimension>(max_side_length: f64, num_particl
     (max_side_length / num_particles_per_dim) * INITIAL_SEARCH_RADIUS_GUESS_FACTOR
 }
 
+/// Determines how the search radii of the undecided tetras evolve
+/// during the halo iteration.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchRadiusConfig {
+    /// Determines by how much all search radii should be larger than the
+    /// radius of the circumcircle/sphere of the tetra, in order to prevent numerical
+    /// problems due to floating point arithmetic.
+    pub safety_factor: Float,
+    /// Determines by how much the search radii are increased between iterations.
+    /// If the factor is too low, large tetras will take a long time
+    /// to find all their haloes. If the factor is too high, we risk importing way
+    /// too many haloes than are needed to construct the proper triangulation.
+    pub increase_factor: Float,
+}
+
+impl Default for SearchRadiusConfig {
+    fn default() -> Self {
+        Self {
+            safety_factor: SEARCH_SAFETY_FACTOR,
+            increase_factor: SEARCH_RADIUS_INCREASE_FACTOR,
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SearchData<D: Dimension> {
     pub point: Point<D>,
@@ -58,14 +77,15 @@ where
     Triangulation<D>: Delaunay<D>,
 {
     fn new(
-        undecided: &UndecidedTetraInfo,
-        circumcircle: &Circumcircle<D>,
+        undecided: &UndecidedTetraInfo<D>,
         triangulation: &Triangulation<D>,
         characteristic_length: f64,
+        config: &SearchRadiusConfig,
     ) -> Self {
-        let max_necessary_radius = circumcircle.radius * SEARCH_SAFETY_FACTOR;
+        let circumcircle = &undecided.circumcircle;
+        let max_necessary_radius = circumcircle.radius * config.safety_factor;
         let radius = match undecided.search_radius {
-            Some(radius) => (radius * SEARCH_RADIUS_INCREASE_FACTOR).min(max_necessary_radius),
+            Some(radius) => (radius * config.increase_factor).min(max_necessary_radius),
             None => max_necessary_radius.min(characteristic_length),
         };
         let point = if radius >= max_necessary_radius {
@@ -105,21 +125,19 @@ pub trait RadiusSearch<D: Dimension> {
     fn rank(&self) -> Rank;
 }
 
-struct UndecidedTetraInfo {
+/// The circumcircle is computed once, when the tetra becomes
+/// undecided. This is valid since the points of a tetra never change
+/// and tetra indices are not reused, so the info (and its circumcircle)
+/// is simply dropped once the tetra has been removed.
+struct UndecidedTetraInfo<D: DDimension> {
     tetra: TetraIndex,
     search_radius: Option<Float>,
+    circumcircle: Circumcircle<D>,
 }
 
-impl UndecidedTetraInfo {
-    fn search_radius_large_enough<D: DDimension>(&self, circumcircle: &Circumcircle<D>) -> bool {
-        self.search_radius.unwrap() >= circumcircle.radius * SEARCH_SAFETY_FACTOR
-    }
-
-    fn circumcircle<D: DDimension>(&self, triangulation: &Triangulation<D>) -> Circumcircle<D>
-    where
-        Triangulation<D>: Delaunay<D>,
-    {
-        triangulation.get_original_tetra_circumcircle(self.tetra)
+impl<D: DDimension> UndecidedTetraInfo<D> {
+    fn search_radius_large_enough(&self, config: &SearchRadiusConfig) -> bool {
+        self.search_radius.unwrap() >= self.circumcircle.radius * config.safety_factor
     }
 }
 
@@ -127,8 +145,9 @@ pub(super) struct HaloIteration<D: DDimension, F> {
     pub triangulation: Triangulation<D>,
     search: F,
     pub haloes: BiMap<CellIndex, PointIndex>,
-    undecided_tetras: Vec<UndecidedTetraInfo>,
+    undecided_tetras: Vec<UndecidedTetraInfo<D>>,
     characteristic_length: Float,
+    config: SearchRadiusConfig,
 }
 
 impl<D, F: RadiusSearch<D>> HaloIteration<D, F>
@@ -140,13 +159,19 @@ where
     SearchData<D>: Visualizable,
     Extent<Point<D>>: Visualizable,
 {
-    pub fn new(triangulation: Triangulation<D>, search: F, characteristic_length: Float) -> Self {
+    pub fn new(
+        triangulation: Triangulation<D>,
+        search: F,
+        characteristic_length: Float,
+        config: SearchRadiusConfig,
+    ) -> Self {
         let mut h = Self {
             triangulation,
             search,
             haloes: BiMap::default(),
             undecided_tetras: vec![],
             characteristic_length,
+            config,
         };
         h.set_all_tetras_undecided();
         h
@@ -211,15 +236,10 @@ where
             .drain(..chunk_size)
             .filter(|undecided| self.triangulation.tetras.contains(undecided.tetra))
             .map(|mut undecided| {
-                let circumcircle = undecided.circumcircle(&self.triangulation);
-                let search_data = SearchData::new(
-                    &undecided,
-                    &circumcircle,
-                    &self.triangulation,
-                    self.characteristic_length,
-                );
+                let search_data =
+                    SearchData::new(&undecided, &self.triangulation, self.characteristic_length);
                 undecided.search_radius = Some(search_data.radius);
-                let large_enough = undecided.search_radius_large_enough(&circumcircle);
+                let large_enough = undecided.search_radius_large_enough();
                 (search_data, (undecided, large_enough))
             })
             .unzip();
@@ -247,10 +267,11 @@ where
             .collect();
     }
 
-    fn get_undecided_tetra_info_for_new_tetra(&self, tetra: TetraIndex) -> UndecidedTetraInfo {
+    fn get_undecided_tetra_info_for_new_tetra(&self, tetra: TetraIndex) -> UndecidedTetraInfo<D> {
         UndecidedTetraInfo {
             tetra,
             search_radius: None,
+            circumcircle: self.triangulation.get_original_tetra_circumcircle(tetra),
         }
     }
 
@@ -274,6 +295,7 @@ mod tests {
 
     use super::RadiusSearch;
     use super::SearchData;
+    use super::SearchRadiusConfig;
     use super::SearchResults;
     use crate::communication::DataByRank;
     use crate::communication::Rank;
@@ -485,6 +507,7 @@ mod tests {
                 cache: HaloCache::default(),
             },
             None,
+            SearchRadiusConfig::default(),
         );
         let full_data = full_constructor.data;
         let sub_data = sub_constructor.data;
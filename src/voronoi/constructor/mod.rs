@@ -10,6 +10,7 @@ pub use parallel::ParallelVoronoiGridConstruction;
 use self::halo_iteration::HaloIteration;
 use self::halo_iteration::RadiusSearch;
 pub(super) use self::halo_iteration::SearchData;
+pub use self::halo_iteration::SearchRadiusConfig;
 use self::local::Local;
 use super::delaunay::PointIndex;
 use super::delaunay::TetraIndex;
@@ -18,6 +19,7 @@ use super::visualizer::Visualizable;
 use super::Cell;
 use super::CellIndex;
 use super::DCell;
+use super::DTetra;
 use super::Delaunay;
 use super::Triangulation;
 use super::TriangulationData;
@@ -53,6 +55,7 @@ where
         iter: impl Iterator<Item = (ParticleId, Point<D>)> + 'b,
         mut search: F,
         characteristic_length: Option<Float>,
+        config: SearchRadiusConfig,
     ) -> Self
     where
         F: RadiusSearch<D>,
@@ -73,7 +76,8 @@ where
             .map(|(id, p)| (ParticleType::Local(id), p))
             .collect();
         info!("Finished local Delaunay construction, starting halo iteration.");
-        let mut iteration = HaloIteration::new(triangulation, search, characteristic_length);
+        let mut iteration =
+            HaloIteration::new(triangulation, search, characteristic_length, config);
         iteration.run();
         map.extend(iteration.haloes);
         info!("Finished delaunay construction.",);
@@ -82,7 +86,7 @@ where
     }
 
     pub fn new(points: impl Iterator<Item = (ParticleId, Point<D>)>) -> Self {
-        Self::construct_from_iter(points, Local, None)
+        Self::construct_from_iter(points, Local, None, SearchRadiusConfig::default())
     }
 
     pub fn only_delaunay<'a>(iter: impl Iterator<Item = &'a Point<D>> + 'a) -> Triangulation<D>
@@ -120,6 +124,20 @@ where
             .triangulation
             .get_original_point(self.get_point_by_cell(cell_index).unwrap())
     }
+
+    /// The number of tetras which only consist of local points and
+    /// the number of tetras with at least one halo (or boundary)
+    /// point.
+    pub fn num_local_and_halo_tetras(&self) -> (usize, usize) {
+        let is_local =
+            |point| matches!(self.get_cell_by_point(point), Some(ParticleType::Local(_)));
+        let tetras = &self.data.triangulation.tetras;
+        let num_local = tetras
+            .iter()
+            .filter(|(_, tetra)| tetra.points().all(is_local))
+            .count();
+        (num_local, tetras.len() - num_local)
+    }
 }
 
 fn map_ptype(ptype: ParticleType, periodic: bool) -> ParticleType {
@@ -144,27 +162,41 @@ impl Constructor<ActiveDimension> {
                     self.data.get_particle_type(voronoi_cell.delaunay_point),
                     periodic,
                 );
+                (particle_type, to_sweep_cell(&voronoi_cell, periodic))
+            })
+            .collect()
+    }
+}
+
+impl VoronoiGrid<ActiveDimension> {
+    /// Converts the cells of the grid into cells of the sweep grid,
+    /// for example to run the sweep on a grid constructed on a
+    /// single rank. If `periodic` is false, faces to periodic
+    /// images are treated as boundaries.
+    pub fn sweep_grid(&self, periodic: bool) -> Vec<(CellIndex, grid::Cell)> {
+        self.cells
+            .iter()
+            .map(|voronoi_cell| (voronoi_cell.index, to_sweep_cell(voronoi_cell, periodic)))
+            .collect()
+    }
+}
+
+fn to_sweep_cell(voronoi_cell: &Cell<ActiveDimension>, periodic: bool) -> grid::Cell {
+    grid::Cell {
+        neighbours: voronoi_cell
+            .faces
+            .iter()
+            .map(|face| {
                 (
-                    particle_type,
-                    grid::Cell {
-                        neighbours: voronoi_cell
-                            .faces
-                            .iter()
-                            .map(|face| {
-                                (
-                                    crate::sweep::grid::Face {
-                                        area: FaceArea::new_unchecked(face.area),
-                                        normal: VecDimensionless::new_unchecked(face.normal),
-                                    },
-                                    map_ptype(face.connection, periodic),
-                                )
-                            })
-                            .collect(),
-                        size: Length::new_unchecked(voronoi_cell.size()),
-                        volume: Volume::new_unchecked(voronoi_cell.volume()),
+                    crate::sweep::grid::Face {
+                        area: FaceArea::new_unchecked(face.area),
+                        normal: VecDimensionless::new_unchecked(face.normal),
                     },
+                    map_ptype(face.connection, periodic),
                 )
             })
-            .collect()
+            .collect(),
+        size: Length::new_unchecked(voronoi_cell.size()),
+        volume: Volume::new_unchecked(voronoi_cell.volume()),
     }
 }
@@ -1,10 +1,14 @@
 use bevy_ecs::prelude::Commands;
 use bevy_ecs::prelude::Entity;
 use bevy_ecs::prelude::Res;
+use bevy_ecs::prelude::Resource;
 use derive_custom::subsweep_parameters;
 use derive_custom::Named;
 use log::debug;
 use log::warn;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
 
 use super::super::Constructor;
 use super::ParallelSearch;
@@ -14,6 +18,7 @@ use crate::dimension::ActiveDimension;
 use crate::domain::DecompositionState;
 use crate::domain::IdEntityMap;
 use crate::domain::QuadTree;
+use crate::hash_map::HashSet;
 use crate::parameters::SimulationBox;
 use crate::parameters::SweepParameters;
 use crate::particle::HaloParticle;
@@ -27,12 +32,86 @@ use crate::sweep::grid::ParticleType;
 use crate::units::Length;
 use crate::units::VecLength;
 use crate::voronoi::constructor::halo_cache::HaloCache;
+use crate::voronoi::constructor::halo_iteration::SearchRadiusConfig;
+use crate::voronoi::constructor::halo_iteration::SEARCH_RADIUS_INCREASE_FACTOR;
+use crate::voronoi::constructor::halo_iteration::SEARCH_SAFETY_FACTOR;
 use crate::voronoi::CellIndex;
 
+/// The number of tetras of the triangulation on this rank, as
+/// determined during the grid construction.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct NumTetras {
+    /// Tetras which only consist of local points.
+    pub local: usize,
+    /// Tetras with at least one halo or boundary point.
+    pub halo: usize,
+}
+
 #[subsweep_parameters("grid")]
 pub struct GridParameters {
     /// The initial search radius for halo iteration during grid construction.
     pub initial_search_radius: Option<Length>,
+    /// Check that every local particle received exactly one cell
+    /// after the grid construction. Intended for debugging.
+    #[serde(default)]
+    pub check_cell_count: bool,
+    /// By how much the search radii should be larger than the
+    /// circumcircle/sphere of a tetra, to guard against floating
+    /// point errors. Needs to be at least 1.
+    #[serde(
+        default = "default_search_safety_factor",
+        deserialize_with = "deserialize_search_safety_factor"
+    )]
+    pub search_safety_factor: f64,
+    /// By how much the search radii grow between halo iterations.
+    /// Large values can import far more haloes than necessary,
+    /// small values increase the number of iterations.
+    #[serde(
+        default = "default_search_radius_increase_factor",
+        deserialize_with = "deserialize_search_radius_increase_factor"
+    )]
+    pub search_radius_increase_factor: f64,
+}
+
+fn default_search_safety_factor() -> f64 {
+    SEARCH_SAFETY_FACTOR
+}
+
+fn default_search_radius_increase_factor() -> f64 {
+    SEARCH_RADIUS_INCREASE_FACTOR
+}
+
+fn deserialize_search_safety_factor<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<f64, D::Error> {
+    let factor = f64::deserialize(deserializer)?;
+    if factor < 1.0 {
+        return Err(D::Error::custom(format!(
+            "Invalid grid parameter search_safety_factor = {factor}: The factor needs to be at least 1, since a search radius smaller than the circumsphere of a tetra can miss haloes and result in a wrong triangulation."
+        )));
+    }
+    Ok(factor)
+}
+
+fn deserialize_search_radius_increase_factor<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<f64, D::Error> {
+    let factor = f64::deserialize(deserializer)?;
+    if factor <= 1.0 {
+        return Err(D::Error::custom(format!(
+            "Invalid grid parameter search_radius_increase_factor = {factor}: The factor needs to be larger than 1, otherwise the search radii never grow."
+        )));
+    }
+    Ok(factor)
+}
+
+impl From<&GridParameters> for SearchRadiusConfig {
+    fn from(parameters: &GridParameters) -> Self {
+        Self {
+            safety_factor: parameters.search_safety_factor,
+            increase_factor: parameters.search_radius_increase_factor,
+        }
+    }
 }
 
 #[derive(Named)]
@@ -64,6 +143,36 @@ fn warn_if_halo_fraction_too_high(
     }
 }
 
+/// Halo handling bugs can silently drop cells, which otherwise
+/// only shows up much later in the sweep.
+pub(super) fn check_every_particle_has_a_cell(
+    ids: impl Iterator<Item = ParticleId>,
+    cells: &[(ParticleType, Cell)],
+) {
+    let local_ids: HashSet<_> = cells
+        .iter()
+        .filter_map(|(type_, _)| match type_ {
+            ParticleType::Local(id) => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let num_local_cells = cells
+        .iter()
+        .filter(|(type_, _)| matches!(type_, ParticleType::Local(_)))
+        .count();
+    let mut num_particles = 0;
+    let missing: Vec<_> = ids
+        .inspect(|_| num_particles += 1)
+        .filter(|id| !local_ids.contains(id))
+        .collect();
+    if num_local_cells != num_particles || !missing.is_empty() {
+        panic!(
+            "Found {} local cells for {} local particles. Particles without a cell: {:?}",
+            num_local_cells, num_particles, missing
+        );
+    }
+}
+
 pub fn construct_grid_system(
     mut commands: Commands,
     particles: Particles<(Entity, &ParticleId, &Position)>,
@@ -88,6 +197,7 @@ pub fn construct_grid_system(
         grid_parameters
             .initial_search_radius
             .map(|r| r.value_unchecked()),
+        SearchRadiusConfig::from(&*grid_parameters),
     );
     let mut num_haloes = 0;
     let mut num_relevant_haloes = 0;
@@ -106,7 +216,13 @@ pub fn construct_grid_system(
                 commands.spawn((HaloParticle { rank }, Position(pos), id));
             }
         };
-    for (cell_index, cell) in cons.sweep_grid(sweep_parameters.periodic) {
+    let (local, halo) = cons.num_local_and_halo_tetras();
+    commands.insert_resource(NumTetras { local, halo });
+    let cells = cons.sweep_grid(sweep_parameters.periodic);
+    if grid_parameters.check_cell_count {
+        check_every_particle_has_a_cell(particles.iter().map(|(_, id, _)| *id), &cells);
+    }
+    for (cell_index, cell) in cells {
         match cell_index {
             ParticleType::Local(id) => {
                 num_local_particles += 1;
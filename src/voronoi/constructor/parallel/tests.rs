@@ -1,55 +1,117 @@
 use bevy_ecs::prelude::Res;
+use bevy_ecs::prelude::With;
 use bevy_ecs::system::Commands;
 
+use crate::communication::MpiWorld;
 use crate::components::Position;
 use crate::domain::DomainPlugin;
 use crate::parameters::SimulationBox;
-use crate::parameters::SimulationParameters;
+use crate::parameters::SweepParameters;
 use crate::prelude::Extent;
 use crate::prelude::LocalParticle;
+use crate::prelude::ParticleId;
 use crate::prelude::ThreeD;
 use crate::prelude::WorldRank;
 use crate::simulation::Simulation;
 use crate::simulation_plugin::StartupStages;
-use crate::test_utils::build_local_communication_sim_with_custom_logic;
+use crate::sweep::grid::Cell;
+use crate::sweep::grid::ParticleType;
+use crate::sweep::parameters::DirectionsSpecification;
+use crate::test_utils::run_on_rank_counts;
+use crate::test_utils::TestSimulationBuilder;
 use crate::units::Time;
 use crate::units::VecLength;
+use crate::voronoi::constructor::parallel::plugin::check_every_particle_has_a_cell;
+use crate::voronoi::constructor::parallel::plugin::GridParameters;
 use crate::voronoi::constructor::parallel::plugin::ParallelVoronoiGridConstruction;
+use crate::voronoi::constructor::Constructor;
 use crate::voronoi::test_utils::TestDimension;
+use crate::voronoi::NumTetras;
+
+const NUM_PARTICLES_PER_RANK: usize = 20;
 
 #[test]
-#[ignore]
 fn parallel_voronoi_construction() {
-    for num_ranks in 1..10 {
-        println!("Running on {}", num_ranks);
-        build_local_communication_sim_with_custom_logic(
-            build_sim,
-            |sim| {
-                sim.update();
-            },
-            num_ranks,
-        );
+    if run_on_rank_counts(
+        concat!(module_path!(), "::parallel_voronoi_construction"),
+        1..=3,
+    ) {
+        return;
     }
-}
-
-fn build_sim(sim: &mut Simulation) {
+    let num_ranks = TestSimulationBuilder::num_ranks();
+    // Every rank spawns its particles in a cube that is offset by
+    // 0.3 along the x axis from that of the previous rank.
     let box_ = SimulationBox::new(Extent::from_min_max(
         VecLength::meters(0.1, 0.1, 0.1),
-        VecLength::meters(0.4, 0.4, 0.4),
+        VecLength::meters(0.4 + 0.3 * (num_ranks - 1) as f64, 0.4, 0.4),
     ));
-    sim.add_parameter_file_contents("{}".into())
-        .add_plugin(ParallelVoronoiGridConstruction)
+    let mut sim = TestSimulationBuilder::new()
+        .simulation_box(box_)
+        .final_time(Time::zero())
+        .setup(build_sim)
+        .run_n_steps(1);
+    let world = sim.world();
+    let mut cells = world.query_filtered::<&Cell, With<LocalParticle>>();
+    // Particles can move between ranks during the domain
+    // decomposition, so only the global number of cells is known.
+    let num_cells_local = cells.iter(world).count();
+    let num_cells: usize = MpiWorld::<usize>::new().all_gather_sum(&num_cells_local);
+    assert_eq!(num_cells, NUM_PARTICLES_PER_RANK * num_ranks);
+    let num_tetras = *world.resource::<NumTetras>();
+    let num_local_tetras: usize = MpiWorld::<usize>::new().all_gather_sum(&num_tetras.local);
+    assert!(num_local_tetras > 0);
+}
+
+fn build_sim(sim: &mut Simulation) {
+    sim.add_plugin(ParallelVoronoiGridConstruction)
         .add_required_component::<Position>()
         .add_plugin(DomainPlugin)
-        .add_parameters_explicitly(box_)
-        .add_parameters_explicitly(SimulationParameters {
-            final_time: Some(Time::zero()),
-        })
+        .add_parameters_explicitly(SweepParameters::test(DirectionsSpecification::Num(1)))
         .add_startup_system_to_stage(StartupStages::ReadInput, spawn_particles_system);
 }
 
 fn spawn_particles_system(mut commands: Commands, rank: Res<WorldRank>) {
-    for p in ThreeD::get_example_point_set_num(20, **rank as usize) {
+    for p in ThreeD::get_example_point_set_num(NUM_PARTICLES_PER_RANK, **rank as usize) {
         commands.spawn((LocalParticle, Position(VecLength::new_unchecked(p))));
     }
 }
+
+#[test]
+#[should_panic(expected = "Particles without a cell: [ParticleId { index: 3, rank: 0 }]")]
+fn missing_cell_is_reported() {
+    let ids: Vec<_> = (0..NUM_PARTICLES_PER_RANK).map(ParticleId::test).collect();
+    let points = ThreeD::get_example_point_set_num(NUM_PARTICLES_PER_RANK, 0);
+    let cons = Constructor::<ThreeD>::new(ids.iter().copied().zip(points));
+    let mut cells = cons.sweep_grid(false);
+    check_every_particle_has_a_cell(ids.iter().copied(), &cells);
+    cells.retain(|(type_, _)| *type_ != ParticleType::Local(ParticleId::test(3)));
+    check_every_particle_has_a_cell(ids.iter().copied(), &cells);
+}
+
+#[test]
+fn invalid_search_radius_factors_are_rejected() {
+    let parse = |contents: &str| serde_yaml::from_str::<GridParameters>(contents);
+    let parameters =
+        parse("search_safety_factor: 1.5\nsearch_radius_increase_factor: 2.0").unwrap();
+    assert_eq!(parameters.search_safety_factor, 1.5);
+    assert_eq!(parameters.search_radius_increase_factor, 2.0);
+    assert!(parse("search_safety_factor: 0.0")
+        .unwrap_err()
+        .to_string()
+        .contains("search_safety_factor = 0"));
+    assert!(parse("search_safety_factor: 0.9")
+        .unwrap_err()
+        .to_string()
+        .contains("search_safety_factor = 0.9"));
+    assert_eq!(
+        parse("search_safety_factor: 1.0")
+            .unwrap()
+            .search_safety_factor,
+        1.0
+    );
+    assert!(parse("search_radius_increase_factor: 1.0")
+        .unwrap_err()
+        .to_string()
+        .contains("search_radius_increase_factor = 1"));
+    assert!(parse("search_radius_increase_factor: 0.5").is_err());
+}
@@ -71,6 +71,47 @@ struct FlipCheckStack {
     current_index: usize,
 }
 
+/// The reasons for which a set of points cannot be triangulated.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TriangulationError {
+    /// A triangulation in `D` dimensions requires at least `D + 1`
+    /// points.
+    TooFewPoints { num_points: usize, required: usize },
+    /// All points lie within a subspace of the given dimension,
+    /// i.e. they are all coincident, collinear or (in 3D) coplanar.
+    Degenerate { dimension: usize },
+    /// The points at these positions in the input are identical.
+    DuplicatePoints(usize, usize),
+}
+
+impl std::fmt::Display for TriangulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewPoints {
+                num_points,
+                required,
+            } => write!(
+                f,
+                "Cannot triangulate {num_points} points, at least {required} are required."
+            ),
+            Self::Degenerate { dimension } => write!(
+                f,
+                "Cannot triangulate points which all lie within a subspace of dimension {dimension}."
+            ),
+            Self::DuplicatePoints(i, j) => {
+                write!(f, "Cannot triangulate duplicate points (at positions {i} and {j}).")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TriangulationError {}
+
+/// Relative to the size of the extent of the points, below which
+/// the points are considered to lie within a lower-dimensional
+/// subspace.
+const DEGENERACY_TOLERANCE: Float = 1e-10;
+
 pub struct Circumcircle<D: DDimension> {
     pub center: Point<D>,
     pub radius: Float,
@@ -152,6 +193,29 @@ where
         Self::construct(points, &extent)
     }
 
+    /// Like [Triangulation::construct_from_iter], but returns an
+    /// error instead of panicking (or producing an invalid
+    /// triangulation) if the points are degenerate.
+    pub fn try_construct_from_iter<T: Hash + Clone + Eq>(
+        iter: impl Iterator<Item = (T, Point<D>)>,
+    ) -> Result<(Self, BiMap<T, PointIndex>), TriangulationError> {
+        let points: Vec<_> = iter.collect();
+        let required = D::NUM as usize + 1;
+        if points.len() < required {
+            return Err(TriangulationError::TooFewPoints {
+                num_points: points.len(),
+                required,
+            });
+        }
+        let extent = Extent::from_points(points.iter().map(|(_, p)| *p)).unwrap();
+        check_for_duplicates(points.iter().map(|(_, p)| *p), &extent)?;
+        let dimension = affine_dimension(points.iter().map(|(_, p)| *p), &extent);
+        if dimension < D::NUM as usize {
+            return Err(TriangulationError::Degenerate { dimension });
+        }
+        Ok(Self::construct(points, &extent))
+    }
+
     pub fn construct_no_key<'a>(points: impl Iterator<Item = &'a Point<D>> + 'a) -> Self
     where
         Point<D>: 'static,
@@ -332,6 +396,63 @@ where
     }
 }
 
+fn check_for_duplicates<P: IntoKey + DVector<Float = Float> + Copy>(
+    points: impl Iterator<Item = P>,
+    extent: &Extent<P>,
+) -> Result<(), TriangulationError> {
+    // Identical points have identical keys, so only points with
+    // the same key need to be compared.
+    let mut points: Vec<_> = points
+        .enumerate()
+        .map(|(i, p)| (p.into_key(extent), i, p))
+        .collect();
+    points.sort_by_key(|(key, i, _)| (*key, *i));
+    let mut start = 0;
+    while start < points.len() {
+        let key = points[start].0;
+        let end = start
+            + points[start..]
+                .iter()
+                .take_while(|(k, _, _)| *k == key)
+                .count();
+        let group = &points[start..end];
+        for (n, (_, i, p1)) in group.iter().enumerate() {
+            for (_, j, p2) in group[n + 1..].iter() {
+                if p1.distance_squared(*p2) == 0.0 {
+                    return Err(TriangulationError::DuplicatePoints(*i, *j));
+                }
+            }
+        }
+        start = end;
+    }
+    Ok(())
+}
+
+/// The dimension of the smallest affine subspace containing all
+/// points, determined by Gram-Schmidt orthogonalization.
+fn affine_dimension<P: DVector<Float = Float> + Copy>(
+    mut points: impl Iterator<Item = P>,
+    extent: &Extent<P>,
+) -> usize {
+    let origin = match points.next() {
+        Some(origin) => origin,
+        None => return 0,
+    };
+    let tolerance = DEGENERACY_TOLERANCE * extent.max_side_length();
+    let mut basis: Vec<P> = vec![];
+    for p in points {
+        let mut v = p - origin;
+        for b in basis.iter() {
+            v = v - *b * v.dot(*b);
+        }
+        let norm = v.dot(v).sqrt();
+        if norm > tolerance {
+            basis.push(v / norm);
+        }
+    }
+    basis.len()
+}
+
 #[cfg(test)]
 #[generic_tests::define]
 pub(super) mod tests {
@@ -339,8 +460,10 @@ pub(super) mod tests {
     use super::dimension::DFace;
     use super::dimension::DTetra;
     use super::Delaunay;
+    use super::Point;
     use super::PointKind;
     use super::Triangulation;
+    use super::TriangulationError;
     use crate::dimension::ThreeD;
     use crate::dimension::TwoD;
     use crate::extent::Extent;
@@ -368,6 +491,39 @@ pub(super) mod tests {
         check(&triangulation, points.len());
     }
 
+    #[test]
+    fn degenerate_input_is_reported<D>()
+    where
+        D: DDimension + TestDimension,
+        Triangulation<D>: Delaunay<D>,
+    {
+        let points = D::get_example_point_set_num(100, 0);
+        let try_construct = |points: Vec<Point<D>>| {
+            Triangulation::<D>::try_construct_from_iter(points.into_iter().enumerate()).map(|_| ())
+        };
+        assert_eq!(try_construct(points.clone()), Ok(()));
+        assert_eq!(
+            try_construct(points[..D::num()].to_vec()),
+            Err(TriangulationError::TooFewPoints {
+                num_points: D::num(),
+                required: D::num() + 1
+            })
+        );
+        let mut with_duplicate = points.clone();
+        with_duplicate.push(points[3]);
+        assert_eq!(
+            try_construct(with_duplicate),
+            Err(TriangulationError::DuplicatePoints(3, points.len()))
+        );
+        let collinear = (0..10)
+            .map(|i| points[0] + (points[1] - points[0]) * i as f64)
+            .collect();
+        assert_eq!(
+            try_construct(collinear),
+            Err(TriangulationError::Degenerate { dimension: 1 })
+        );
+    }
+
     #[test]
     fn correct_number_of_objects<D>()
     where
@@ -10,22 +10,33 @@ pub mod triangulation_data;
 mod utils;
 pub mod visualizer;
 
+use std::hash::Hash;
+
 use bevy_ecs::prelude::Resource;
 pub use cell::Cell;
 pub use cell::DCell;
 pub use constructor::parallel::plugin::construct_grid_system;
+pub use constructor::parallel::plugin::NumTetras;
 pub use constructor::Constructor;
 pub use delaunay::dimension::DDimension;
 pub use delaunay::dimension::DTetra;
 use delaunay::Delaunay;
 use delaunay::PointIndex;
 pub use delaunay::Triangulation;
+pub use delaunay::TriangulationError;
 pub use math::traits::DVector;
 pub use math::traits::MinMax;
 pub use primitives::Point2d;
 pub use primitives::Point3d;
 pub use triangulation_data::TriangulationData;
 
+use self::constructor::SearchData;
+use self::visualizer::Visualizable;
+use crate::dimension::ActiveWrapType;
+use crate::dimension::Point;
+use crate::extent::Extent;
+use crate::hash_map::HashMap;
+use crate::prelude::ParticleId;
 use crate::sweep::grid::ParticleType;
 
 pub type CellIndex = ParticleType;
@@ -45,6 +56,69 @@ where
     }
 }
 
+impl<D> VoronoiGrid<D>
+where
+    D: DDimension<WrapType = ActiveWrapType>,
+    Triangulation<D>: Delaunay<D>,
+    Cell<D>: DCell<Dimension = D>,
+    SearchData<D>: Visualizable,
+    Extent<Point<D>>: Visualizable,
+{
+    /// Constructs the grid of an arbitrary set of labeled points on
+    /// this rank only, i.e. without any communication or halo
+    /// particles. The cells of the resulting grid can be looked up
+    /// by their label.
+    pub fn from_points<L: Hash + Eq + Clone>(
+        points: impl IntoIterator<Item = (L, Point<D>)>,
+    ) -> LabeledVoronoiGrid<L, D> {
+        let (labels, points): (Vec<_>, Vec<_>) = points.into_iter().unzip();
+        let ids = (0..labels.len()).map(|index| ParticleId {
+            index: index as u32,
+            rank: 0,
+        });
+        let grid = Constructor::new(ids.zip(points)).voronoi();
+        let cells_by_label = grid
+            .cells
+            .iter()
+            .enumerate()
+            .filter_map(|(cell_index, cell)| match cell.index {
+                ParticleType::Local(id) => Some((labels[id.index as usize].clone(), cell_index)),
+                _ => None,
+            })
+            .collect();
+        LabeledVoronoiGrid {
+            grid,
+            labels,
+            cells_by_label,
+        }
+    }
+}
+
+/// A [VoronoiGrid] constructed from labeled points via
+/// [VoronoiGrid::from_points].
+pub struct LabeledVoronoiGrid<L, D: DDimension> {
+    pub grid: VoronoiGrid<D>,
+    labels: Vec<L>,
+    cells_by_label: HashMap<L, usize>,
+}
+
+impl<L: Hash + Eq, D: DDimension> LabeledVoronoiGrid<L, D> {
+    pub fn get(&self, label: &L) -> Option<&Cell<D>> {
+        self.cells_by_label
+            .get(label)
+            .map(|cell_index| &self.grid.cells[*cell_index])
+    }
+
+    /// Returns the label of the cell that the given face
+    /// connection or cell index refers to, if any.
+    pub fn get_label(&self, index: CellIndex) -> Option<&L> {
+        match index {
+            ParticleType::Local(id) => self.labels.get(id.index as usize),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 #[generic_tests::define]
 mod tests {
@@ -242,4 +316,61 @@ mod quantitative_tests {
             }
         }
     }
+
+    #[cfg(feature = "3d")]
+    #[test]
+    fn sweep_grid_from_voronoi_grid() {
+        use crate::dimension::ThreeD;
+        use crate::voronoi::primitives::Point3d;
+        let points = vec![
+            (ParticleId::test(0), Point3d::new(0.0, 0.0, 0.0)),
+            (ParticleId::test(1), Point3d::new(0.6, 0.1, 0.1)),
+            (ParticleId::test(2), Point3d::new(0.1, 0.5, 0.1)),
+            (ParticleId::test(3), Point3d::new(0.1, 0.1, 0.4)),
+            (ParticleId::test(4), Point3d::new(0.1, 0.1, 0.1)),
+        ];
+        let grid: VoronoiGrid<ThreeD> = Constructor::new(points.into_iter()).voronoi();
+        let cells = grid.sweep_grid(false);
+        assert_eq!(cells.len(), 5);
+        for ((index, cell), voronoi_cell) in cells.iter().zip(grid.cells.iter()) {
+            assert!(*index == voronoi_cell.index);
+            assert_float_is_close(cell.volume.value_unchecked(), voronoi_cell.volume());
+            assert_eq!(cell.neighbours.len(), voronoi_cell.faces.len());
+        }
+        let (_, center) = cells
+            .iter()
+            .find(|(index, _)| *index == ParticleType::Local(ParticleId::test(4)))
+            .unwrap();
+        assert_float_is_close(center.volume.value_unchecked(), 0.0703125);
+        assert!(center
+            .neighbours
+            .iter()
+            .all(|(_, neighbour)| matches!(neighbour, ParticleType::Local(_))));
+    }
+
+    #[cfg(feature = "3d")]
+    #[test]
+    fn grid_from_labeled_points() {
+        use crate::dimension::ThreeD;
+        use crate::voronoi::primitives::Point3d;
+        let points = vec![
+            ("origin", Point3d::new(0.0, 0.0, 0.0)),
+            ("x", Point3d::new(0.6, 0.1, 0.1)),
+            ("y", Point3d::new(0.1, 0.5, 0.1)),
+            ("z", Point3d::new(0.1, 0.1, 0.4)),
+            ("center", Point3d::new(0.1, 0.1, 0.1)),
+        ];
+        let grid = VoronoiGrid::<ThreeD>::from_points(points);
+        assert_eq!(grid.grid.cells.len(), 5);
+        let cell = grid.get(&"center").unwrap();
+        assert_float_is_close(cell.volume(), 0.0703125);
+        let mut neighbours: Vec<_> = cell
+            .faces
+            .iter()
+            .map(|face| *grid.get_label(face.connection).unwrap())
+            .collect();
+        neighbours.sort();
+        assert_eq!(neighbours, ["origin", "x", "y", "z"]);
+        assert!(grid.get(&"w").is_none());
+    }
 }
@@ -70,6 +70,22 @@ where
             .map(|p| Cell::<D>::new(self, p))
     }
 
+    /// The natural neighbours of the point, i.e. all points which
+    /// share a Delaunay edge with it. The points of the
+    /// all-encompassing initial tetra are not included.
+    pub fn natural_neighbours(&self, p: PointIndex) -> Vec<PointIndex> {
+        let mut neighbours: Vec<_> = self.point_to_tetras_map[&p]
+            .iter()
+            .flat_map(|tetra| self.triangulation.tetras[*tetra].points())
+            .filter(|neighbour| {
+                *neighbour != p && self.triangulation.point_kinds[neighbour] != PointKind::Outer
+            })
+            .collect();
+        neighbours.sort();
+        neighbours.dedup();
+        neighbours
+    }
+
     pub fn get_particle_type(&self, p: PointIndex) -> ParticleType {
         if self.triangulation.point_kinds[&p] == PointKind::Outer {
             return ParticleType::Boundary;
@@ -96,3 +112,38 @@ where
     }
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TriangulationData;
+    use crate::dimension::TwoD;
+    use crate::hash_map::BiMap;
+    use crate::prelude::ParticleId;
+    use crate::sweep::grid::ParticleType;
+    use crate::voronoi::Point2d;
+    use crate::voronoi::Triangulation;
+
+    #[test]
+    fn natural_neighbours_on_triangular_lattice() {
+        let n = 10;
+        let points = (0..n).flat_map(|i| {
+            (0..n).map(move |j| {
+                let x = i as f64 + 0.5 * (j % 2) as f64;
+                let y = j as f64 * 3.0f64.sqrt() / 2.0;
+                ((i, j), Point2d::new(x, y))
+            })
+        });
+        let (triangulation, indices) = Triangulation::<TwoD>::construct_from_iter(points);
+        let map: BiMap<_, _> = indices
+            .iter()
+            .map(|((i, j), p)| (ParticleType::Local(ParticleId::test(i * n + j)), *p))
+            .collect();
+        let data = TriangulationData::from_triangulation_and_map(triangulation, map);
+        for ((i, j), p) in indices.iter() {
+            let is_interior = (1..n - 1).contains(i) && (1..n - 1).contains(j);
+            if is_interior {
+                assert_eq!(data.natural_neighbours(*p).len(), 6);
+            }
+        }
+    }
+}